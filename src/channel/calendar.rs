@@ -0,0 +1,603 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone, Utc};
+use icalendar::{
+    Calendar, CalendarComponent, CalendarDateTime, Component, DatePerhapsTime, EventLike,
+    EventStatus, Property,
+};
+use rrule::{RRule, RRuleSet, Tz, Unvalidated};
+use tokio::sync::mpsc;
+
+use super::{Channel, IncomingMessage};
+use crate::store::Store;
+
+/// Upper bound on instances expanded per event and check, against runaway
+/// rules.
+const MAX_INSTANCES: u16 = 1000;
+
+/// What to read and how early to fire, resolved from the channel config.
+#[derive(Clone)]
+pub struct CalendarSettings {
+    /// `.ics` files, or directories of them (vdirs).
+    pub paths: Vec<PathBuf>,
+    /// How long before an event starts the message is sent.
+    pub lead: Duration,
+    pub poll_interval: Duration,
+    pub db_path: PathBuf,
+}
+
+/// Local time in a zone; unknown zone names (Windows ones and the like)
+/// and floating times use the local zone.
+fn zoned(naive: NaiveDateTime, tzid: Option<&str>) -> Option<DateTime<Tz>> {
+    let tz = match tzid.map(str::parse::<chrono_tz::Tz>) {
+        Some(Ok(tz)) => Tz::Tz(tz),
+        _ => Tz::LOCAL,
+    };
+    tz.from_local_datetime(&naive).earliest()
+}
+
+fn resolve(value: &CalendarDateTime) -> Option<DateTime<Tz>> {
+    match value {
+        CalendarDateTime::Utc(utc) => Some(utc.with_timezone(&Tz::UTC)),
+        CalendarDateTime::Floating(naive) => zoned(*naive, None),
+        CalendarDateTime::WithTimezone { date_time, tzid } => zoned(*date_time, Some(tzid)),
+    }
+}
+
+/// A `DTSTART`-like value and whether it's a plain date.
+fn resolve_date(value: &DatePerhapsTime) -> Option<(DateTime<Tz>, bool)> {
+    match value {
+        DatePerhapsTime::DateTime(dt) => Some((resolve(dt)?, false)),
+        DatePerhapsTime::Date(date) => Some((zoned(date.and_hms_opt(0, 0, 0)?, None)?, true)),
+    }
+}
+
+/// All instants of an `EXDATE` property, which may list several.
+fn exdates(prop: &Property) -> Vec<DateTime<Tz>> {
+    let tzid = prop.params().get("TZID").map(|p| p.value());
+    prop.value()
+        .split(',')
+        .filter_map(|v| match tzid {
+            Some(tzid) => {
+                let naive = NaiveDateTime::parse_from_str(v.trim(), "%Y%m%dT%H%M%S").ok()?;
+                zoned(naive, Some(tzid))
+            }
+            None => resolve(&v.trim().parse().ok()?),
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone)]
+struct Event {
+    uid: String,
+    summary: String,
+    description: String,
+    location: String,
+    organizer: String,
+    attendees: Vec<String>,
+    start: DateTime<Tz>,
+    all_day: bool,
+    duration: chrono::Duration,
+    rrule: Option<String>,
+    exdates: Vec<DateTime<Tz>>,
+    /// Set on an edited instance of a recurring event.
+    recurrence_id: Option<DateTime<Utc>>,
+    cancelled: bool,
+}
+
+/// `CN=Jane Doe:mailto:jane@example.org` → `Jane Doe <jane@example.org>`
+fn format_person(prop: &Property) -> String {
+    let value = prop.value();
+    let email = value
+        .strip_prefix("mailto:")
+        .or_else(|| value.strip_prefix("MAILTO:"))
+        .unwrap_or(value);
+    match prop.params().get("CN").map(|p| p.value()) {
+        Some(name) if !name.is_empty() => format!("{name} <{email}>"),
+        _ => email.to_string(),
+    }
+}
+
+fn parse_event(event: &icalendar::Event) -> Option<Event> {
+    let text = |value: Option<&str>| value.unwrap_or_default().to_string();
+    let multi = |name: &str| event.multi_properties().get(name).into_iter().flatten();
+
+    let (start, all_day) = resolve_date(&event.get_start()?)?;
+    let duration = match (event.get_end(), event.property_value("DURATION")) {
+        (Some(end), _) => resolve_date(&end).map(|(end, _)| end - start),
+        (None, Some(d)) => iso8601::duration(d)
+            .ok()
+            .and_then(|d| chrono::Duration::from_std(d.into()).ok()),
+        (None, None) => None,
+    }
+    .unwrap_or_else(chrono::Duration::zero);
+
+    Some(Event {
+        uid: text(event.get_uid()),
+        summary: text(event.get_summary()),
+        description: text(event.get_description()),
+        location: text(event.get_location()),
+        organizer: event
+            .properties()
+            .get("ORGANIZER")
+            .map(format_person)
+            .unwrap_or_default(),
+        attendees: multi("ATTENDEE").map(format_person).collect(),
+        start,
+        all_day,
+        duration,
+        rrule: event.property_value("RRULE").map(String::from),
+        exdates: multi("EXDATE").flat_map(exdates).collect(),
+        recurrence_id: event
+            .get_recurrence_id()
+            .and_then(|r| resolve_date(&r))
+            .map(|(r, _)| r.with_timezone(&Utc)),
+        cancelled: event.get_status() == Some(EventStatus::Cancelled),
+    })
+}
+
+/// All VEVENTs in an iCalendar document.
+fn parse_ics(text: &str) -> Result<Vec<Event>, String> {
+    let calendar: Calendar = text.parse()?;
+    Ok(calendar
+        .components
+        .iter()
+        .filter_map(|c| match c {
+            CalendarComponent::Event(e) => parse_event(e),
+            _ => None,
+        })
+        .collect())
+}
+
+/// Instance starts of `event` in `(from, to]`. A rule `rrule` can't handle
+/// leaves just the first instance.
+fn occurrences(event: &Event, from: DateTime<Utc>, to: DateTime<Utc>) -> Vec<DateTime<Utc>> {
+    let in_window = |t: &DateTime<Utc>| *t > from && *t <= to;
+    let first = || {
+        let start = event.start.with_timezone(&Utc);
+        let excluded = event.exdates.iter().any(|x| *x == start);
+        Some(start)
+            .filter(|s| in_window(s) && !excluded)
+            .into_iter()
+            .collect()
+    };
+
+    let Some(value) = event.rrule.as_deref() else {
+        return first();
+    };
+    let rule = match value
+        .parse::<RRule<Unvalidated>>()
+        .and_then(|r| r.validate(event.start))
+    {
+        Ok(rule) => rule,
+        Err(e) => {
+            tracing::debug!(
+                "unsupported RRULE '{}' on '{}': {}",
+                value,
+                event.summary,
+                e
+            );
+            return first();
+        }
+    };
+
+    let tz = event.start.timezone();
+    RRuleSet::new(event.start)
+        .rrule(rule)
+        .set_exdates(event.exdates.clone())
+        .after(from.with_timezone(&tz))
+        .before(to.with_timezone(&tz))
+        .all(MAX_INSTANCES)
+        .dates
+        .into_iter()
+        .map(|t| t.with_timezone(&Utc))
+        .filter(in_window)
+        .collect()
+}
+
+/// `.ics` files under `path`, descending into vdir collections.
+fn ics_files(path: &Path, out: &mut Vec<PathBuf>) {
+    if path.is_file() {
+        out.push(path.to_path_buf());
+        return;
+    }
+    let Ok(entries) = std::fs::read_dir(path) else {
+        tracing::warn!("cannot read calendar {}", path.display());
+        return;
+    };
+    for entry in entries.filter_map(|e| e.ok()) {
+        let p = entry.path();
+        if p.is_dir() {
+            ics_files(&p, out);
+        } else if p.extension().is_some_and(|e| e.eq_ignore_ascii_case("ics")) {
+            out.push(p);
+        }
+    }
+}
+
+fn format_local(t: DateTime<Utc>) -> String {
+    t.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string()
+}
+
+fn to_incoming(event: &Event, start: DateTime<Utc>) -> IncomingMessage {
+    let mut meta = HashMap::new();
+    meta.insert("uid".to_string(), event.uid.clone());
+    meta.insert("title".to_string(), event.summary.clone());
+    meta.insert("start".to_string(), format_local(start));
+    meta.insert("end".to_string(), format_local(start + event.duration));
+    meta.insert("location".to_string(), event.location.clone());
+    meta.insert("description".to_string(), event.description.clone());
+    meta.insert("organizer".to_string(), event.organizer.clone());
+    meta.insert("attendees".to_string(), event.attendees.join(", "));
+    IncomingMessage {
+        channel: "calendar".to_string(),
+        sender: event.uid.clone(),
+        text: event.summary.clone(),
+        meta,
+        ..Default::default()
+    }
+}
+
+/// Timed events starting within `(now, now + lead]`, soonest first.
+/// Edited instances replace the occurrence they override; all-day and
+/// cancelled events never fire.
+fn upcoming(
+    events: &[Event],
+    now: DateTime<Utc>,
+    lead: chrono::Duration,
+) -> Vec<(Event, DateTime<Utc>)> {
+    let overridden: HashSet<(String, DateTime<Utc>)> = events
+        .iter()
+        .filter_map(|e| e.recurrence_id.map(|r| (e.uid.clone(), r)))
+        .collect();
+
+    let mut due: Vec<(Event, DateTime<Utc>)> = events
+        .iter()
+        .filter(|e| !e.all_day && !e.cancelled)
+        .flat_map(|e| {
+            occurrences(e, now, now + lead)
+                .into_iter()
+                .filter(|start| {
+                    e.recurrence_id.is_some() || !overridden.contains(&(e.uid.clone(), *start))
+                })
+                .map(|start| (e.clone(), start))
+                .collect::<Vec<_>>()
+        })
+        .collect();
+    due.sort_by_key(|(_, start)| *start);
+    due
+}
+
+fn load(paths: &[PathBuf]) -> Vec<Event> {
+    let mut files = Vec::new();
+    for path in paths {
+        ics_files(path, &mut files);
+    }
+    files
+        .iter()
+        .filter_map(|f| {
+            let events = std::fs::read_to_string(f)
+                .map_err(|e| e.to_string())
+                .and_then(|text| parse_ics(&text));
+            match events {
+                Ok(events) => Some(events),
+                Err(e) => {
+                    tracing::warn!("cannot read {}: {e}", f.display());
+                    None
+                }
+            }
+        })
+        .flatten()
+        .collect()
+}
+
+/// Events entering the lead window that haven't fired yet, each with the
+/// start to record once it's sent. Reads files and the store, so it runs off
+/// the async threads.
+fn due(
+    settings: &CalendarSettings,
+    now: DateTime<Utc>,
+) -> crate::error::Result<Vec<(IncomingMessage, String)>> {
+    let lead = chrono::Duration::from_std(settings.lead).unwrap_or_default();
+    let store = Store::open(&settings.db_path)?;
+    let mut messages = Vec::new();
+    for (event, start) in upcoming(&load(&settings.paths), now, lead) {
+        let key = start.to_rfc3339();
+        if !store.has_calendar_fire(&event.uid, &key)? {
+            messages.push((to_incoming(&event, start), key));
+        }
+    }
+    Ok(messages)
+}
+
+/// Fires a message `lead` before each event in local calendars.
+pub struct CalendarChannel {
+    settings: CalendarSettings,
+}
+
+impl CalendarChannel {
+    pub fn new(settings: CalendarSettings) -> Self {
+        Self { settings }
+    }
+}
+
+#[async_trait::async_trait]
+impl Channel for CalendarChannel {
+    async fn start(&self, tx: mpsc::Sender<IncomingMessage>) -> crate::error::Result<()> {
+        for path in &self.settings.paths {
+            tracing::info!("calendar {}", path.display());
+        }
+        let mut interval = tokio::time::interval(self.settings.poll_interval);
+        loop {
+            interval.tick().await;
+            let settings = self.settings.clone();
+            let checked = tokio::task::spawn_blocking(move || due(&settings, Utc::now()))
+                .await
+                .unwrap_or_else(|e| Err(crate::error::Error::Channel(e.to_string())));
+            let due = match checked {
+                Ok(due) => due,
+                Err(e) => {
+                    tracing::error!("calendar check failed: {e}");
+                    continue;
+                }
+            };
+            for (msg, start) in due {
+                tracing::info!("calendar: {} at {}", msg.text, msg.meta["start"]);
+                let uid = msg.sender.clone();
+                if tx.send(msg).await.is_err() {
+                    return Ok(()); // receiver dropped
+                }
+                // Only now, so an event isn't lost if the send never happened
+                let db_path = self.settings.db_path.clone();
+                let recorded = tokio::task::spawn_blocking(move || {
+                    Store::open(&db_path)?.record_calendar_fire(&uid, &start)
+                })
+                .await
+                .unwrap_or_else(|e| Err(crate::error::Error::Channel(e.to_string())));
+                if let Err(e) = recorded {
+                    tracing::warn!("calendar: cannot record fire: {e}");
+                }
+            }
+        }
+    }
+
+    async fn send(&self, to: &str, _message: &str) -> crate::error::Result<()> {
+        tracing::debug!("calendar event {to} can't be replied to, dropping reply");
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        "calendar"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    fn event(body: &str) -> Event {
+        let ics = format!("BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nUID:u1\r\nSUMMARY:Standup\r\n{body}END:VEVENT\r\nEND:VCALENDAR\r\n");
+        parse_ics(&ics).unwrap().remove(0)
+    }
+
+    fn starts(e: &Event, from: &str, to: &str) -> Vec<String> {
+        occurrences(e, utc(from), utc(to))
+            .iter()
+            .map(|t| t.to_rfc3339())
+            .collect()
+    }
+
+    #[test]
+    fn test_calendar_channel_name() {
+        let ch = CalendarChannel::new(CalendarSettings {
+            paths: vec![],
+            lead: Duration::from_secs(900),
+            poll_interval: Duration::from_secs(60),
+            db_path: PathBuf::new(),
+        });
+        assert_eq!(ch.name(), "calendar");
+    }
+
+    #[test]
+    fn test_parse_event_fields() {
+        let ics = "BEGIN:VCALENDAR\r\n\
+BEGIN:VEVENT\r\n\
+UID:abc@example.org\r\n\
+SUMMARY:Quarterly review\\, Q1\r\n\
+DTSTART;TZID=Europe/Berlin:20260105T100000\r\n\
+DTEND;TZID=Europe/Berlin:20260105T113000\r\n\
+LOCATION:Room 4\r\n\
+DESCRIPTION:Agenda:\\n- numbers\\n- hiring and a long line that is\r\n  folded\r\n\
+ORGANIZER;CN=Jane Doe:mailto:jane@example.org\r\n\
+ATTENDEE;CN=\"Smith, Bob\";ROLE=REQ-PARTICIPANT:mailto:bob@example.org\r\n\
+ATTENDEE:mailto:eve@example.org\r\n\
+BEGIN:VALARM\r\n\
+DESCRIPTION:Reminder\r\n\
+END:VALARM\r\n\
+END:VEVENT\r\n\
+END:VCALENDAR\r\n";
+        let events = parse_ics(ics).unwrap();
+        assert_eq!(events.len(), 1);
+        let e = &events[0];
+        assert_eq!(e.summary, "Quarterly review, Q1");
+        assert_eq!(e.location, "Room 4");
+        assert_eq!(
+            e.description,
+            "Agenda:\n- numbers\n- hiring and a long line that is folded"
+        );
+        assert_eq!(e.organizer, "Jane Doe <jane@example.org>");
+        assert_eq!(
+            e.attendees,
+            vec!["Smith, Bob <bob@example.org>", "eve@example.org"]
+        );
+        assert_eq!(e.start.timezone().name(), "Europe/Berlin");
+        assert_eq!(e.start.with_timezone(&Utc), utc("2026-01-05T09:00:00Z"));
+        assert_eq!(e.duration, chrono::Duration::minutes(90));
+    }
+
+    #[test]
+    fn test_single_event_window() {
+        let e = event("DTSTART:20260105T090000Z\r\nDURATION:PT30M\r\n");
+        assert_eq!(e.duration, chrono::Duration::minutes(30));
+        assert_eq!(
+            starts(&e, "2026-01-05T08:30:00Z", "2026-01-05T09:00:00Z"),
+            ["2026-01-05T09:00:00+00:00"]
+        );
+        assert!(starts(&e, "2026-01-05T09:00:00Z", "2026-01-05T10:00:00Z").is_empty());
+    }
+
+    #[test]
+    fn test_weekly_byday_with_exdate() {
+        let e = event(
+            "DTSTART:20260105T090000Z\r\nRRULE:FREQ=WEEKLY;BYDAY=MO,WE;COUNT=5\r\nEXDATE:20260107T090000Z\r\n",
+        );
+        assert_eq!(
+            starts(&e, "2026-01-01T00:00:00Z", "2026-02-01T00:00:00Z"),
+            [
+                "2026-01-05T09:00:00+00:00",
+                "2026-01-12T09:00:00+00:00",
+                "2026-01-14T09:00:00+00:00",
+                "2026-01-19T09:00:00+00:00",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_daily_until_and_interval() {
+        let e = event(
+            "DTSTART:20260105T090000Z\r\nRRULE:FREQ=DAILY;INTERVAL=2;UNTIL=20260110T000000Z\r\n",
+        );
+        assert_eq!(
+            starts(&e, "2026-01-01T00:00:00Z", "2026-02-01T00:00:00Z"),
+            [
+                "2026-01-05T09:00:00+00:00",
+                "2026-01-07T09:00:00+00:00",
+                "2026-01-09T09:00:00+00:00",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_monthly_skips_short_months() {
+        let e = event("DTSTART:20260131T090000Z\r\nRRULE:FREQ=MONTHLY;COUNT=3\r\n");
+        assert_eq!(
+            starts(&e, "2026-01-01T00:00:00Z", "2026-12-31T00:00:00Z"),
+            [
+                "2026-01-31T09:00:00+00:00",
+                "2026-03-31T09:00:00+00:00",
+                "2026-05-31T09:00:00+00:00",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_recurring_keeps_wall_time_across_dst() {
+        let e = event("DTSTART;TZID=Europe/Berlin:20260326T100000\r\nRRULE:FREQ=DAILY;COUNT=3\r\n");
+        assert_eq!(
+            starts(&e, "2026-03-01T00:00:00Z", "2026-04-01T00:00:00Z"),
+            [
+                "2026-03-26T09:00:00+00:00",
+                "2026-03-27T09:00:00+00:00",
+                "2026-03-28T09:00:00+00:00",
+            ]
+        );
+        let e = event("DTSTART;TZID=Europe/Berlin:20260328T100000\r\nRRULE:FREQ=DAILY;COUNT=2\r\n");
+        assert_eq!(
+            starts(&e, "2026-03-01T00:00:00Z", "2026-04-01T00:00:00Z"),
+            ["2026-03-28T09:00:00+00:00", "2026-03-29T08:00:00+00:00"]
+        );
+    }
+
+    #[test]
+    fn test_upcoming_skips_all_day_cancelled_and_overridden() {
+        let ics = "BEGIN:VCALENDAR\r\n\
+BEGIN:VEVENT\r\nUID:series\r\nSUMMARY:Sync\r\nDTSTART:20260105T090000Z\r\nRRULE:FREQ=DAILY\r\nEND:VEVENT\r\n\
+BEGIN:VEVENT\r\nUID:series\r\nSUMMARY:Sync (moved)\r\nRECURRENCE-ID:20260106T090000Z\r\nDTSTART:20260106T091000Z\r\nEND:VEVENT\r\n\
+BEGIN:VEVENT\r\nUID:holiday\r\nSUMMARY:Holiday\r\nDTSTART;VALUE=DATE:20260106\r\nEND:VEVENT\r\n\
+BEGIN:VEVENT\r\nUID:gone\r\nSUMMARY:Cancelled\r\nSTATUS:CANCELLED\r\nDTSTART:20260106T090500Z\r\nEND:VEVENT\r\n\
+END:VCALENDAR\r\n";
+        let events = parse_ics(ics).unwrap();
+        let due = upcoming(
+            &events,
+            utc("2026-01-06T08:45:00Z"),
+            chrono::Duration::minutes(30),
+        );
+        let titles: Vec<&str> = due.iter().map(|(e, _)| e.summary.as_str()).collect();
+        assert_eq!(titles, ["Sync (moved)"]);
+    }
+
+    #[test]
+    fn test_monthly_ordinal_byday() {
+        let e = event("DTSTART:20260113T090000Z\r\nRRULE:FREQ=MONTHLY;BYDAY=2TU;COUNT=3\r\n");
+        assert_eq!(
+            starts(&e, "2026-01-01T00:00:00Z", "2026-12-31T00:00:00Z"),
+            [
+                "2026-01-13T09:00:00+00:00",
+                "2026-02-10T09:00:00+00:00",
+                "2026-03-10T09:00:00+00:00",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unsupported_rule_fires_first_instance() {
+        let e = event("DTSTART:20260105T090000Z\r\nRRULE:FREQ=SOMETIMES\r\n");
+        assert_eq!(
+            starts(&e, "2026-01-01T00:00:00Z", "2026-12-31T00:00:00Z"),
+            ["2026-01-05T09:00:00+00:00"]
+        );
+    }
+
+    #[test]
+    fn test_due_fires_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let vdir = dir.path().join("calendars").join("work");
+        std::fs::create_dir_all(&vdir).unwrap();
+        std::fs::write(
+            vdir.join("e1.ics"),
+            "BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nUID:e1\r\nSUMMARY:1:1 with Jane\r\n\
+LOCATION:Cafe\r\nATTENDEE;CN=Jane:mailto:jane@example.org\r\n\
+DTSTART:20260105T090000Z\r\nDTEND:20260105T093000Z\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n",
+        )
+        .unwrap();
+        std::fs::write(vdir.join("notes.txt"), "not a calendar").unwrap();
+
+        let settings = CalendarSettings {
+            paths: vec![dir.path().join("calendars")],
+            lead: Duration::from_secs(30 * 60),
+            poll_interval: Duration::from_secs(60),
+            db_path: dir.path().join("vatic.db"),
+        };
+        assert!(due(&settings, utc("2026-01-05T08:00:00Z"))
+            .unwrap()
+            .is_empty());
+
+        let fired = due(&settings, utc("2026-01-05T08:31:00Z")).unwrap();
+        assert_eq!(fired.len(), 1);
+        let (msg, start) = &fired[0];
+        assert_eq!(msg.sender, "e1");
+        assert_eq!(msg.text, "1:1 with Jane");
+        assert_eq!(msg.meta["location"], "Cafe");
+        assert_eq!(msg.meta["attendees"], "Jane <jane@example.org>");
+        assert_eq!(msg.meta["start"], format_local(utc("2026-01-05T09:00:00Z")));
+
+        // Not sent yet, so still due
+        assert_eq!(
+            due(&settings, utc("2026-01-05T08:32:00Z")).unwrap().len(),
+            1
+        );
+        Store::open(&settings.db_path)
+            .unwrap()
+            .record_calendar_fire(&msg.sender, start)
+            .unwrap();
+        assert!(due(&settings, utc("2026-01-05T08:33:00Z"))
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -0,0 +1,513 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use futures_util::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::Message as WsMessage;
+
+use super::{split_message, Channel, IncomingMessage};
+
+const API_BASE: &str = "https://discord.com/api/v10";
+const GATEWAY_URL: &str = "wss://gateway.discord.gg/?v=10&encoding=json";
+const MAX_MESSAGE: usize = 2000;
+/// Reconnect requests in a row without reaching READY before giving up.
+const MAX_RECONNECTS: u32 = 3;
+
+/// GUILDS | GUILD_MESSAGES | DIRECT_MESSAGES | MESSAGE_CONTENT
+const INTENTS: u64 = 1 | (1 << 9) | (1 << 12) | (1 << 15);
+
+/// Which guilds and channels we answer in. Empty lists allow everything;
+/// DMs are never filtered here (use `allowed_senders` for that).
+#[derive(Clone, Default)]
+pub struct DiscordFilter {
+    pub guilds: Vec<String>,
+    pub channels: Vec<String>,
+}
+
+impl DiscordFilter {
+    fn allows(&self, guild: &str, channel: &str) -> bool {
+        (self.guilds.is_empty() || self.guilds.iter().any(|g| g == guild))
+            && (self.channels.is_empty() || self.channels.iter().any(|c| c == channel))
+    }
+}
+
+/// Remove `<@id>` / `<@!id>` mentions of the bot.
+fn strip_mention(text: &str, bot_id: &str) -> String {
+    text.replace(&format!("<@{bot_id}>"), "")
+        .replace(&format!("<@!{bot_id}>"), "")
+        .trim()
+        .to_string()
+}
+
+/// Guild messages need a mention of the bot; DMs are taken as-is.
+/// The sender is the author's user id; replies go to the Discord channel we
+/// were asked in.
+fn to_incoming(d: &Value, bot_id: &str, filter: &DiscordFilter) -> Option<IncomingMessage> {
+    let author = d.get("author")?;
+    if author.get("bot").and_then(|b| b.as_bool()) == Some(true) {
+        return None;
+    }
+    let user_id = author.get("id")?.as_str()?;
+    let channel_id = d.get("channel_id")?.as_str()?;
+    let content = d.get("content")?.as_str()?;
+
+    let mut meta = HashMap::new();
+    let text = match d.get("guild_id").and_then(|g| g.as_str()) {
+        Some(guild) => {
+            let mentioned = d
+                .get("mentions")
+                .and_then(|m| m.as_array())
+                .is_some_and(|m| m.iter().any(|u| u.get("id") == Some(&json!(bot_id))));
+            if !mentioned || !filter.allows(guild, channel_id) {
+                return None;
+            }
+            meta.insert("guild".to_string(), guild.to_string());
+            strip_mention(content, bot_id)
+        }
+        None => content.trim().to_string(),
+    };
+    if text.is_empty() {
+        return None;
+    }
+
+    meta.insert("user".to_string(), user_id.to_string());
+    if let Some(name) = author.get("username").and_then(|n| n.as_str()) {
+        meta.insert("username".to_string(), name.to_string());
+    }
+    Some(IncomingMessage {
+        channel: "discord".to_string(),
+        sender: user_id.to_string(),
+        text,
+        meta,
+        reply_to: Some(channel_id.to_string()),
+        ..Default::default()
+    })
+}
+
+/// How a gateway session ended without an error.
+enum SessionEnd {
+    /// The receiver is gone.
+    Stopped,
+    /// Discord asked for a reconnect (op 7) or a fresh identify (op 9),
+    /// to be made after `delay`. `ready` tells whether we got that far.
+    Reconnect { delay: Duration, ready: bool },
+}
+
+/// Discord bot over the gateway WebSocket, replying through the REST API.
+pub struct DiscordChannel {
+    token: String,
+    filter: DiscordFilter,
+    api_base: String,
+    gateway_url: String,
+    client: reqwest::Client,
+    /// Our own user id, learned from READY.
+    bot_id: Mutex<String>,
+}
+
+impl DiscordChannel {
+    pub fn new(token: String, filter: DiscordFilter) -> Self {
+        Self {
+            token,
+            filter,
+            api_base: API_BASE.to_string(),
+            gateway_url: GATEWAY_URL.to_string(),
+            client: reqwest::Client::new(),
+            bot_id: Mutex::new(String::new()),
+        }
+    }
+
+    /// One gateway session.
+    async fn session(
+        &self,
+        tx: &mpsc::Sender<IncomingMessage>,
+    ) -> crate::error::Result<SessionEnd> {
+        let err = |e: tokio_tungstenite::tungstenite::Error| {
+            crate::error::Error::Channel(format!("discord gateway: {e}"))
+        };
+        let (mut ws, _) = tokio_tungstenite::connect_async(self.gateway_url.as_str())
+            .await
+            .map_err(err)?;
+
+        let mut heartbeat = tokio::time::interval(Duration::from_secs(3600));
+        let mut seq = Value::Null;
+        let mut ready = false;
+        // A beat sent before the last one's ACK arrived means a zombie connection
+        let mut acked = true;
+
+        loop {
+            tokio::select! {
+                frame = ws.next() => {
+                    let Some(frame) = frame else {
+                        return Err(crate::error::Error::Channel(
+                            "discord gateway closed".to_string(),
+                        ));
+                    };
+                    let text = match frame.map_err(err)? {
+                        WsMessage::Text(text) => text,
+                        WsMessage::Close(reason) => {
+                            return Err(crate::error::Error::Channel(format!(
+                                "discord gateway closed: {reason:?}"
+                            )));
+                        }
+                        _ => continue,
+                    };
+                    let Ok(payload) = serde_json::from_str::<Value>(&text) else {
+                        continue;
+                    };
+                    if let Some(s) = payload.get("s").filter(|s| !s.is_null()) {
+                        seq = s.clone();
+                    }
+
+                    match payload.get("op").and_then(|o| o.as_u64()) {
+                        // Hello: start heartbeating and identify
+                        Some(10) => {
+                            let ms = payload
+                                .pointer("/d/heartbeat_interval")
+                                .and_then(|i| i.as_u64())
+                                .unwrap_or(41250);
+                            heartbeat = tokio::time::interval(Duration::from_millis(ms));
+                            heartbeat.tick().await;
+                            let identify = json!({
+                                "op": 2,
+                                "d": {
+                                    "token": self.token,
+                                    "intents": INTENTS,
+                                    "properties": { "os": "linux", "browser": "vatic", "device": "vatic" },
+                                },
+                            });
+                            ws.send(WsMessage::Text(identify.to_string().into()))
+                                .await
+                                .map_err(err)?;
+                        }
+                        // Heartbeat requested right now
+                        Some(1) => {
+                            let beat = json!({ "op": 1, "d": seq });
+                            ws.send(WsMessage::Text(beat.to_string().into()))
+                                .await
+                                .map_err(err)?;
+                        }
+                        Some(11) => acked = true,
+                        // Reconnect: start over right away
+                        Some(7) => {
+                            return Ok(SessionEnd::Reconnect { delay: Duration::ZERO, ready });
+                        }
+                        // Invalid session: Discord wants 1-5s before the next identify
+                        Some(9) => {
+                            let delay = Duration::from_secs(1)
+                                + Duration::from_secs(4).mul_f64(crate::daemon::supervisor::jitter());
+                            return Ok(SessionEnd::Reconnect { delay, ready });
+                        }
+                        Some(0) => match payload.get("t").and_then(|t| t.as_str()) {
+                            Some("READY") => {
+                                ready = true;
+                                if let Some(id) = payload.pointer("/d/user/id").and_then(|i| i.as_str()) {
+                                    tracing::info!("discord connected as {}", id);
+                                    *self.bot_id.lock().unwrap() = id.to_string();
+                                }
+                            }
+                            Some("MESSAGE_CREATE") => {
+                                let bot_id = self.bot_id.lock().unwrap().clone();
+                                let Some(msg) = payload
+                                    .get("d")
+                                    .and_then(|d| to_incoming(d, &bot_id, &self.filter))
+                                else {
+                                    continue;
+                                };
+                                if tx.send(msg).await.is_err() {
+                                    return Ok(SessionEnd::Stopped); // receiver dropped
+                                }
+                            }
+                            _ => {}
+                        },
+                        _ => {}
+                    }
+                }
+                _ = heartbeat.tick() => {
+                    if !acked {
+                        return Err(crate::error::Error::Channel(
+                            "discord heartbeat not acknowledged".to_string(),
+                        ));
+                    }
+                    acked = false;
+                    let beat = json!({ "op": 1, "d": seq });
+                    ws.send(WsMessage::Text(beat.to_string().into()))
+                        .await
+                        .map_err(err)?;
+                }
+            }
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Channel for DiscordChannel {
+    async fn start(&self, tx: mpsc::Sender<IncomingMessage>) -> crate::error::Result<()> {
+        // Discord moves sessions between gateways now and then; only failures
+        // and a gateway that keeps turning us away go back to the supervisor
+        let mut retries = 0;
+        loop {
+            match self.session(&tx).await? {
+                SessionEnd::Stopped => return Ok(()),
+                SessionEnd::Reconnect { delay, ready } => {
+                    retries = if ready { 1 } else { retries + 1 };
+                    if retries > MAX_RECONNECTS {
+                        return Err(crate::error::Error::Channel(
+                            "discord gateway keeps asking to reconnect".to_string(),
+                        ));
+                    }
+                    tokio::time::sleep(delay).await;
+                }
+            }
+        }
+    }
+
+    async fn send(&self, to: &str, message: &str) -> crate::error::Result<()> {
+        let url = format!("{}/channels/{to}/messages", self.api_base);
+        for chunk in split_message(message, MAX_MESSAGE) {
+            let resp = self
+                .client
+                .post(&url)
+                .header("Authorization", format!("Bot {}", self.token))
+                .json(&json!({ "content": chunk }))
+                .send()
+                .await
+                .map_err(|e| crate::error::Error::Channel(format!("discord send failed: {e}")))?;
+            if !resp.status().is_success() {
+                let status = resp.status();
+                let body = resp.text().await.unwrap_or_default();
+                return Err(crate::error::Error::Channel(format!(
+                    "discord send failed: {status} {body}"
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        "discord"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::extract::ws::{Message as AxumWs, WebSocketUpgrade};
+    use axum::extract::Path;
+    use axum::routing::{any, post};
+    use axum::{Json, Router};
+    use std::sync::Arc;
+
+    fn guild_message(channel: &str, content: &str, mentions: Value) -> Value {
+        json!({
+            "channel_id": channel, "guild_id": "G1", "content": content,
+            "author": { "id": "U1", "username": "jane" },
+            "mentions": mentions,
+        })
+    }
+
+    #[test]
+    fn test_discord_channel_name() {
+        let ch = DiscordChannel::new("t".into(), DiscordFilter::default());
+        assert_eq!(ch.name(), "discord");
+    }
+
+    #[test]
+    fn test_guild_mention() {
+        let d = guild_message("C1", "<@42> what's up", json!([{ "id": "42" }]));
+        let msg = to_incoming(&d, "42", &DiscordFilter::default()).unwrap();
+        assert_eq!(msg.sender, "U1");
+        assert_eq!(msg.reply_address(), "C1");
+        assert_eq!(msg.text, "what's up");
+        assert_eq!(msg.meta["username"], "jane");
+        assert_eq!(msg.meta["guild"], "G1");
+    }
+
+    #[test]
+    fn test_guild_without_mention_ignored() {
+        let d = guild_message("C1", "chatter", json!([]));
+        assert!(to_incoming(&d, "42", &DiscordFilter::default()).is_none());
+    }
+
+    #[test]
+    fn test_direct_message_and_bots() {
+        let dm = json!({
+            "channel_id": "D1", "content": " hi ",
+            "author": { "id": "U1", "username": "jane" },
+        });
+        let msg = to_incoming(&dm, "42", &DiscordFilter::default()).unwrap();
+        assert_eq!(msg.text, "hi");
+        assert_eq!(msg.sender, "U1");
+        assert_eq!(msg.reply_address(), "D1");
+        let bot = json!({
+            "channel_id": "D1", "content": "hi",
+            "author": { "id": "B1", "bot": true },
+        });
+        assert!(to_incoming(&bot, "42", &DiscordFilter::default()).is_none());
+    }
+
+    #[test]
+    fn test_allowlists() {
+        let filter = DiscordFilter {
+            guilds: vec!["G1".into()],
+            channels: vec!["C1".into()],
+        };
+        let mentions = json!([{ "id": "42" }]);
+        assert!(to_incoming(
+            &guild_message("C1", "<@42> a", mentions.clone()),
+            "42",
+            &filter
+        )
+        .is_some());
+        assert!(to_incoming(
+            &guild_message("C2", "<@42> a", mentions.clone()),
+            "42",
+            &filter
+        )
+        .is_none());
+
+        let mut other_guild = guild_message("C1", "<@42> a", mentions);
+        other_guild["guild_id"] = json!("G2");
+        assert!(to_incoming(&other_guild, "42", &filter).is_none());
+    }
+
+    /// Fake gateway (hello → identify → ready → message) and REST endpoint.
+    #[tokio::test]
+    async fn test_gateway_roundtrip_and_split() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let posted: Arc<Mutex<Vec<(String, String)>>> = Arc::default();
+        let identified: Arc<Mutex<Option<Value>>> = Arc::default();
+
+        let posted_clone = Arc::clone(&posted);
+        let identified_clone = Arc::clone(&identified);
+        let app = Router::new()
+            .route(
+                "/channels/{id}/messages",
+                post(move |Path(id): Path<String>, Json(body): Json<Value>| async move {
+                    let content = body["content"].as_str().unwrap().to_string();
+                    posted_clone.lock().unwrap().push((id, content));
+                    Json(json!({ "id": "1" }))
+                }),
+            )
+            .route(
+                "/gateway",
+                any(move |ws: WebSocketUpgrade| async move {
+                    ws.on_upgrade(move |mut socket| async move {
+                        let hello = json!({ "op": 10, "d": { "heartbeat_interval": 45000 } });
+                        socket.send(AxumWs::Text(hello.to_string().into())).await.unwrap();
+                        if let Some(Ok(AxumWs::Text(id))) = socket.next().await {
+                            *identified_clone.lock().unwrap() = serde_json::from_str(&id).ok();
+                        }
+                        let ready = json!({ "op": 0, "s": 1, "t": "READY", "d": { "user": { "id": "42" } } });
+                        let msg = json!({ "op": 0, "s": 2, "t": "MESSAGE_CREATE", "d": guild_message(
+                            "C1", "<@!42> hello", json!([{ "id": "42" }])) });
+                        for frame in [ready, msg] {
+                            socket.send(AxumWs::Text(frame.to_string().into())).await.unwrap();
+                        }
+                        while socket.next().await.is_some() {}
+                    })
+                }),
+            );
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let mut ch = DiscordChannel::new("tok".into(), DiscordFilter::default());
+        ch.api_base = format!("http://{addr}");
+        ch.gateway_url = format!("ws://{addr}/gateway");
+        let ch = Arc::new(ch);
+        let (tx, mut rx) = mpsc::channel(10);
+        let runner = Arc::clone(&ch);
+        tokio::spawn(async move { runner.start(tx).await });
+
+        let msg = rx.recv().await.unwrap();
+        assert_eq!(msg.sender, "U1");
+        assert_eq!(msg.reply_address(), "C1");
+        assert_eq!(msg.text, "hello");
+        let identify = identified.lock().unwrap().clone().unwrap();
+        assert_eq!(identify["op"], 2);
+        assert_eq!(identify["d"]["token"], "tok");
+        assert_eq!(identify["d"]["intents"], INTENTS);
+
+        let long = "word ".repeat(500);
+        ch.send("C1", &long).await.unwrap();
+        let posted = posted.lock().unwrap();
+        assert_eq!(posted.len(), 2);
+        assert!(posted
+            .iter()
+            .all(|(id, c)| id == "C1" && c.len() <= MAX_MESSAGE));
+    }
+
+    /// Serve `session` as the gateway and run a channel against it.
+    async fn run_against<F, Fut>(session: F) -> crate::error::Result<()>
+    where
+        F: Fn(axum::extract::ws::WebSocket) -> Fut + Clone + Send + Sync + 'static,
+        Fut: std::future::Future<Output = ()> + Send + 'static,
+    {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let app = Router::new().route(
+            "/gateway",
+            any(move |ws: WebSocketUpgrade| {
+                let session = session.clone();
+                async move { ws.on_upgrade(session) }
+            }),
+        );
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let mut ch = DiscordChannel::new("tok".into(), DiscordFilter::default());
+        ch.gateway_url = format!("ws://{addr}/gateway");
+        let (tx, _rx) = mpsc::channel(10);
+        ch.start(tx).await
+    }
+
+    #[tokio::test]
+    async fn test_reconnect_loop_goes_to_supervisor() {
+        let sessions = Arc::new(std::sync::atomic::AtomicU32::new(0));
+        let counted = Arc::clone(&sessions);
+        let err = run_against(move |mut socket| {
+            counted.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            async move {
+                let hello = json!({ "op": 10, "d": { "heartbeat_interval": 45000 } });
+                socket
+                    .send(AxumWs::Text(hello.to_string().into()))
+                    .await
+                    .unwrap();
+                socket.next().await; // identify
+                let reconnect = json!({ "op": 7, "d": null });
+                let _ = socket
+                    .send(AxumWs::Text(reconnect.to_string().into()))
+                    .await;
+            }
+        })
+        .await
+        .unwrap_err();
+        assert!(
+            err.to_string().contains("keeps asking to reconnect"),
+            "{err}"
+        );
+        assert_eq!(
+            sessions.load(std::sync::atomic::Ordering::SeqCst),
+            MAX_RECONNECTS + 1
+        );
+    }
+
+    #[tokio::test]
+    async fn test_missing_heartbeat_ack_fails() {
+        let err = run_against(|mut socket| async move {
+            let hello = json!({ "op": 10, "d": { "heartbeat_interval": 20 } });
+            socket
+                .send(AxumWs::Text(hello.to_string().into()))
+                .await
+                .unwrap();
+            // Read identify and heartbeats, never acknowledge them
+            while socket.next().await.is_some() {}
+        })
+        .await
+        .unwrap_err();
+        assert!(
+            err.to_string().contains("heartbeat not acknowledged"),
+            "{err}"
+        );
+    }
+}
@@ -1,8 +1,14 @@
-use std::collections::{HashSet, VecDeque};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
+use mail_parser::{MessageParser, MimeHeaders};
 use tokio::sync::mpsc;
 
 const MAX_SEEN: usize = 10_000;
+/// Attachments are only needed while jobs run on the message.
+const ATTACHMENT_TTL: Duration = Duration::from_secs(24 * 60 * 60);
 
 use super::{Channel, IncomingMessage};
 
@@ -12,7 +18,7 @@ fn sanitize_header(value: &str) -> String {
 }
 
 /// Prepend subject to body when present, otherwise just the body.
-fn format_email_text(subject: &str, body: &str) -> String {
+pub fn format_email_text(subject: &str, body: &str) -> String {
     if subject.is_empty() {
         body.to_string()
     } else {
@@ -20,16 +26,304 @@ fn format_email_text(subject: &str, body: &str) -> String {
     }
 }
 
+/// What we need from the original message to thread a reply.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ReplyContext {
+    pub subject: String,
+    pub message_id: Option<String>,
+    pub references: Vec<String>,
+}
+
+/// A parsed incoming email, independent of how it was fetched.
+#[derive(Debug, Clone)]
+pub struct ParsedEmail {
+    pub from: Option<String>,
+    pub subject: String,
+    pub body: String,
+    pub reply: ReplyContext,
+    /// (filename, contents) for every non-inline part.
+    pub attachments: Vec<(String, Vec<u8>)>,
+}
+
+/// Parse a raw RFC 5322 message. HTML-only bodies are converted to text.
+pub fn parse_email(raw: &[u8]) -> Option<ParsedEmail> {
+    let message = MessageParser::default().parse(raw)?;
+
+    let from = message
+        .from()
+        .and_then(|a| a.first())
+        .and_then(|a| a.address())
+        .map(|a| a.to_string());
+    let subject = message.subject().unwrap_or("").trim().to_string();
+    let body = message
+        .body_text(0)
+        .map(|b| b.trim().to_string())
+        .unwrap_or_default();
+
+    let references = message
+        .references()
+        .as_text_list()
+        .map(|refs| refs.iter().map(|r| r.to_string()).collect())
+        .unwrap_or_default();
+
+    let attachments = message
+        .attachments()
+        .enumerate()
+        .map(|(i, part)| {
+            let name = part
+                .attachment_name()
+                .map(sanitize_filename)
+                .filter(|n| !n.is_empty())
+                .unwrap_or_else(|| format!("attachment-{}", i + 1));
+            (name, part.contents().to_vec())
+        })
+        .collect();
+
+    Some(ParsedEmail {
+        from,
+        subject: subject.clone(),
+        body,
+        reply: ReplyContext {
+            subject,
+            message_id: message.message_id().map(|id| id.to_string()),
+            references,
+        },
+        attachments,
+    })
+}
+
+/// Keep only characters that are safe in a single path component.
+fn sanitize_filename(name: &str) -> String {
+    let cleaned: String = name
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || matches!(c, '.' | '-' | '_' | ' ') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    cleaned.trim_start_matches('.').trim().to_string()
+}
+
+/// `report.pdf`, then `report-2.pdf` and so on for repeated names.
+fn unique_name(name: &str, used: &mut HashSet<String>) -> String {
+    let (stem, ext) = match name.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() => (stem, format!(".{ext}")),
+        _ => (name, String::new()),
+    };
+    let mut candidate = name.to_string();
+    let mut n = 1;
+    while !used.insert(candidate.clone()) {
+        n += 1;
+        candidate = format!("{stem}-{n}{ext}");
+    }
+    candidate
+}
+
+/// Owner-only, since mail can be private.
+fn create_private_dir(dir: &Path) -> std::io::Result<()> {
+    use std::os::unix::fs::DirBuilderExt;
+    std::fs::DirBuilder::new()
+        .recursive(true)
+        .mode(0o700)
+        .create(dir)
+}
+
+/// Write attachments into `dir`, returning the paths that were saved.
+/// Older messages' attachments next to it are cleaned up on the way, even
+/// when this one has none.
+pub fn save_attachments(dir: &Path, attachments: &[(String, Vec<u8>)]) -> Vec<PathBuf> {
+    if let Some(root) = dir.parent() {
+        prune_attachments(root, ATTACHMENT_TTL);
+    }
+    if attachments.is_empty() {
+        return vec![];
+    }
+    if let Err(e) = create_private_dir(dir) {
+        tracing::error!("cannot create attachment dir {}: {e}", dir.display());
+        return vec![];
+    }
+
+    let mut used = HashSet::new();
+    let mut paths = Vec::new();
+    for (name, contents) in attachments {
+        let path = dir.join(unique_name(name, &mut used));
+        match std::fs::write(&path, contents) {
+            Ok(()) => paths.push(path),
+            Err(e) => tracing::error!("cannot save attachment {}: {e}", path.display()),
+        }
+    }
+    paths
+}
+
+/// Remove per-message attachment dirs under `root` older than `ttl`.
+fn prune_attachments(root: &Path, ttl: Duration) {
+    let Ok(entries) = std::fs::read_dir(root) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let expired = entry
+            .metadata()
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|t| t.elapsed().ok())
+            .is_some_and(|age| age > ttl);
+        if expired && entry.path().is_dir() {
+            if let Err(e) = std::fs::remove_dir_all(entry.path()) {
+                tracing::warn!("cannot remove {}: {e}", entry.path().display());
+            }
+        }
+    }
+}
+
+/// Where attachments for a given message end up, under a channel's
+/// attachment root (`<data dir>/attachments/<channel>`).
+pub fn attachment_dir(root: &Path, id: &str) -> PathBuf {
+    root.join(sanitize_filename(id))
+}
+
+/// `Re: <subject>`, without stacking prefixes on an existing reply.
+fn reply_subject(subject: &str) -> String {
+    let subject = subject.trim();
+    if subject.is_empty() {
+        "Re: vatic".to_string()
+    } else if subject.to_lowercase().starts_with("re:") {
+        subject.to_string()
+    } else {
+        format!("Re: {}", subject)
+    }
+}
+
+/// Angle-bracket a message id if it isn't already.
+fn bracket_id(id: &str) -> String {
+    let id = id.trim();
+    if id.starts_with('<') {
+        id.to_string()
+    } else {
+        format!("<{}>", id)
+    }
+}
+
+/// Sanitized threading headers for a reply.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplyHeaders {
+    pub subject: String,
+    pub in_reply_to: Option<String>,
+    pub references: Option<String>,
+}
+
+/// Derive `Subject`, `In-Reply-To` and `References` from the original message.
+pub fn reply_headers(reply: Option<&ReplyContext>) -> ReplyHeaders {
+    let Some(ctx) = reply else {
+        return ReplyHeaders {
+            subject: "Re: vatic".to_string(),
+            in_reply_to: None,
+            references: None,
+        };
+    };
+
+    let subject = sanitize_header(&reply_subject(&ctx.subject));
+    let Some(id) = &ctx.message_id else {
+        return ReplyHeaders {
+            subject,
+            in_reply_to: None,
+            references: None,
+        };
+    };
+
+    let id = bracket_id(&sanitize_header(id));
+    let mut refs: Vec<String> = ctx
+        .references
+        .iter()
+        .map(|r| bracket_id(&sanitize_header(r)))
+        .collect();
+    refs.push(id.clone());
+
+    ReplyHeaders {
+        subject,
+        in_reply_to: Some(id),
+        references: Some(refs.join(" ")),
+    }
+}
+
+/// Build an RFC 2822 reply, threaded onto the original when we have one.
+pub fn build_reply(to: &str, reply: Option<&ReplyContext>, body: &str) -> String {
+    let h = reply_headers(reply);
+    let mut headers = vec![
+        format!("To: {}", sanitize_header(to)),
+        format!("Subject: {}", h.subject),
+    ];
+    if let Some(id) = h.in_reply_to {
+        headers.push(format!("In-Reply-To: {}", id));
+    }
+    if let Some(refs) = h.references {
+        headers.push(format!("References: {}", refs));
+    }
+
+    format!("{}\r\n\r\n{}", headers.join("\r\n"), body)
+}
+
+/// Remembers recent messages by Message-ID, so a reply threads onto the
+/// message it answers rather than the sender's latest.
+#[derive(Default)]
+pub struct ReplyTracker {
+    contexts: HashMap<String, (String, ReplyContext)>,
+    order: VecDeque<String>,
+    unthreaded: u64,
+}
+
+impl ReplyTracker {
+    /// Remember a message from `sender`. Returns the key to reply to,
+    /// which goes into `IncomingMessage::reply_to`.
+    pub fn insert(&mut self, sender: &str, ctx: ReplyContext) -> String {
+        let key = match &ctx.message_id {
+            Some(id) => bracket_id(&sanitize_header(id)),
+            None => {
+                self.unthreaded += 1;
+                format!("<{}.unthreaded.vatic>", self.unthreaded)
+            }
+        };
+        if self
+            .contexts
+            .insert(key.clone(), (sender.to_string(), ctx))
+            .is_none()
+        {
+            self.order.push_back(key.clone());
+        }
+        while self.contexts.len() > MAX_SEEN {
+            if let Some(old) = self.order.pop_front() {
+                self.contexts.remove(&old);
+            }
+        }
+        key
+    }
+
+    /// Recipient address and thread for `to`: a key from `insert` means
+    /// the original sender, anything else is a plain address.
+    pub fn resolve(&self, to: &str) -> (String, Option<ReplyContext>) {
+        match self.contexts.get(to) {
+            Some((sender, ctx)) => (sender.clone(), Some(ctx.clone())),
+            None => (to.to_string(), None),
+        }
+    }
+}
+
 pub struct EmailChannel {
     poll_interval: u64,
     account: Option<String>,
+    attachments: PathBuf,
+    replies: Arc<Mutex<ReplyTracker>>,
 }
 
 impl EmailChannel {
-    pub fn new(poll_interval: u64, account: Option<String>) -> Self {
+    pub fn new(poll_interval: u64, account: Option<String>, attachments: PathBuf) -> Self {
         Self {
             poll_interval,
             account,
+            attachments,
+            replies: Arc::new(Mutex::new(ReplyTracker::default())),
         }
     }
 }
@@ -65,20 +359,38 @@ impl Channel for EmailChannel {
                     }
                 }
 
-                let body = match read_message(&envelope.id, self.account.as_deref()).await {
-                    Ok(body) => body,
+                let dir = attachment_dir(&self.attachments, &envelope.id);
+                let raw = match export_message(&envelope.id, self.account.as_deref(), &dir).await {
+                    Ok(raw) => raw,
                     Err(e) => {
-                        tracing::error!("himalaya message read {} failed: {e}", envelope.id);
+                        tracing::error!("himalaya message export {} failed: {e}", envelope.id);
                         continue;
                     }
                 };
 
-                let text = format_email_text(&envelope.subject, &body);
+                let Some(parsed) = parse_email(&raw) else {
+                    tracing::error!("cannot parse message {}", envelope.id);
+                    continue;
+                };
+
+                // Prefer the real address; the listing only shows display names
+                let sender = parsed.from.clone().unwrap_or_else(|| envelope.from.clone());
+                let reply_to = self
+                    .replies
+                    .lock()
+                    .unwrap()
+                    .insert(&sender, parsed.reply.clone());
+
+                let attachments = save_attachments(&dir, &parsed.attachments);
+                let text = format_email_text(&parsed.subject, &parsed.body);
 
                 let msg = IncomingMessage {
                     channel: "himalaya".to_string(),
-                    sender: envelope.from.clone(),
+                    sender,
                     text,
+                    attachments,
+                    reply_to: Some(reply_to),
+                    ..Default::default()
                 };
 
                 if tx.send(msg).await.is_err() {
@@ -94,9 +406,8 @@ impl Channel for EmailChannel {
             args.extend(["--account", acct]);
         }
 
-        // Build minimal RFC 2822 message (strip CR/LF from header values)
-        let safe_to = sanitize_header(to);
-        let email = format!("To: {}\r\nSubject: Re: vatic\r\n\r\n{}", safe_to, message);
+        let (to, reply) = self.replies.lock().unwrap().resolve(to);
+        let email = build_reply(&to, reply.as_ref(), message);
 
         let mut cmd = tokio::process::Command::new("himalaya");
         cmd.args(&args)
@@ -194,34 +505,60 @@ async fn list_envelopes(
     Ok(envelopes)
 }
 
-async fn read_message(
+/// Export the full raw message via `himalaya message export --full`.
+async fn export_message(
     id: &str,
     account: Option<&str>,
-) -> std::result::Result<String, crate::error::Error> {
-    let mut args = vec!["message", "read", id, "--mime-type", "plain"];
-    if let Some(acct) = account {
-        args.extend(["--account", acct]);
-    }
+    dir: &Path,
+) -> std::result::Result<Vec<u8>, crate::error::Error> {
+    create_private_dir(dir).map_err(|e| {
+        crate::error::Error::Channel(format!("cannot create {}: {e}", dir.display()))
+    })?;
+    let dest = dir.join("message.eml");
+    let dest_str = dest.to_string_lossy().to_string();
+
+    let raw = async {
+        let mut args = vec![
+            "message",
+            "export",
+            id,
+            "--full",
+            "--destination",
+            dest_str.as_str(),
+        ];
+        if let Some(acct) = account {
+            args.extend(["--account", acct]);
+        }
 
-    let output = tokio::time::timeout(
-        std::time::Duration::from_secs(30),
-        tokio::process::Command::new("himalaya")
-            .args(&args)
-            .output(),
-    )
-    .await
-    .map_err(|_| crate::error::Error::Channel("himalaya message read timed out".to_string()))?
-    .map_err(|e| crate::error::Error::Channel(format!("cannot run himalaya: {e}")))?;
+        let output = tokio::time::timeout(
+            std::time::Duration::from_secs(30),
+            tokio::process::Command::new("himalaya")
+                .args(&args)
+                .output(),
+        )
+        .await
+        .map_err(|_| crate::error::Error::Channel("himalaya message export timed out".to_string()))?
+        .map_err(|e| crate::error::Error::Channel(format!("cannot run himalaya: {e}")))?;
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(crate::error::Error::Channel(format!(
-            "himalaya message read failed: {}",
-            stderr.trim()
-        )));
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(crate::error::Error::Channel(format!(
+                "himalaya message export failed: {}",
+                stderr.trim()
+            )));
+        }
+
+        tokio::fs::read(&dest)
+            .await
+            .map_err(|e| crate::error::Error::Channel(format!("cannot read exported message: {e}")))
     }
+    .await;
 
-    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    // Leave nothing behind; save_attachments recreates the dir if the
+    // message has attachments
+    let _ = tokio::fs::remove_file(&dest).await;
+    let _ = tokio::fs::remove_dir(dir).await;
+    raw
 }
 
 #[cfg(test)]
@@ -230,7 +567,7 @@ mod tests {
 
     #[test]
     fn test_email_channel_name() {
-        let ch = EmailChannel::new(60, None);
+        let ch = EmailChannel::new(60, None, "/tmp/attachments".into());
         assert_eq!(ch.name(), "himalaya");
     }
 
@@ -358,4 +695,178 @@ mod tests {
     fn test_format_email_text_empty_body() {
         assert_eq!(format_email_text("Subject", ""), "Subject\n\n");
     }
+
+    const MULTIPART: &str = "From: Jane Doe <jane@test.org>\r\n\
+To: bot@example.com\r\n\
+Subject: Quarterly report\r\n\
+Message-ID: <abc123@test.org>\r\n\
+References: <root@test.org>\r\n\
+MIME-Version: 1.0\r\n\
+Content-Type: multipart/mixed; boundary=\"XYZ\"\r\n\
+\r\n\
+--XYZ\r\n\
+Content-Type: text/plain; charset=utf-8\r\n\
+\r\n\
+Please summarize the attached file.\r\n\
+--XYZ\r\n\
+Content-Type: text/csv\r\n\
+Content-Disposition: attachment; filename=\"../numbers.csv\"\r\n\
+\r\n\
+a,b\r\n\
+1,2\r\n\
+--XYZ--\r\n";
+
+    #[test]
+    fn test_parse_email_multipart() {
+        let parsed = parse_email(MULTIPART.as_bytes()).unwrap();
+        assert_eq!(parsed.from.as_deref(), Some("jane@test.org"));
+        assert_eq!(parsed.subject, "Quarterly report");
+        assert_eq!(parsed.body, "Please summarize the attached file.");
+        assert_eq!(parsed.reply.message_id.as_deref(), Some("abc123@test.org"));
+        assert_eq!(parsed.reply.references, vec!["root@test.org".to_string()]);
+        assert_eq!(parsed.attachments.len(), 1);
+        assert_eq!(parsed.attachments[0].0, "_numbers.csv");
+        assert!(String::from_utf8_lossy(&parsed.attachments[0].1).contains("1,2"));
+    }
+
+    #[test]
+    fn test_parse_email_html_only() {
+        let raw = "From: a@b.c\r\nSubject: Hi\r\nContent-Type: text/html\r\n\r\n<p>Hello <b>world</b></p>";
+        let parsed = parse_email(raw.as_bytes()).unwrap();
+        assert!(parsed.body.contains("Hello"));
+        assert!(parsed.body.contains("world"));
+        assert!(!parsed.body.contains("<b>"));
+    }
+
+    #[test]
+    fn test_reply_subject_adds_prefix() {
+        assert_eq!(reply_subject("Quarterly report"), "Re: Quarterly report");
+    }
+
+    #[test]
+    fn test_reply_subject_keeps_existing_prefix() {
+        assert_eq!(
+            reply_subject("RE: Quarterly report"),
+            "RE: Quarterly report"
+        );
+    }
+
+    #[test]
+    fn test_reply_subject_empty() {
+        assert_eq!(reply_subject(""), "Re: vatic");
+    }
+
+    #[test]
+    fn test_build_reply_threads_headers() {
+        let ctx = ReplyContext {
+            subject: "Quarterly report".into(),
+            message_id: Some("abc123@test.org".into()),
+            references: vec!["root@test.org".into()],
+        };
+        let email = build_reply("jane@test.org", Some(&ctx), "Done.");
+        assert_eq!(
+            email,
+            "To: jane@test.org\r\n\
+Subject: Re: Quarterly report\r\n\
+In-Reply-To: <abc123@test.org>\r\n\
+References: <root@test.org> <abc123@test.org>\r\n\
+\r\n\
+Done."
+        );
+    }
+
+    #[test]
+    fn test_build_reply_without_context() {
+        let email = build_reply("jane@test.org", None, "Done.");
+        assert_eq!(
+            email,
+            "To: jane@test.org\r\nSubject: Re: vatic\r\n\r\nDone."
+        );
+    }
+
+    #[test]
+    fn test_build_reply_strips_header_injection() {
+        let ctx = ReplyContext {
+            subject: "Hi\r\nBcc: evil@hack.com".into(),
+            message_id: None,
+            references: vec![],
+        };
+        let email = build_reply("jane@test.org", Some(&ctx), "body");
+        assert!(!email.contains("\r\nBcc:"));
+    }
+
+    #[test]
+    fn test_reply_tracker_by_message_id() {
+        let mut tracker = ReplyTracker::default();
+        let first = tracker.insert(
+            "jane@test.org",
+            ReplyContext {
+                subject: "First".into(),
+                message_id: Some("one@test.org".into()),
+                ..Default::default()
+            },
+        );
+        tracker.insert(
+            "jane@test.org",
+            ReplyContext {
+                subject: "Second".into(),
+                message_id: Some("two@test.org".into()),
+                ..Default::default()
+            },
+        );
+        assert_eq!(first, "<one@test.org>");
+        let (to, ctx) = tracker.resolve(&first);
+        assert_eq!(to, "jane@test.org");
+        assert_eq!(ctx.unwrap().subject, "First");
+
+        let unthreaded = tracker.insert("bob@test.org", ReplyContext::default());
+        assert_eq!(tracker.resolve(&unthreaded).0, "bob@test.org");
+        assert_eq!(
+            tracker.resolve("bob@test.org"),
+            ("bob@test.org".to_string(), None)
+        );
+    }
+
+    #[test]
+    fn test_sanitize_filename_strips_path() {
+        assert_eq!(sanitize_filename("../../etc/passwd"), "_.._etc_passwd");
+        assert_eq!(sanitize_filename("report 2026.pdf"), "report 2026.pdf");
+    }
+
+    #[test]
+    fn test_save_attachments() {
+        use std::os::unix::fs::PermissionsExt;
+        let root = tempfile::tempdir().unwrap();
+        let stale = root.path().join("old");
+        std::fs::create_dir(&stale).unwrap();
+        let old = std::time::SystemTime::now() - ATTACHMENT_TTL * 2;
+        std::fs::File::open(&stale)
+            .unwrap()
+            .set_modified(old)
+            .unwrap();
+
+        let dir = attachment_dir(root.path(), "m1");
+        let paths = save_attachments(
+            &dir,
+            &[
+                ("a.txt".to_string(), b"hello".to_vec()),
+                ("a.txt".to_string(), b"again".to_vec()),
+            ],
+        );
+        assert_eq!(paths, [dir.join("a.txt"), dir.join("a-2.txt")]);
+        assert_eq!(std::fs::read_to_string(&paths[1]).unwrap(), "again");
+        let mode = std::fs::metadata(&dir).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o700);
+        assert!(!stale.exists());
+
+        std::fs::create_dir(&stale).unwrap();
+        std::fs::File::open(&stale)
+            .unwrap()
+            .set_modified(old)
+            .unwrap();
+        let bare = attachment_dir(root.path(), "m2");
+        assert!(save_attachments(&bare, &[]).is_empty());
+        assert!(!stale.exists());
+        assert!(!bare.exists());
+    }
 }
@@ -0,0 +1,323 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use feed_rs::model::Entry;
+use tokio::sync::mpsc;
+
+use super::{Channel, IncomingMessage};
+use crate::store::Store;
+
+/// Flatten the HTML most feeds put in summaries into plain text.
+fn strip_html(html: &str) -> String {
+    let mut out = String::new();
+    let mut in_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => {
+                in_tag = false;
+                out.push(' ');
+            }
+            _ if !in_tag => out.push(c),
+            _ => {}
+        }
+    }
+    let out = out
+        .replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&");
+    out.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// One message per entry; the feed URL is the sender and the entry's
+/// fields are available as `{% meta:title %}`, `{% meta:link %}`, etc.
+fn entry_to_message(url: &str, feed_title: &str, entry: &Entry) -> IncomingMessage {
+    let title = entry
+        .title
+        .as_ref()
+        .map(|t| strip_html(&t.content))
+        .unwrap_or_default();
+    let link = entry
+        .links
+        .first()
+        .map(|l| l.href.clone())
+        .unwrap_or_default();
+    let summary = entry
+        .summary
+        .as_ref()
+        .map(|s| s.content.clone())
+        .or_else(|| entry.content.as_ref().and_then(|c| c.body.clone()))
+        .map(|s| strip_html(&s))
+        .unwrap_or_default();
+    let published = entry
+        .published
+        .or(entry.updated)
+        .map(|d| d.to_rfc3339())
+        .unwrap_or_default();
+
+    let text = [title.as_str(), link.as_str(), "", summary.as_str()]
+        .join("\n")
+        .trim()
+        .to_string();
+
+    let mut meta = HashMap::new();
+    meta.insert("feed".to_string(), feed_title.to_string());
+    meta.insert("guid".to_string(), entry.id.clone());
+    meta.insert("title".to_string(), title);
+    meta.insert("link".to_string(), link);
+    meta.insert("summary".to_string(), summary);
+    meta.insert("published".to_string(), published);
+    IncomingMessage {
+        channel: "feed".to_string(),
+        sender: url.to_string(),
+        text,
+        meta,
+        ..Default::default()
+    }
+}
+
+/// Entries of `feed` not seen before, oldest first. A feed we've never
+/// polled is only recorded, so adding one doesn't replay its whole history;
+/// that holds for a feed that starts out empty too.
+fn new_entries(
+    store: &Store,
+    url: &str,
+    feed: &feed_rs::model::Feed,
+) -> crate::error::Result<Vec<IncomingMessage>> {
+    let first_poll = !store.has_feed(url)?;
+    let feed_title = feed
+        .title
+        .as_ref()
+        .map(|t| strip_html(&t.content))
+        .unwrap_or_default();
+
+    let mut fresh = Vec::new();
+    for entry in &feed.entries {
+        if store.record_feed_item(url, &entry.id)? && !first_poll {
+            fresh.push(entry_to_message(url, &feed_title, entry));
+        }
+    }
+    store.record_feed_poll(url)?;
+    // Feeds list newest first
+    fresh.reverse();
+    Ok(fresh)
+}
+
+/// Polls RSS/Atom feeds and emits each new entry once.
+pub struct FeedChannel {
+    urls: Vec<String>,
+    poll_interval: Duration,
+    db_path: PathBuf,
+    client: reqwest::Client,
+}
+
+impl FeedChannel {
+    pub fn new(urls: Vec<String>, poll_interval: Duration, db_path: PathBuf) -> Self {
+        Self {
+            urls,
+            poll_interval,
+            db_path,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    async fn fetch(&self, url: &str) -> crate::error::Result<feed_rs::model::Feed> {
+        let err = |e: reqwest::Error| crate::error::Error::Channel(format!("feed {url}: {e}"));
+        let body = self
+            .client
+            .get(url)
+            .header("User-Agent", concat!("vatic/", env!("CARGO_PKG_VERSION")))
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(err)?
+            .bytes()
+            .await
+            .map_err(err)?;
+        feed_rs::parser::parse(&body[..])
+            .map_err(|e| crate::error::Error::Channel(format!("feed {url}: {e}")))
+    }
+
+    /// Poll every feed once. Returns false when the receiver is gone.
+    async fn poll(&self, tx: &mpsc::Sender<IncomingMessage>) -> crate::error::Result<bool> {
+        for url in &self.urls {
+            let feed = match self.fetch(url).await {
+                Ok(feed) => feed,
+                Err(e) => {
+                    tracing::warn!("{e}");
+                    continue;
+                }
+            };
+            let fresh = {
+                let store = Store::open(&self.db_path)?;
+                new_entries(&store, url, &feed)?
+            };
+            if !fresh.is_empty() {
+                tracing::info!("feed {}: {} new entries", url, fresh.len());
+            }
+            for msg in fresh {
+                if tx.send(msg).await.is_err() {
+                    return Ok(false);
+                }
+            }
+        }
+        Ok(true)
+    }
+}
+
+#[async_trait::async_trait]
+impl Channel for FeedChannel {
+    async fn start(&self, tx: mpsc::Sender<IncomingMessage>) -> crate::error::Result<()> {
+        let mut interval = tokio::time::interval(self.poll_interval);
+        loop {
+            interval.tick().await;
+            match self.poll(&tx).await {
+                Ok(true) => {}
+                Ok(false) => return Ok(()), // receiver dropped
+                Err(e) => tracing::error!("feed poll failed: {e}"),
+            }
+        }
+    }
+
+    async fn send(&self, to: &str, _message: &str) -> crate::error::Result<()> {
+        tracing::debug!("feed {to} can't be replied to, dropping reply");
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        "feed"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::routing::get;
+    use axum::Router;
+    use std::sync::{Arc, Mutex};
+
+    fn rss(items: &[(&str, &str)]) -> String {
+        let items: String = items
+            .iter()
+            .map(|(guid, title)| {
+                format!(
+                    "<item><guid>{guid}</guid><title>{title}</title>\
+                     <link>https://blog.example.org/{guid}</link>\
+                     <description>&lt;p&gt;About {title} &amp;amp; more&lt;/p&gt;</description>\
+                     <pubDate>Mon, 05 Jan 2026 10:00:00 GMT</pubDate></item>"
+                )
+            })
+            .collect();
+        format!(
+            "<?xml version=\"1.0\"?><rss version=\"2.0\"><channel>\
+             <title>Example Blog</title>{items}</channel></rss>"
+        )
+    }
+
+    fn parse(xml: &str) -> feed_rs::model::Feed {
+        feed_rs::parser::parse(xml.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn test_feed_channel_name() {
+        let ch = FeedChannel::new(vec![], Duration::from_secs(60), PathBuf::new());
+        assert_eq!(ch.name(), "feed");
+    }
+
+    #[test]
+    fn test_strip_html() {
+        assert_eq!(
+            strip_html("<p>Hello <b>world</b></p>\n<p>a &amp; b</p>"),
+            "Hello world a & b"
+        );
+    }
+
+    #[test]
+    fn test_entry_fields() {
+        let feed = parse(&rss(&[("p1", "Release 1.0")]));
+        let msg = entry_to_message(
+            "https://blog.example.org/rss",
+            "Example Blog",
+            &feed.entries[0],
+        );
+        assert_eq!(msg.channel, "feed");
+        assert_eq!(msg.sender, "https://blog.example.org/rss");
+        assert_eq!(msg.meta["title"], "Release 1.0");
+        assert_eq!(msg.meta["link"], "https://blog.example.org/p1");
+        assert_eq!(msg.meta["summary"], "About Release 1.0 & more");
+        assert_eq!(msg.meta["published"], "2026-01-05T10:00:00+00:00");
+        assert_eq!(msg.meta["guid"], "p1");
+        assert_eq!(msg.meta["feed"], "Example Blog");
+        assert!(msg
+            .text
+            .starts_with("Release 1.0\nhttps://blog.example.org/p1\n\n"));
+    }
+
+    #[test]
+    fn test_first_poll_seeds_then_dedupes() {
+        let store = Store::open_memory().unwrap();
+        let url = "https://blog.example.org/rss";
+
+        let first = new_entries(&store, url, &parse(&rss(&[("p1", "One")]))).unwrap();
+        assert!(first.is_empty());
+
+        let feed = parse(&rss(&[("p3", "Three"), ("p2", "Two"), ("p1", "One")]));
+        let second = new_entries(&store, url, &feed).unwrap();
+        let titles: Vec<&str> = second.iter().map(|m| m.meta["title"].as_str()).collect();
+        assert_eq!(titles, ["Two", "Three"]);
+
+        assert!(new_entries(&store, url, &feed).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_empty_first_poll_counts() {
+        let store = Store::open_memory().unwrap();
+        let url = "https://blog.example.org/rss";
+
+        assert!(new_entries(&store, url, &parse(&rss(&[])))
+            .unwrap()
+            .is_empty());
+        let feed = parse(&rss(&[("p1", "One")]));
+        let titles: Vec<String> = new_entries(&store, url, &feed)
+            .unwrap()
+            .into_iter()
+            .map(|m| m.meta["title"].clone())
+            .collect();
+        assert_eq!(titles, ["One"]);
+    }
+
+    #[tokio::test]
+    async fn test_poll_local_feed() {
+        let dir = tempfile::tempdir().unwrap();
+        let body = Arc::new(Mutex::new(rss(&[("p1", "One")])));
+
+        let served = Arc::clone(&body);
+        let app = Router::new().route(
+            "/rss",
+            get(move || async move { served.lock().unwrap().clone() }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/rss", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let ch = FeedChannel::new(
+            vec![url.clone()],
+            Duration::from_secs(60),
+            dir.path().join("vatic.db"),
+        );
+        let (tx, mut rx) = mpsc::channel(10);
+        assert!(ch.poll(&tx).await.unwrap());
+        assert!(rx.try_recv().is_err());
+
+        *body.lock().unwrap() = rss(&[("p2", "Two"), ("p1", "One")]);
+        assert!(ch.poll(&tx).await.unwrap());
+        let msg = rx.try_recv().unwrap();
+        assert_eq!(msg.sender, url);
+        assert_eq!(msg.meta["title"], "Two");
+        assert!(rx.try_recv().is_err());
+    }
+}
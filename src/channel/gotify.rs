@@ -0,0 +1,340 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use futures_util::StreamExt;
+use serde::Deserialize;
+use serde_json::json;
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::Message as WsMessage;
+
+use super::ntfy::PushOptions;
+use super::{Channel, IncomingMessage};
+
+/// Gotify pings the stream every 45s; anything longer is a dead connection.
+const READ_TIMEOUT: Duration = Duration::from_secs(120);
+
+#[derive(Debug, Deserialize)]
+struct GotifyMessage {
+    id: u64,
+    appid: u64,
+    message: String,
+    title: Option<String>,
+    priority: Option<u8>,
+}
+
+/// The sending application's id is the sender; replies always go out
+/// through our own application token.
+fn to_incoming(msg: GotifyMessage) -> Option<IncomingMessage> {
+    let text = msg.message.trim();
+    if text.is_empty() {
+        return None;
+    }
+    let mut meta = HashMap::new();
+    meta.insert("id".to_string(), msg.id.to_string());
+    meta.insert("title".to_string(), msg.title.unwrap_or_default());
+    meta.insert(
+        "priority".to_string(),
+        msg.priority.unwrap_or(0).to_string(),
+    );
+    Some(IncomingMessage {
+        channel: "gotify".to_string(),
+        sender: msg.appid.to_string(),
+        text: text.to_string(),
+        meta,
+        ..Default::default()
+    })
+}
+
+#[derive(Debug, Deserialize)]
+struct GotifyApplication {
+    id: u64,
+    token: String,
+}
+
+/// ntfy's 1–5 onto Gotify's 0–10, where 8 and up is high priority.
+fn gotify_priority(priority: u8) -> u8 {
+    match priority {
+        0 | 1 => 1,
+        2 => 3,
+        3 => 5,
+        4 => 8,
+        _ => 10,
+    }
+}
+
+/// `http://host` → `ws://host/stream?token=...`
+fn stream_url(server: &str, client_token: &str) -> String {
+    let ws = if let Some(rest) = server.strip_prefix("https://") {
+        format!("wss://{rest}")
+    } else if let Some(rest) = server.strip_prefix("http://") {
+        format!("ws://{rest}")
+    } else {
+        server.to_string()
+    };
+    format!("{ws}/stream?token={client_token}")
+}
+
+/// Gotify: the client WebSocket stream in, the application API out.
+pub struct GotifyChannel {
+    server: String,
+    app_token: String,
+    client_token: String,
+    push: PushOptions,
+    client: reqwest::Client,
+}
+
+impl GotifyChannel {
+    pub fn new(server: String, app_token: String, client_token: String, push: PushOptions) -> Self {
+        Self {
+            server: server.trim_end_matches('/').to_string(),
+            app_token,
+            client_token,
+            push,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Id of the application we send through, so its messages on the
+    /// stream can be skipped.
+    async fn own_app_id(&self) -> crate::error::Result<u64> {
+        let err = |e: reqwest::Error| {
+            crate::error::Error::Channel(format!("gotify application lookup failed: {e}"))
+        };
+        let apps: Vec<GotifyApplication> = self
+            .client
+            .get(format!("{}/application", self.server))
+            .header("X-Gotify-Key", &self.client_token)
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(err)?
+            .json()
+            .await
+            .map_err(err)?;
+        apps.into_iter()
+            .find(|app| app.token == self.app_token)
+            .map(|app| app.id)
+            .ok_or_else(|| {
+                crate::error::Error::Channel(
+                    "gotify application token doesn't belong to the client's user".to_string(),
+                )
+            })
+    }
+
+    async fn session(&self, tx: &mpsc::Sender<IncomingMessage>) -> crate::error::Result<bool> {
+        let own_app = self.own_app_id().await?;
+        let (mut ws, _) =
+            tokio_tungstenite::connect_async(stream_url(&self.server, &self.client_token))
+                .await
+                .map_err(|e| crate::error::Error::Channel(format!("gotify stream: {e}")))?;
+        tracing::info!("gotify stream connected to {}", self.server);
+
+        loop {
+            let frame = match tokio::time::timeout(READ_TIMEOUT, ws.next()).await {
+                Ok(Some(frame)) => frame
+                    .map_err(|e| crate::error::Error::Channel(format!("gotify stream: {e}")))?,
+                Ok(None) => return Ok(false),
+                Err(_) => {
+                    return Err(crate::error::Error::Channel(
+                        "gotify stream timed out".to_string(),
+                    ))
+                }
+            };
+            let WsMessage::Text(text) = frame else {
+                continue;
+            };
+            let Ok(msg) = serde_json::from_str::<GotifyMessage>(&text) else {
+                continue;
+            };
+            if msg.appid == own_app {
+                continue;
+            }
+            if let Some(incoming) = to_incoming(msg) {
+                if tx.send(incoming).await.is_err() {
+                    return Ok(true); // receiver dropped
+                }
+            }
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Channel for GotifyChannel {
+    async fn start(&self, tx: mpsc::Sender<IncomingMessage>) -> crate::error::Result<()> {
+        if self.session(&tx).await? {
+            return Ok(()); // receiver dropped
+        }
+        Err(crate::error::Error::Channel(
+            "gotify stream ended".to_string(),
+        ))
+    }
+
+    async fn send(&self, _to: &str, message: &str) -> crate::error::Result<()> {
+        let mut body = json!({
+            "message": message,
+            "extras": { "client::display": { "contentType": "text/markdown" } },
+        });
+        if let Some(title) = &self.push.title {
+            body["title"] = json!(title);
+        }
+        if let Some(priority) = self.push.priority {
+            body["priority"] = json!(gotify_priority(priority));
+        }
+        if let Some(click) = &self.push.click {
+            body["extras"]["client::notification"] = json!({ "click": { "url": click } });
+        }
+
+        self.client
+            .post(format!("{}/message", self.server))
+            .header("X-Gotify-Key", &self.app_token)
+            .json(&body)
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| crate::error::Error::Channel(format!("gotify send failed: {e}")))?;
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        "gotify"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::extract::ws::{Message as AxumWs, WebSocketUpgrade};
+    use axum::extract::Query;
+    use axum::http::HeaderMap;
+    use axum::routing::{any, get, post};
+    use axum::{Json, Router};
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn test_gotify_channel_name() {
+        let ch = GotifyChannel::new(
+            "http://g".into(),
+            "a".into(),
+            "c".into(),
+            Default::default(),
+        );
+        assert_eq!(ch.name(), "gotify");
+    }
+
+    #[test]
+    fn test_stream_url() {
+        assert_eq!(
+            stream_url("https://push.example.org", "C1"),
+            "wss://push.example.org/stream?token=C1"
+        );
+        assert_eq!(
+            stream_url("http://127.0.0.1:8080", "C1"),
+            "ws://127.0.0.1:8080/stream?token=C1"
+        );
+    }
+
+    #[test]
+    fn test_gotify_priority() {
+        assert_eq!(gotify_priority(1), 1);
+        assert_eq!(gotify_priority(3), 5);
+        assert_eq!(gotify_priority(4), 8);
+        assert_eq!(gotify_priority(5), 10);
+        assert_eq!(gotify_priority(9), 10);
+    }
+
+    #[test]
+    fn test_message_to_incoming() {
+        let msg: GotifyMessage = serde_json::from_str(
+            r#"{"id":7,"appid":3,"message":"door opened","title":"frontdoor","priority":8,"date":"2026-01-01T03:00:00Z"}"#,
+        )
+        .unwrap();
+        let incoming = to_incoming(msg).unwrap();
+        assert_eq!(incoming.sender, "3");
+        assert_eq!(incoming.text, "door opened");
+        assert_eq!(incoming.meta["title"], "frontdoor");
+        assert_eq!(incoming.meta["priority"], "8");
+    }
+
+    /// Local stand-in for Gotify: the stream carries a message from our own
+    /// application and one from another; the message endpoint records what
+    /// we posted.
+    #[tokio::test]
+    async fn test_stream_and_send() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let posted: Arc<Mutex<Vec<(String, serde_json::Value)>>> = Arc::default();
+
+        let posted_clone = Arc::clone(&posted);
+        let app = Router::new()
+            .route(
+                "/message",
+                post(
+                    move |headers: HeaderMap, Json(body): Json<serde_json::Value>| async move {
+                        let key = headers["x-gotify-key"].to_str().unwrap().to_string();
+                        posted_clone.lock().unwrap().push((key, body));
+                        Json(json!({ "id": 41 }))
+                    },
+                ),
+            )
+            .route(
+                "/application",
+                get(|headers: HeaderMap| async move {
+                    assert_eq!(headers["x-gotify-key"], "client");
+                    Json(json!([
+                        { "id": 1, "token": "app", "name": "vatic" },
+                        { "id": 2, "token": "other", "name": "ups" },
+                    ]))
+                }),
+            )
+            .route(
+                "/stream",
+                any(
+                    |Query(q): Query<HashMap<String, String>>, ws: WebSocketUpgrade| async move {
+                        assert_eq!(q["token"], "client");
+                        ws.on_upgrade(|mut socket| async move {
+                            for frame in [
+                                json!({ "id": 41, "appid": 1, "message": "echo" }),
+                                json!({ "id": 42, "appid": 2, "message": "power spiked" }),
+                            ] {
+                                let text = frame.to_string();
+                                socket.send(AxumWs::Text(text.into())).await.unwrap();
+                            }
+                            while socket.next().await.is_some() {}
+                        })
+                    },
+                ),
+            );
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let push = PushOptions {
+            title: Some("vatic".into()),
+            priority: Some(5),
+            tags: vec![],
+            click: Some("https://example.org".into()),
+        };
+        let ch = Arc::new(GotifyChannel::new(
+            format!("http://{addr}"),
+            "app".into(),
+            "client".into(),
+            push,
+        ));
+        let (tx, mut rx) = mpsc::channel(10);
+        let runner = Arc::clone(&ch);
+        tokio::spawn(async move { runner.start(tx).await });
+        ch.send("", "on it").await.unwrap();
+        let msg = rx.recv().await.unwrap();
+        assert_eq!(msg.sender, "2");
+        assert_eq!(msg.text, "power spiked");
+
+        let posted = posted.lock().unwrap();
+        let (key, body) = &posted[0];
+        assert_eq!(key, "app");
+        assert_eq!(body["message"], "on it");
+        assert_eq!(body["title"], "vatic");
+        assert_eq!(body["priority"], 10);
+        assert_eq!(
+            body["extras"]["client::notification"]["click"]["url"],
+            "https://example.org"
+        );
+    }
+}
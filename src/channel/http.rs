@@ -0,0 +1,569 @@
+use std::collections::{HashMap, VecDeque};
+use std::io::{Read, Write};
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use axum::extract::ws::{Message as WsMessage, WebSocket, WebSocketUpgrade};
+use axum::extract::{Query, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{Html, IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use futures_util::{SinkExt, StreamExt};
+use serde::Deserialize;
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+
+use super::webhook::constant_time_eq;
+use super::{Channel, IncomingMessage};
+
+const INDEX_HTML: &str = include_str!("http.html");
+
+/// Replies kept for a session while no socket is connected.
+const MAX_BACKLOG: usize = 50;
+/// Sessions without a socket are forgotten, backlog and all, after this
+/// long without a message, poll or connection.
+const SESSION_IDLE: Duration = Duration::from_secs(24 * 60 * 60);
+
+struct Session {
+    sockets: Vec<mpsc::UnboundedSender<String>>,
+    backlog: VecDeque<String>,
+    last_seen: Instant,
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Self {
+            sockets: Vec::new(),
+            backlog: VecDeque::new(),
+            last_seen: Instant::now(),
+        }
+    }
+}
+
+impl Session {
+    fn idle(&self, now: Instant) -> bool {
+        self.sockets.iter().all(|tx| tx.is_closed())
+            && now.saturating_duration_since(self.last_seen) >= SESSION_IDLE
+    }
+}
+
+type Sessions = Arc<Mutex<HashMap<String, Session>>>;
+
+/// The session for `id`, marked as seen. Creating one first drops the idle
+/// ones, so ids the browser made up don't pile up forever.
+fn touch<'a>(
+    sessions: &'a mut HashMap<String, Session>,
+    id: &str,
+    now: Instant,
+) -> &'a mut Session {
+    if !sessions.contains_key(id) {
+        sessions.retain(|_, s| !s.idle(now));
+    }
+    let session = sessions.entry(id.to_string()).or_default();
+    session.last_seen = now;
+    session
+}
+
+/// Session ids come from the browser, so keep them to something log-safe.
+fn valid_session(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= 64
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// The token from `path`, or a new random one written there (owner-only)
+/// when it doesn't exist yet.
+pub fn load_or_create_token(path: &Path) -> std::io::Result<String> {
+    use std::os::unix::fs::OpenOptionsExt;
+    match std::fs::read_to_string(path) {
+        Ok(token) if !token.trim().is_empty() => return Ok(token.trim().to_string()),
+        Ok(_) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => return Err(e),
+    }
+    let mut bytes = [0u8; 24];
+    std::fs::File::open("/dev/urandom")?.read_exact(&mut bytes)?;
+    let token = hex::encode(bytes);
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(path)?
+        .write_all(token.as_bytes())?;
+    Ok(token)
+}
+
+/// Whether `host` (a `Host` header) names the address we listen on. A
+/// wildcard bind accepts any name; loopback also answers to `localhost`.
+fn host_allowed(addr: SocketAddr, host: &str) -> bool {
+    if addr.ip().is_unspecified() {
+        return true;
+    }
+    if host == addr.to_string() {
+        return true;
+    }
+    addr.ip().is_loopback() && host == format!("localhost:{}", addr.port())
+}
+
+struct WebState {
+    token: String,
+    addr: SocketAddr,
+    sessions: Sessions,
+    tx: mpsc::Sender<IncomingMessage>,
+}
+
+impl WebState {
+    /// Bearer header for API clients, `?token=` for the browser socket.
+    fn authorized(&self, headers: &HeaderMap, query_token: Option<&str>) -> bool {
+        headers
+            .get(axum::http::header::AUTHORIZATION)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|h| h.strip_prefix("Bearer "))
+            .or(query_token)
+            .is_some_and(|t| constant_time_eq(t.trim().as_bytes(), self.token.as_bytes()))
+    }
+
+    /// Guards against other sites' pages (and DNS rebinding) talking to
+    /// the local server: `Host` must be our address, and a browser's
+    /// `Origin` must be that same host.
+    fn same_origin(&self, headers: &HeaderMap) -> bool {
+        let header = |name| headers.get(name).and_then(|v| v.to_str().ok());
+        let Some(host) = header(axum::http::header::HOST) else {
+            return false;
+        };
+        if !host_allowed(self.addr, host) {
+            return false;
+        }
+        header(axum::http::header::ORIGIN).is_none_or(|origin| {
+            origin
+                .strip_prefix("http://")
+                .or_else(|| origin.strip_prefix("https://"))
+                == Some(host)
+        })
+    }
+
+    /// Check origin and token, in that order.
+    fn check(&self, headers: &HeaderMap, query_token: Option<&str>) -> Result<(), StatusCode> {
+        if !self.same_origin(headers) {
+            return Err(StatusCode::FORBIDDEN);
+        }
+        if !self.authorized(headers, query_token) {
+            return Err(StatusCode::UNAUTHORIZED);
+        }
+        Ok(())
+    }
+
+    async fn submit(&self, session: &str, text: &str) -> bool {
+        touch(&mut self.sessions.lock().unwrap(), session, Instant::now());
+        let msg = IncomingMessage {
+            channel: "http".to_string(),
+            sender: session.to_string(),
+            text: text.to_string(),
+            ..Default::default()
+        };
+        self.tx.send(msg).await.is_ok()
+    }
+}
+
+#[derive(Deserialize)]
+struct SessionQuery {
+    session: String,
+    token: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct PostMessage {
+    session: String,
+    text: String,
+}
+
+async fn index() -> Html<&'static str> {
+    Html(INDEX_HTML)
+}
+
+async fn open_socket(
+    State(state): State<Arc<WebState>>,
+    Query(q): Query<SessionQuery>,
+    headers: HeaderMap,
+    ws: WebSocketUpgrade,
+) -> Response {
+    if let Err(status) = state.check(&headers, q.token.as_deref()) {
+        return status.into_response();
+    }
+    if !valid_session(&q.session) {
+        return (StatusCode::BAD_REQUEST, "invalid session id").into_response();
+    }
+    ws.on_upgrade(move |socket| run_socket(state, q.session, socket))
+}
+
+async fn run_socket(state: Arc<WebState>, session: String, socket: WebSocket) {
+    let (mut sink, mut stream) = socket.split();
+    let (out_tx, mut out_rx) = mpsc::unbounded_channel::<String>();
+
+    // Attach to the session and pick up replies that arrived while away
+    let backlog: Vec<String> = {
+        let mut sessions = state.sessions.lock().unwrap();
+        let s = touch(&mut sessions, &session, Instant::now());
+        s.sockets.push(out_tx);
+        s.backlog.drain(..).collect()
+    };
+
+    let writer = tokio::spawn(async move {
+        for text in backlog {
+            if sink.send(WsMessage::Text(text.into())).await.is_err() {
+                return;
+            }
+        }
+        while let Some(text) = out_rx.recv().await {
+            if sink.send(WsMessage::Text(text.into())).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    tracing::debug!("http session {} connected", session);
+
+    while let Some(Ok(frame)) = stream.next().await {
+        match frame {
+            WsMessage::Text(text) => {
+                let text = text.as_str().trim();
+                if text.is_empty() {
+                    continue;
+                }
+                if !state.submit(&session, text).await {
+                    break; // receiver dropped
+                }
+            }
+            WsMessage::Close(_) => break,
+            _ => {}
+        }
+    }
+
+    writer.abort();
+    let _ = writer.await;
+    if let Some(s) = state.sessions.lock().unwrap().get_mut(&session) {
+        s.sockets.retain(|tx| !tx.is_closed());
+        s.last_seen = Instant::now();
+    }
+    tracing::debug!("http session {} disconnected", session);
+}
+
+async fn post_message(
+    State(state): State<Arc<WebState>>,
+    headers: HeaderMap,
+    Json(body): Json<PostMessage>,
+) -> Response {
+    if let Err(status) = state.check(&headers, None) {
+        return status.into_response();
+    }
+    if !valid_session(&body.session) {
+        return (StatusCode::BAD_REQUEST, "invalid session id").into_response();
+    }
+    let text = body.text.trim();
+    if text.is_empty() {
+        return (StatusCode::BAD_REQUEST, "empty message").into_response();
+    }
+    if !state.submit(&body.session, text).await {
+        return StatusCode::SERVICE_UNAVAILABLE.into_response();
+    }
+    (
+        StatusCode::ACCEPTED,
+        Json(serde_json::json!({ "session": body.session })),
+    )
+        .into_response()
+}
+
+/// Drain replies for clients that poll instead of holding a socket.
+async fn get_messages(
+    State(state): State<Arc<WebState>>,
+    Query(q): Query<SessionQuery>,
+    headers: HeaderMap,
+) -> Response {
+    if let Err(status) = state.check(&headers, q.token.as_deref()) {
+        return status.into_response();
+    }
+    if !valid_session(&q.session) {
+        return (StatusCode::BAD_REQUEST, "invalid session id").into_response();
+    }
+    let replies: Vec<String> = state
+        .sessions
+        .lock()
+        .unwrap()
+        .get_mut(&q.session)
+        .map(|s| {
+            s.last_seen = Instant::now();
+            s.backlog.drain(..).collect()
+        })
+        .unwrap_or_default();
+    Json(replies).into_response()
+}
+
+/// Local chat UI plus a JSON/WebSocket API. Each browser is its own sender.
+pub struct HttpChannel {
+    listen: String,
+    token: String,
+    sessions: Sessions,
+}
+
+impl HttpChannel {
+    pub fn new(listen: String, token: String) -> Self {
+        Self {
+            listen,
+            token,
+            sessions: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    async fn serve(
+        &self,
+        listener: TcpListener,
+        tx: mpsc::Sender<IncomingMessage>,
+    ) -> crate::error::Result<()> {
+        let addr = listener
+            .local_addr()
+            .map_err(|e| crate::error::Error::Channel(format!("http server failed: {e}")))?;
+        let state = Arc::new(WebState {
+            token: self.token.clone(),
+            addr,
+            sessions: Arc::clone(&self.sessions),
+            tx,
+        });
+        let app = Router::new()
+            .route("/", get(index))
+            .route("/ws", get(open_socket))
+            .route("/api/messages", get(get_messages).post(post_message))
+            .with_state(state);
+
+        axum::serve(listener, app)
+            .await
+            .map_err(|e| crate::error::Error::Channel(format!("http server failed: {e}")))
+    }
+}
+
+#[async_trait::async_trait]
+impl Channel for HttpChannel {
+    async fn start(&self, tx: mpsc::Sender<IncomingMessage>) -> crate::error::Result<()> {
+        let listener = TcpListener::bind(&self.listen).await.map_err(|e| {
+            crate::error::Error::Channel(format!("cannot listen on {}: {e}", self.listen))
+        })?;
+        tracing::info!("http chat on http://{}", self.listen);
+        self.serve(listener, tx).await
+    }
+
+    async fn send(&self, to: &str, message: &str) -> crate::error::Result<()> {
+        let mut sessions = self.sessions.lock().unwrap();
+        let Some(s) = sessions.get_mut(to) else {
+            tracing::debug!("no http session {to}, dropping reply");
+            return Ok(());
+        };
+        s.sockets.retain(|tx| tx.send(message.to_string()).is_ok());
+        if s.sockets.is_empty() {
+            s.backlog.push_back(message.to_string());
+            while s.backlog.len() > MAX_BACKLOG {
+                s.backlog.pop_front();
+            }
+        }
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        "http"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOKEN: &str = "t0k";
+
+    async fn spawn() -> (Arc<HttpChannel>, String, mpsc::Receiver<IncomingMessage>) {
+        let ch = Arc::new(HttpChannel::new("127.0.0.1:0".into(), TOKEN.into()));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let (tx, rx) = mpsc::channel(10);
+        let server = Arc::clone(&ch);
+        tokio::spawn(async move { server.serve(listener, tx).await });
+        (ch, base, rx)
+    }
+
+    #[test]
+    fn test_http_channel_name() {
+        assert_eq!(
+            HttpChannel::new("127.0.0.1:0".into(), TOKEN.into()).name(),
+            "http"
+        );
+    }
+
+    #[test]
+    fn test_valid_session() {
+        assert!(valid_session("0b7c6a52-9f1e-4d7a-b3a1-2f4c1d9e8a77"));
+        assert!(!valid_session(""));
+        assert!(!valid_session("../etc"));
+        assert!(!valid_session(&"a".repeat(65)));
+    }
+
+    #[test]
+    fn test_idle_sessions_expire() {
+        let mut sessions = HashMap::new();
+        let start = Instant::now();
+        touch(&mut sessions, "old", start);
+        let (open_tx, _open_rx) = mpsc::unbounded_channel();
+        touch(&mut sessions, "open", start).sockets.push(open_tx);
+
+        // Existing sessions are only touched
+        let later = start + SESSION_IDLE + Duration::from_secs(1);
+        touch(&mut sessions, "open", later);
+        assert!(sessions.contains_key("old"));
+
+        touch(&mut sessions, "new", later);
+        assert!(!sessions.contains_key("old"));
+        assert!(sessions.contains_key("open"));
+        assert!(sessions.contains_key("new"));
+    }
+
+    #[test]
+    fn test_host_allowed() {
+        let local: SocketAddr = "127.0.0.1:8788".parse().unwrap();
+        assert!(host_allowed(local, "127.0.0.1:8788"));
+        assert!(host_allowed(local, "localhost:8788"));
+        assert!(!host_allowed(local, "evil.example:8788"));
+        assert!(!host_allowed(local, "127.0.0.1:9999"));
+        let any: SocketAddr = "0.0.0.0:8788".parse().unwrap();
+        assert!(host_allowed(any, "box.lan:8788"));
+    }
+
+    #[test]
+    fn test_load_or_create_token() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("http").join("token");
+        let token = load_or_create_token(&path).unwrap();
+        assert_eq!(token.len(), 48);
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        assert_eq!(load_or_create_token(&path).unwrap(), token);
+    }
+
+    #[tokio::test]
+    async fn test_send_buffers_without_socket() {
+        let (ch, _base, _rx) = spawn().await;
+        ch.sessions
+            .lock()
+            .unwrap()
+            .insert("s1".into(), Session::default());
+        for i in 0..MAX_BACKLOG + 5 {
+            ch.send("s1", &i.to_string()).await.unwrap();
+        }
+        ch.send("nobody", "hi").await.unwrap();
+        let sessions = ch.sessions.lock().unwrap();
+        let backlog = &sessions["s1"].backlog;
+        assert_eq!(backlog.len(), MAX_BACKLOG);
+        assert_eq!(backlog.front().map(String::as_str), Some("5"));
+        assert!(!sessions.contains_key("nobody"));
+    }
+
+    #[tokio::test]
+    async fn test_serves_chat_page() {
+        let (_ch, base, _rx) = spawn().await;
+        let body = reqwest::get(&base).await.unwrap().text().await.unwrap();
+        assert!(body.contains("<title>vatic</title>"));
+    }
+
+    #[tokio::test]
+    async fn test_json_api_roundtrip() {
+        let (ch, base, mut rx) = spawn().await;
+        let client = reqwest::Client::new();
+
+        let resp = client
+            .post(format!("{base}/api/messages"))
+            .bearer_auth(TOKEN)
+            .json(&serde_json::json!({"session": "abc", "text": " hello "}))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), 202);
+
+        let msg = rx.recv().await.unwrap();
+        assert_eq!(msg.channel, "http");
+        assert_eq!(msg.sender, "abc");
+        assert_eq!(msg.text, "hello");
+
+        ch.send("abc", "hi there").await.unwrap();
+        let replies: Vec<String> = client
+            .get(format!("{base}/api/messages?session=abc&token={TOKEN}"))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(replies, vec!["hi there"]);
+    }
+
+    #[tokio::test]
+    async fn test_rejects_bad_requests() {
+        let (_ch, base, _rx) = spawn().await;
+        let client = reqwest::Client::new();
+        let url = format!("{base}/api/messages");
+        let body = serde_json::json!({"session": "abc", "text": "hi"});
+
+        let resp = client.post(&url).json(&body).send().await.unwrap();
+        assert_eq!(resp.status(), 401);
+
+        let resp = client
+            .post(&url)
+            .bearer_auth(TOKEN)
+            .json(&serde_json::json!({"session": "abc", "text": "  "}))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), 400);
+
+        let resp = client
+            .get(format!("{url}?session=../x&token={TOKEN}"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), 400);
+
+        // Another site's page, or a rebound DNS name
+        let resp = client
+            .post(&url)
+            .bearer_auth(TOKEN)
+            .header("Origin", "http://evil.example")
+            .json(&body)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), 403);
+        let resp = client
+            .post(&url)
+            .bearer_auth(TOKEN)
+            .header("Host", "evil.example")
+            .json(&body)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), 403);
+
+        let resp = client
+            .post(&url)
+            .bearer_auth(TOKEN)
+            .header("Origin", &base)
+            .json(&body)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), 202);
+    }
+}
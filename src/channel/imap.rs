@@ -0,0 +1,390 @@
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_imap::types::Fetch;
+use futures_util::TryStreamExt;
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::TcpStream;
+use tokio::sync::mpsc;
+
+use super::email::{
+    attachment_dir, format_email_text, parse_email, reply_headers, save_attachments, ReplyTracker,
+};
+use super::{Channel, IncomingMessage};
+
+/// Fallback poll when the server doesn't advertise IDLE.
+const POLL_INTERVAL: Duration = Duration::from_secs(60);
+
+fn imap_err(context: &str, e: impl std::fmt::Display) -> crate::error::Error {
+    crate::error::Error::Channel(format!("imap {context}: {e}"))
+}
+
+/// Connection details, resolved from the channel config and `secrets.toml`.
+#[derive(Clone)]
+pub struct ImapSettings {
+    pub host: String,
+    pub port: u16,
+    pub user: String,
+    pub password: String,
+    pub mailbox: String,
+    pub smtp_host: String,
+    pub smtp_port: u16,
+    pub from: String,
+    /// Implicit TLS for IMAP, TLS/STARTTLS for SMTP. Off only for local test servers.
+    pub tls: bool,
+    /// Where attachments are saved, `<data dir>/attachments/<channel>`.
+    pub attachments: PathBuf,
+}
+
+/// Last UID handed to the daemon, only valid for one UIDVALIDITY.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct UidCursor {
+    validity: u32,
+    last: u32,
+}
+
+/// The UID SEARCH to run and the UID floor to filter results by.
+/// Without a usable cursor we fall back to unread mail.
+fn search_query(cursor: Option<UidCursor>, validity: u32) -> (String, u32) {
+    match cursor {
+        Some(c) if c.validity == validity => (format!("UID {}:*", c.last + 1), c.last),
+        _ => ("UNSEEN".to_string(), 0),
+    }
+}
+
+/// Email over IMAP IDLE (push) with replies sent over SMTP.
+pub struct ImapChannel {
+    settings: ImapSettings,
+    cursor: Arc<Mutex<Option<UidCursor>>>,
+    replies: Arc<Mutex<ReplyTracker>>,
+}
+
+impl ImapChannel {
+    pub fn new(settings: ImapSettings) -> Self {
+        Self {
+            settings,
+            cursor: Arc::new(Mutex::new(None)),
+            replies: Arc::new(Mutex::new(ReplyTracker::default())),
+        }
+    }
+
+    /// Connect, log in and stay in the IDLE loop until the session breaks.
+    /// Returns `Ok` only when the receiver is gone.
+    async fn connect(&self, tx: &mpsc::Sender<IncomingMessage>) -> crate::error::Result<()> {
+        let s = &self.settings;
+        let tcp = TcpStream::connect((s.host.as_str(), s.port))
+            .await
+            .map_err(|e| imap_err("connect failed", e))?;
+
+        if s.tls {
+            let connector = tokio_native_tls::native_tls::TlsConnector::new()
+                .map_err(|e| imap_err("tls setup failed", e))?;
+            let stream = tokio_native_tls::TlsConnector::from(connector)
+                .connect(&s.host, tcp)
+                .await
+                .map_err(|e| imap_err("tls handshake failed", e))?;
+            self.session(stream, tx).await
+        } else {
+            self.session(tcp, tx).await
+        }
+    }
+
+    async fn session<T>(
+        &self,
+        stream: T,
+        tx: &mpsc::Sender<IncomingMessage>,
+    ) -> crate::error::Result<()>
+    where
+        T: AsyncRead + AsyncWrite + Unpin + std::fmt::Debug + Send,
+    {
+        let mut client = async_imap::Client::new(stream);
+        client
+            .read_response()
+            .await
+            .map_err(|e| imap_err("greeting failed", e))?;
+
+        let mut session = client
+            .login(&self.settings.user, &self.settings.password)
+            .await
+            .map_err(|(e, _)| imap_err("login failed", e))?;
+
+        let idle_supported = session
+            .capabilities()
+            .await
+            .map(|caps| caps.has_str("IDLE"))
+            .unwrap_or(false);
+
+        let mailbox = session
+            .select(&self.settings.mailbox)
+            .await
+            .map_err(|e| imap_err("select failed", e))?;
+        let validity = mailbox.uid_validity.unwrap_or(0);
+
+        tracing::info!(
+            "imap connected as {} ({})",
+            self.settings.user,
+            if idle_supported { "idle" } else { "polling" }
+        );
+
+        // First pass after (re)connect: anything unread, then continue from UIDNEXT
+        let mut floor = mailbox.uid_next.map(|n| n.saturating_sub(1)).unwrap_or(0);
+
+        loop {
+            let cursor = *self.cursor.lock().unwrap();
+            let (query, last) = search_query(cursor, validity);
+            let mut uids: Vec<u32> = session
+                .uid_search(&query)
+                .await
+                .map_err(|e| imap_err("search failed", e))?
+                .into_iter()
+                .filter(|uid| *uid > last)
+                .collect();
+            uids.sort_unstable();
+
+            if !uids.is_empty() {
+                let set = uids
+                    .iter()
+                    .map(|u| u.to_string())
+                    .collect::<Vec<_>>()
+                    .join(",");
+                // BODY[] (not PEEK) marks the messages \Seen on the server
+                let fetched: Vec<Fetch> = session
+                    .uid_fetch(&set, "(UID BODY[])")
+                    .await
+                    .map_err(|e| imap_err("fetch failed", e))?
+                    .try_collect()
+                    .await
+                    .map_err(|e| imap_err("fetch failed", e))?;
+
+                for fetch in &fetched {
+                    let Some(uid) = fetch.uid else { continue };
+                    floor = floor.max(uid);
+                    if let Some(msg) = self.to_message(uid, fetch.body()) {
+                        if tx.send(msg).await.is_err() {
+                            let _ = session.logout().await;
+                            return Ok(()); // receiver dropped
+                        }
+                    }
+                }
+            }
+
+            *self.cursor.lock().unwrap() = Some(UidCursor {
+                validity,
+                last: floor.max(last),
+            });
+
+            if idle_supported {
+                let mut idle = session.idle();
+                idle.init().await.map_err(|e| imap_err("idle failed", e))?;
+                let (wait, stop) = idle.wait();
+                wait.await.map_err(|e| imap_err("idle failed", e))?;
+                drop(stop);
+                session = idle.done().await.map_err(|e| imap_err("idle failed", e))?;
+            } else {
+                tokio::time::sleep(POLL_INTERVAL).await;
+                session
+                    .noop()
+                    .await
+                    .map_err(|e| imap_err("noop failed", e))?;
+            }
+        }
+    }
+
+    fn to_message(&self, uid: u32, body: Option<&[u8]>) -> Option<IncomingMessage> {
+        let parsed = body.and_then(parse_email)?;
+        let Some(sender) = parsed.from.clone() else {
+            tracing::warn!("imap message {uid} has no From address, skipping");
+            return None;
+        };
+
+        let reply_to = self
+            .replies
+            .lock()
+            .unwrap()
+            .insert(&sender, parsed.reply.clone());
+
+        let dir = attachment_dir(
+            &self.settings.attachments,
+            &format!("{}-{uid}", self.settings.user),
+        );
+        Some(IncomingMessage {
+            channel: "imap".to_string(),
+            sender,
+            text: format_email_text(&parsed.subject, &parsed.body),
+            attachments: save_attachments(&dir, &parsed.attachments),
+            reply_to: Some(reply_to),
+            ..Default::default()
+        })
+    }
+
+    fn smtp_transport(&self) -> crate::error::Result<AsyncSmtpTransport<Tokio1Executor>> {
+        let s = &self.settings;
+        let builder = if !s.tls {
+            AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&s.smtp_host)
+        } else if s.smtp_port == 465 {
+            AsyncSmtpTransport::<Tokio1Executor>::relay(&s.smtp_host)
+                .map_err(|e| crate::error::Error::Channel(format!("smtp setup failed: {e}")))?
+        } else {
+            AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&s.smtp_host)
+                .map_err(|e| crate::error::Error::Channel(format!("smtp setup failed: {e}")))?
+        };
+
+        Ok(builder
+            .port(s.smtp_port)
+            .credentials(Credentials::new(s.user.clone(), s.password.clone()))
+            .build())
+    }
+}
+
+#[async_trait::async_trait]
+impl Channel for ImapChannel {
+    async fn start(&self, tx: mpsc::Sender<IncomingMessage>) -> crate::error::Result<()> {
+        self.connect(&tx).await
+    }
+
+    async fn send(&self, to: &str, message: &str) -> crate::error::Result<()> {
+        let (to, reply) = self.replies.lock().unwrap().resolve(to);
+        let headers = reply_headers(reply.as_ref());
+
+        let from: Mailbox = self.settings.from.parse().map_err(|e| {
+            crate::error::Error::Channel(format!("invalid from '{}': {e}", self.settings.from))
+        })?;
+        let to_mailbox: Mailbox = to
+            .parse()
+            .map_err(|e| crate::error::Error::Channel(format!("invalid recipient '{to}': {e}")))?;
+
+        let mut builder = Message::builder()
+            .from(from)
+            .to(to_mailbox)
+            .subject(headers.subject);
+        if let Some(id) = headers.in_reply_to {
+            builder = builder.in_reply_to(id);
+        }
+        if let Some(refs) = headers.references {
+            builder = builder.references(refs);
+        }
+        let email = builder
+            .body(message.to_string())
+            .map_err(|e| crate::error::Error::Channel(format!("cannot build email: {e}")))?;
+
+        self.smtp_transport()?
+            .send(email)
+            .await
+            .map_err(|e| crate::error::Error::Channel(format!("smtp send failed: {e}")))?;
+
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        "imap"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings() -> ImapSettings {
+        ImapSettings {
+            host: "imap.example.com".into(),
+            port: 993,
+            user: "bot@example.com".into(),
+            password: "secret".into(),
+            mailbox: "INBOX".into(),
+            smtp_host: "smtp.example.com".into(),
+            smtp_port: 465,
+            from: "bot@example.com".into(),
+            tls: true,
+            attachments: "/tmp/attachments".into(),
+        }
+    }
+
+    #[test]
+    fn test_imap_channel_name() {
+        let ch = ImapChannel::new(settings());
+        assert_eq!(ch.name(), "imap");
+    }
+
+    #[test]
+    fn test_search_query_without_cursor() {
+        assert_eq!(search_query(None, 7), ("UNSEEN".to_string(), 0));
+    }
+
+    #[test]
+    fn test_search_query_continues_from_cursor() {
+        let cursor = UidCursor {
+            validity: 7,
+            last: 41,
+        };
+        assert_eq!(search_query(Some(cursor), 7), ("UID 42:*".to_string(), 41));
+    }
+
+    #[test]
+    fn test_search_query_resets_on_uidvalidity_change() {
+        let cursor = UidCursor {
+            validity: 7,
+            last: 41,
+        };
+        assert_eq!(search_query(Some(cursor), 8), ("UNSEEN".to_string(), 0));
+    }
+
+    #[test]
+    fn test_to_message_tracks_reply_context() {
+        let ch = ImapChannel::new(settings());
+        let raw = b"From: jane@test.org\r\nSubject: Hi\r\nMessage-ID: <x@test.org>\r\n\r\nHello";
+        let msg = ch.to_message(5, Some(raw)).unwrap();
+        assert_eq!(msg.channel, "imap");
+        assert_eq!(msg.sender, "jane@test.org");
+        assert_eq!(msg.text, "Hi\n\nHello");
+        assert_eq!(msg.reply_to.as_deref(), Some("<x@test.org>"));
+        assert_eq!(
+            ch.replies.lock().unwrap().resolve("<x@test.org>").0,
+            "jane@test.org"
+        );
+    }
+
+    #[test]
+    fn test_to_message_without_body() {
+        let ch = ImapChannel::new(settings());
+        assert!(ch.to_message(5, None).is_none());
+    }
+
+    /// Round trip against a local test server, e.g.
+    /// `podman run -p 3025:3025 -p 3143:3143 docker.io/greenmail/standalone`.
+    /// Run with `cargo test -- --ignored imap_roundtrip`.
+    #[tokio::test]
+    #[ignore]
+    async fn test_imap_roundtrip_local_server() {
+        let host = std::env::var("VATIC_TEST_MAIL_HOST").unwrap_or("127.0.0.1".into());
+        let ch = ImapChannel::new(ImapSettings {
+            host: host.clone(),
+            port: 3143,
+            user: "vatic@localhost".into(),
+            password: "vatic".into(),
+            mailbox: "INBOX".into(),
+            smtp_host: host,
+            smtp_port: 3025,
+            from: "vatic@localhost".into(),
+            tls: false,
+            attachments: std::env::temp_dir().join("vatic-test-attachments"),
+        });
+
+        ch.send("vatic@localhost", "ping").await.unwrap();
+
+        let (tx, mut rx) = mpsc::channel(10);
+        let ch = Arc::new(ch);
+        let runner = Arc::clone(&ch);
+        tokio::spawn(async move { runner.start(tx).await });
+
+        let msg = tokio::time::timeout(Duration::from_secs(30), rx.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(msg.sender, "vatic@localhost");
+        assert!(msg.text.contains("ping"));
+    }
+}
@@ -0,0 +1,639 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use base64::Engine;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tokio::sync::mpsc;
+
+use super::{split_message, Channel, IncomingMessage};
+
+/// Protocol limit per line, including CRLF.
+const MAX_LINE: usize = 512;
+/// Room for the `:nick!user@host ` prefix the server adds when relaying.
+const PREFIX_RESERVE: usize = 100;
+/// Targets so long that less than this is left per line are refused.
+const MIN_CHUNK: usize = 64;
+/// Lines we may send back to back before throttling kicks in.
+const FLOOD_BURST: u32 = 4;
+/// Steady-state pace once the burst is used up.
+const FLOOD_INTERVAL: Duration = Duration::from_secs(1);
+
+type Outbox = Arc<Mutex<Option<mpsc::UnboundedSender<String>>>>;
+
+/// Connection details, resolved from the channel config and `secrets.toml`.
+#[derive(Clone)]
+pub struct IrcSettings {
+    pub server: String,
+    pub port: u16,
+    pub tls: bool,
+    pub nick: String,
+    /// SASL account, defaults to the nick.
+    pub user: Option<String>,
+    /// SASL PLAIN password; no SASL without it.
+    pub password: Option<String>,
+    pub channels: Vec<String>,
+}
+
+#[derive(Debug, PartialEq)]
+struct IrcLine {
+    prefix: Option<String>,
+    command: String,
+    params: Vec<String>,
+}
+
+/// Parse a raw line, ignoring IRCv3 message tags.
+fn parse_line(line: &str) -> Option<IrcLine> {
+    let mut rest = line.trim_end_matches(['\r', '\n']);
+    if rest.starts_with('@') {
+        rest = rest.split_once(' ')?.1;
+    }
+
+    let prefix = match rest.strip_prefix(':') {
+        Some(r) => {
+            let (p, r) = r.split_once(' ')?;
+            rest = r;
+            Some(p.to_string())
+        }
+        None => None,
+    };
+
+    let (head, trailing) = match rest.split_once(" :") {
+        Some((h, t)) => (h, Some(t)),
+        None => (rest, None),
+    };
+    let mut parts = head.split_whitespace();
+    let command = parts.next()?.to_uppercase();
+    let mut params: Vec<String> = parts.map(String::from).collect();
+    if let Some(t) = trailing {
+        params.push(t.to_string());
+    }
+
+    Some(IrcLine {
+        prefix,
+        command,
+        params,
+    })
+}
+
+fn nick_of(prefix: &str) -> &str {
+    prefix.split('!').next().unwrap_or(prefix)
+}
+
+/// `vatic: do x` / `vatic, do x` → `do x`; a mention elsewhere keeps the
+/// whole line. `None` if the nick isn't mentioned at all.
+fn strip_highlight(text: &str, nick: &str) -> Option<String> {
+    if let Some(head) = text.get(..nick.len()) {
+        let rest = &text[nick.len()..];
+        if head.eq_ignore_ascii_case(nick) && (rest.is_empty() || rest.starts_with([':', ',', ' ']))
+        {
+            return Some(rest.trim_start_matches([':', ',']).trim().to_string());
+        }
+    }
+
+    let mentioned = text
+        .split(|c: char| !(c.is_alphanumeric() || "-_[]\\`^{}|".contains(c)))
+        .any(|word| word.eq_ignore_ascii_case(nick));
+    mentioned.then(|| text.trim().to_string())
+}
+
+/// The nick is always the sender. Channel messages only count when they
+/// highlight us, and are answered in the channel.
+fn privmsg_to_message(line: &IrcLine, own_nick: &str) -> Option<IncomingMessage> {
+    let from = nick_of(line.prefix.as_deref()?);
+    let [target, text] = line.params.as_slice() else {
+        return None;
+    };
+    if text.starts_with('\x01') {
+        return None; // CTCP
+    }
+
+    let (reply_to, text) = if target.eq_ignore_ascii_case(own_nick) {
+        (None, text.trim().to_string())
+    } else {
+        (Some(target.clone()), strip_highlight(text, own_nick)?)
+    };
+    if text.is_empty() {
+        return None;
+    }
+
+    let mut meta = HashMap::new();
+    meta.insert("nick".to_string(), from.to_string());
+    Some(IncomingMessage {
+        channel: "irc".to_string(),
+        sender: from.to_string(),
+        text,
+        meta,
+        reply_to,
+        ..Default::default()
+    })
+}
+
+fn sasl_plain(user: &str, password: &str) -> String {
+    base64::engine::general_purpose::STANDARD.encode(format!("{user}\0{user}\0{password}"))
+}
+
+/// Client-side flood control: every line costs `interval`, and we may run
+/// up to `burst` lines ahead of real time.
+struct Throttle {
+    interval: Duration,
+    window: Duration,
+    clock: Option<Instant>,
+}
+
+impl Throttle {
+    fn new(burst: u32, interval: Duration) -> Self {
+        Self {
+            interval,
+            window: interval * burst,
+            clock: None,
+        }
+    }
+
+    /// How long to wait before sending the next line.
+    fn delay(&mut self, now: Instant) -> Duration {
+        let clock = self.clock.map_or(now, |c| c.max(now)) + self.interval;
+        self.clock = Some(clock);
+        (clock - now).saturating_sub(self.window)
+    }
+}
+
+async fn run_writer<W: AsyncWrite + Unpin>(mut writer: W, mut rx: mpsc::UnboundedReceiver<String>) {
+    let mut throttle = Throttle::new(FLOOD_BURST, FLOOD_INTERVAL);
+    while let Some(line) = rx.recv().await {
+        let wait = throttle.delay(Instant::now());
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+        if writer
+            .write_all(format!("{line}\r\n").as_bytes())
+            .await
+            .is_err()
+        {
+            break;
+        }
+    }
+}
+
+/// Plain IRC client: TLS, SASL PLAIN, channel joins, highlights and PMs.
+pub struct IrcChannel {
+    settings: IrcSettings,
+    nick: Arc<Mutex<String>>,
+    outbox: Outbox,
+}
+
+impl IrcChannel {
+    pub fn new(settings: IrcSettings) -> Self {
+        let nick = settings.nick.clone();
+        Self {
+            settings,
+            nick: Arc::new(Mutex::new(nick)),
+            outbox: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Connect and run one session. `Ok(true)` means the receiver is gone.
+    async fn connect(&self, tx: &mpsc::Sender<IncomingMessage>) -> crate::error::Result<bool> {
+        let s = &self.settings;
+        let tcp = TcpStream::connect((s.server.as_str(), s.port))
+            .await
+            .map_err(|e| crate::error::Error::Channel(format!("irc connect failed: {e}")))?;
+
+        if s.tls {
+            let connector = tokio_native_tls::native_tls::TlsConnector::new()
+                .map_err(|e| crate::error::Error::Channel(format!("irc tls setup failed: {e}")))?;
+            let stream = tokio_native_tls::TlsConnector::from(connector)
+                .connect(&s.server, tcp)
+                .await
+                .map_err(|e| {
+                    crate::error::Error::Channel(format!("irc tls handshake failed: {e}"))
+                })?;
+            self.session(stream, tx).await
+        } else {
+            self.session(tcp, tx).await
+        }
+    }
+
+    async fn session<T>(
+        &self,
+        stream: T,
+        tx: &mpsc::Sender<IncomingMessage>,
+    ) -> crate::error::Result<bool>
+    where
+        T: AsyncRead + AsyncWrite + Send + 'static,
+    {
+        let (reader, writer) = tokio::io::split(stream);
+        let (out, out_rx) = mpsc::unbounded_channel();
+        let writer_task = tokio::spawn(run_writer(writer, out_rx));
+        *self.outbox.lock().unwrap() = Some(out.clone());
+
+        let result = self.read_loop(reader, &out, tx).await;
+
+        *self.outbox.lock().unwrap() = None;
+        writer_task.abort();
+        result
+    }
+
+    async fn read_loop<R: AsyncRead + Unpin>(
+        &self,
+        reader: R,
+        out: &mpsc::UnboundedSender<String>,
+        tx: &mpsc::Sender<IncomingMessage>,
+    ) -> crate::error::Result<bool> {
+        let s = &self.settings;
+        let send = |line: String| {
+            let _ = out.send(line);
+        };
+
+        if s.password.is_some() {
+            send("CAP REQ :sasl".to_string());
+        }
+        // Start from the configured nick again, not the `_` fallback of the
+        // last connection
+        let nick = s.nick.clone();
+        *self.nick.lock().unwrap() = nick.clone();
+        send(format!("NICK {nick}"));
+        send(format!("USER {nick} 0 * :vatic"));
+
+        let mut lines = BufReader::new(reader).lines();
+        while let Some(raw) = lines
+            .next_line()
+            .await
+            .map_err(|e| crate::error::Error::Channel(format!("irc read failed: {e}")))?
+        {
+            let Some(line) = parse_line(&raw) else {
+                continue;
+            };
+            let last = line.params.last().map(String::as_str).unwrap_or_default();
+
+            match line.command.as_str() {
+                "PING" => send(format!("PONG :{last}")),
+                "CAP" if line.params.get(1).map(String::as_str) == Some("ACK") => {
+                    send("AUTHENTICATE PLAIN".to_string());
+                }
+                "CAP" if line.params.get(1).map(String::as_str) == Some("NAK") => {
+                    tracing::warn!("irc server refused SASL, continuing without it");
+                    send("CAP END".to_string());
+                }
+                "AUTHENTICATE" if last == "+" => {
+                    let user = s.user.as_deref().unwrap_or(&s.nick);
+                    let password = s.password.as_deref().unwrap_or_default();
+                    send(format!("AUTHENTICATE {}", sasl_plain(user, password)));
+                }
+                "903" => {
+                    tracing::info!("irc SASL authentication succeeded");
+                    send("CAP END".to_string());
+                }
+                "902" | "904" | "905" => {
+                    tracing::error!("irc SASL authentication failed: {last}");
+                    send("CAP END".to_string());
+                }
+                "001" => {
+                    if let Some(me) = line.params.first() {
+                        *self.nick.lock().unwrap() = me.clone();
+                    }
+                    tracing::info!(
+                        "irc connected to {} as {}",
+                        s.server,
+                        self.nick.lock().unwrap()
+                    );
+                    if !s.channels.is_empty() {
+                        send(format!("JOIN {}", s.channels.join(",")));
+                    }
+                }
+                "433" => {
+                    let next = format!("{}_", self.nick.lock().unwrap());
+                    tracing::warn!("irc nick in use, trying {next}");
+                    *self.nick.lock().unwrap() = next.clone();
+                    send(format!("NICK {next}"));
+                }
+                "PRIVMSG" => {
+                    let own = self.nick.lock().unwrap().clone();
+                    if let Some(msg) = privmsg_to_message(&line, &own) {
+                        if tx.send(msg).await.is_err() {
+                            send("QUIT :bye".to_string());
+                            return Ok(true); // receiver dropped
+                        }
+                    }
+                }
+                "ERROR" => {
+                    return Err(crate::error::Error::Channel(format!(
+                        "irc server closed the link: {last}"
+                    )));
+                }
+                _ => {}
+            }
+        }
+        Ok(false)
+    }
+}
+
+#[async_trait::async_trait]
+impl Channel for IrcChannel {
+    async fn start(&self, tx: mpsc::Sender<IncomingMessage>) -> crate::error::Result<()> {
+        if self.connect(&tx).await? {
+            return Ok(()); // receiver dropped
+        }
+        Err(crate::error::Error::Channel(
+            "irc connection closed".to_string(),
+        ))
+    }
+
+    async fn send(&self, to: &str, message: &str) -> crate::error::Result<()> {
+        let outbox = self.outbox.lock().unwrap();
+        let Some(out) = outbox.as_ref() else {
+            return Err(crate::error::Error::Channel(
+                "irc is not connected".to_string(),
+            ));
+        };
+
+        if to.is_empty() || to.contains([' ', '\r', '\n', '\0']) {
+            return Err(crate::error::Error::Channel(format!(
+                "invalid irc target {to:?}"
+            )));
+        }
+        // "PRIVMSG <to> :" plus CRLF
+        let max = MAX_LINE.saturating_sub(PREFIX_RESERVE + to.len() + 12);
+        if max < MIN_CHUNK {
+            return Err(crate::error::Error::Channel(format!(
+                "irc target too long: {to}"
+            )));
+        }
+        // A bare CR or NUL would end the line early and let the rest
+        // through as a command
+        for line in message.split(['\r', '\n', '\0']).filter(|l| !l.is_empty()) {
+            for chunk in split_message(line, max) {
+                out.send(format!("PRIVMSG {to} :{chunk}"))
+                    .map_err(|_| crate::error::Error::Channel("irc send failed".to_string()))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        "irc"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    fn settings(port: u16) -> IrcSettings {
+        IrcSettings {
+            server: "127.0.0.1".into(),
+            port,
+            tls: false,
+            nick: "vatic".into(),
+            user: None,
+            password: None,
+            channels: vec!["#dev".into()],
+        }
+    }
+
+    fn privmsg(raw: &str) -> Option<IncomingMessage> {
+        privmsg_to_message(&parse_line(raw).unwrap(), "vatic")
+    }
+
+    #[test]
+    fn test_irc_channel_name() {
+        assert_eq!(IrcChannel::new(settings(6667)).name(), "irc");
+    }
+
+    #[test]
+    fn test_parse_line() {
+        let line = parse_line(":alice!a@host PRIVMSG #dev :hello there\r\n").unwrap();
+        assert_eq!(line.prefix.as_deref(), Some("alice!a@host"));
+        assert_eq!(line.command, "PRIVMSG");
+        assert_eq!(line.params, vec!["#dev", "hello there"]);
+    }
+
+    #[test]
+    fn test_parse_line_tags_and_no_prefix() {
+        let line = parse_line("@time=2026-01-01T00:00:00Z PING :irc.local").unwrap();
+        assert_eq!(line.prefix, None);
+        assert_eq!(line.command, "PING");
+        assert_eq!(line.params, vec!["irc.local"]);
+    }
+
+    #[test]
+    fn test_strip_highlight() {
+        assert_eq!(strip_highlight("vatic: status", "vatic").unwrap(), "status");
+        assert_eq!(strip_highlight("Vatic, status", "vatic").unwrap(), "status");
+        assert_eq!(
+            strip_highlight("ask vatic about it", "vatic").unwrap(),
+            "ask vatic about it"
+        );
+        assert!(strip_highlight("vaticano is nice", "vatic").is_none());
+        assert!(strip_highlight("hello all", "vatic").is_none());
+    }
+
+    #[test]
+    fn test_channel_message_needs_highlight() {
+        assert!(privmsg(":alice!a@h PRIVMSG #dev :morning all").is_none());
+        let msg = privmsg(":alice!a@h PRIVMSG #dev :vatic: deploy").unwrap();
+        assert_eq!(msg.sender, "alice");
+        assert_eq!(msg.reply_address(), "#dev");
+        assert_eq!(msg.text, "deploy");
+        assert_eq!(msg.meta["nick"], "alice");
+    }
+
+    #[test]
+    fn test_allowed_senders_apply_in_channels() {
+        let job = crate::config::types::parse_job_config(
+            &toml::from_str(
+                "[agent]\nname = \"ollama\"\n[input]\nchannel = \"irc\"\nallowed_senders = [\"alice\"]\n",
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        let alice = privmsg(":alice!a@h PRIVMSG #dev :vatic: deploy").unwrap();
+        assert!(crate::daemon::matches_input(&job, &alice));
+        let mallory = privmsg(":mallory!m@h PRIVMSG #dev :vatic: deploy").unwrap();
+        assert!(!crate::daemon::matches_input(&job, &mallory));
+    }
+
+    #[test]
+    fn test_private_message_uses_nick() {
+        let msg = privmsg(":alice!a@h PRIVMSG vatic :hi").unwrap();
+        assert_eq!(msg.channel, "irc");
+        assert_eq!(msg.sender, "alice");
+        assert_eq!(msg.reply_address(), "alice");
+        assert_eq!(msg.text, "hi");
+    }
+
+    #[test]
+    fn test_ctcp_ignored() {
+        assert!(privmsg(":alice!a@h PRIVMSG vatic :\x01VERSION\x01").is_none());
+    }
+
+    #[test]
+    fn test_sasl_plain() {
+        assert_eq!(sasl_plain("bot", "pw"), "Ym90AGJvdABwdw==");
+    }
+
+    #[test]
+    fn test_throttle_burst_then_paced() {
+        let mut t = Throttle::new(2, Duration::from_secs(1));
+        let now = Instant::now();
+        assert_eq!(t.delay(now), Duration::ZERO);
+        assert_eq!(t.delay(now), Duration::ZERO);
+        assert_eq!(t.delay(now), Duration::from_secs(1));
+        assert_eq!(t.delay(now), Duration::from_secs(2));
+        // Budget refills after idling
+        assert_eq!(t.delay(now + Duration::from_secs(10)), Duration::ZERO);
+    }
+
+    #[tokio::test]
+    async fn test_send_without_connection() {
+        let ch = IrcChannel::new(settings(6667));
+        assert!(ch.send("#dev", "hi").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_send_splits_on_cr_and_nul() {
+        let ch = IrcChannel::new(settings(6667));
+        let (out, mut rx) = mpsc::unbounded_channel();
+        *ch.outbox.lock().unwrap() = Some(out);
+
+        ch.send("#dev", "hi\rQUIT :pwned\0x\r\n\nbye")
+            .await
+            .unwrap();
+        let mut sent = Vec::new();
+        while let Ok(line) = rx.try_recv() {
+            sent.push(line);
+        }
+        assert_eq!(
+            sent,
+            [
+                "PRIVMSG #dev :hi",
+                "PRIVMSG #dev :QUIT :pwned",
+                "PRIVMSG #dev :x",
+                "PRIVMSG #dev :bye"
+            ]
+        );
+
+        assert!(ch.send("#dev\rQUIT", "hi").await.is_err());
+        assert!(ch.send(&"#".repeat(400), "hi").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_nick_reset_on_reconnect() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = tokio::spawn(async move {
+            let mut nicks = Vec::new();
+            for _ in 0..2 {
+                let (stream, _) = listener.accept().await.unwrap();
+                let (r, mut w) = stream.into_split();
+                let mut lines = BufReader::new(r).lines();
+                while let Some(line) = lines.next_line().await.unwrap() {
+                    if line.starts_with("USER") {
+                        w.write_all(b":irc.local 433 * vatic :in use\r\n")
+                            .await
+                            .unwrap();
+                    } else if let Some(nick) = line.strip_prefix("NICK ") {
+                        nicks.push(nick.to_string());
+                        if nick.ends_with('_') {
+                            break;
+                        }
+                    }
+                }
+            }
+            nicks
+        });
+
+        let ch = IrcChannel::new(settings(port));
+        let (tx, _rx) = mpsc::channel(10);
+        assert!(!ch.connect(&tx).await.unwrap());
+        assert!(!ch.connect(&tx).await.unwrap());
+        assert_eq!(
+            server.await.unwrap(),
+            ["vatic", "vatic_", "vatic", "vatic_"]
+        );
+    }
+
+    /// A dropped connection goes back to the supervisor instead of being
+    /// retried here.
+    #[tokio::test]
+    async fn test_start_fails_when_connection_closes() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            drop(stream);
+        });
+
+        let ch = IrcChannel::new(settings(port));
+        let (tx, _rx) = mpsc::channel(10);
+        let err = ch.start(tx).await.unwrap_err();
+        assert_eq!(err.to_string(), "channel error: irc connection closed");
+    }
+
+    /// Minimal fake server: registers us, relays a highlight, collects replies.
+    #[tokio::test]
+    async fn test_roundtrip_with_fake_server() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let (r, mut w) = stream.into_split();
+            let mut lines = BufReader::new(r).lines();
+            let mut seen = Vec::new();
+
+            while let Some(line) = lines.next_line().await.unwrap() {
+                seen.push(line.clone());
+                if line.starts_with("USER") {
+                    w.write_all(b":irc.local 001 vatic :Welcome\r\n")
+                        .await
+                        .unwrap();
+                }
+                if line.starts_with("JOIN") {
+                    w.write_all(b":alice!a@h PRIVMSG #dev :vatic: ping\r\n")
+                        .await
+                        .unwrap();
+                }
+                if line.starts_with("PRIVMSG")
+                    && seen.iter().filter(|l| l.starts_with("PRIVMSG")).count() == 2
+                {
+                    return seen;
+                }
+            }
+            seen
+        });
+
+        let ch = Arc::new(IrcChannel::new(settings(port)));
+        let (tx, mut rx) = mpsc::channel(10);
+        let runner = Arc::clone(&ch);
+        tokio::spawn(async move { runner.start(tx).await });
+
+        let msg = rx.recv().await.unwrap();
+        assert_eq!(msg.sender, "alice");
+        assert_eq!(msg.reply_address(), "#dev");
+        assert_eq!(msg.text, "ping");
+
+        ch.send("#dev", "pong\nsecond line").await.unwrap();
+        let seen = server.await.unwrap();
+        assert!(seen.contains(&"NICK vatic".to_string()));
+        assert!(seen.contains(&"JOIN #dev".to_string()));
+        assert!(seen.contains(&"PRIVMSG #dev :pong".to_string()));
+        assert!(seen.contains(&"PRIVMSG #dev :second line".to_string()));
+    }
+
+    /// Against a real ircd, e.g. `podman run -p 6667:6667 docker.io/ergochat/ergo`.
+    /// Run with `cargo test -- --ignored irc_local_server`.
+    #[tokio::test]
+    #[ignore]
+    async fn test_irc_local_server() {
+        let ch = Arc::new(IrcChannel::new(settings(6667)));
+        let (tx, _rx) = mpsc::channel(10);
+        let runner = Arc::clone(&ch);
+        tokio::spawn(async move { runner.start(tx).await });
+
+        tokio::time::sleep(Duration::from_secs(3)).await;
+        ch.send("#dev", "hello from vatic").await.unwrap();
+    }
+}
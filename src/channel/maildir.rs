@@ -0,0 +1,314 @@
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use tokio::sync::mpsc;
+
+use super::email::{
+    attachment_dir, build_reply, format_email_text, parse_email, save_attachments, ReplyTracker,
+};
+use super::{Channel, IncomingMessage};
+
+/// Maildir filename with the seen flag set, per the `:2,<flags>` convention.
+fn seen_name(name: &str) -> String {
+    match name.split_once(":2,") {
+        Some((base, flags)) => {
+            let mut flags: Vec<char> = flags.chars().collect();
+            if !flags.contains(&'S') {
+                flags.push('S');
+                flags.sort_unstable();
+            }
+            format!("{}:2,{}", base, flags.into_iter().collect::<String>())
+        }
+        None => format!("{}:2,S", name),
+    }
+}
+
+/// Files waiting in `new/`, oldest name first (names start with a timestamp).
+fn pending_messages(root: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let mut paths: Vec<PathBuf> = std::fs::read_dir(root.join("new"))?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.is_file())
+        .filter(|p| {
+            // Dotfiles are in-progress deliveries or editor noise
+            p.file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| !n.starts_with('.'))
+        })
+        .collect();
+    paths.sort();
+    Ok(paths)
+}
+
+/// Reads mail delivered by mbsync/offlineimap/fdm straight from a Maildir.
+/// Replies are sent with `msmtp`.
+pub struct MaildirChannel {
+    path: PathBuf,
+    poll_interval: u64,
+    account: Option<String>,
+    attachments: PathBuf,
+    replies: Arc<Mutex<ReplyTracker>>,
+}
+
+impl MaildirChannel {
+    pub fn new(
+        path: &str,
+        poll_interval: u64,
+        account: Option<String>,
+        attachments: PathBuf,
+    ) -> Self {
+        Self {
+            path: PathBuf::from(shellexpand::tilde(path).as_ref()),
+            poll_interval,
+            account,
+            attachments,
+            replies: Arc::new(Mutex::new(ReplyTracker::default())),
+        }
+    }
+
+    /// Parse one message from `new/` and move it to `cur/` as seen.
+    fn take_message(&self, path: &Path) -> crate::error::Result<Option<IncomingMessage>> {
+        let name = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or_default()
+            .to_string();
+
+        let raw = std::fs::read(path).map_err(|e| {
+            crate::error::Error::Channel(format!("cannot read {}: {e}", path.display()))
+        })?;
+
+        // Move first so a message that fails to parse isn't retried forever
+        let dest = self.path.join("cur").join(seen_name(&name));
+        std::fs::rename(path, &dest).map_err(|e| {
+            crate::error::Error::Channel(format!("cannot move {} to cur/: {e}", path.display()))
+        })?;
+
+        let Some(parsed) = parse_email(&raw) else {
+            tracing::error!("cannot parse maildir message {}", name);
+            return Ok(None);
+        };
+
+        let Some(sender) = parsed.from.clone() else {
+            tracing::warn!("maildir message {} has no From address, skipping", name);
+            return Ok(None);
+        };
+
+        let reply_to = self
+            .replies
+            .lock()
+            .unwrap()
+            .insert(&sender, parsed.reply.clone());
+
+        let attachments = save_attachments(
+            &attachment_dir(&self.attachments, &name),
+            &parsed.attachments,
+        );
+
+        Ok(Some(IncomingMessage {
+            channel: "maildir".to_string(),
+            sender,
+            text: format_email_text(&parsed.subject, &parsed.body),
+            attachments,
+            reply_to: Some(reply_to),
+            ..Default::default()
+        }))
+    }
+}
+
+#[async_trait::async_trait]
+impl Channel for MaildirChannel {
+    async fn start(&self, tx: mpsc::Sender<IncomingMessage>) -> crate::error::Result<()> {
+        for sub in ["new", "cur", "tmp"] {
+            if !self.path.join(sub).is_dir() {
+                return Err(crate::error::Error::Channel(format!(
+                    "{} is not a maildir (missing {sub}/)",
+                    self.path.display()
+                )));
+            }
+        }
+
+        tracing::info!("maildir watching {}", self.path.display());
+
+        let mut interval =
+            tokio::time::interval(std::time::Duration::from_secs(self.poll_interval));
+
+        loop {
+            interval.tick().await;
+
+            let pending = match pending_messages(&self.path) {
+                Ok(p) => p,
+                Err(e) => {
+                    tracing::error!("cannot list {}/new: {e}", self.path.display());
+                    continue;
+                }
+            };
+
+            for path in pending {
+                let msg = match self.take_message(&path) {
+                    Ok(Some(msg)) => msg,
+                    Ok(None) => continue,
+                    Err(e) => {
+                        tracing::error!("{e}");
+                        continue;
+                    }
+                };
+
+                if tx.send(msg).await.is_err() {
+                    return Ok(()); // receiver dropped
+                }
+            }
+        }
+    }
+
+    async fn send(&self, to: &str, message: &str) -> crate::error::Result<()> {
+        let (to, reply) = self.replies.lock().unwrap().resolve(to);
+        let email = build_reply(&to, reply.as_ref(), message);
+
+        let mut args = vec!["--read-recipients"];
+        if let Some(ref acct) = self.account {
+            args.extend(["--account", acct]);
+        }
+
+        let mut child = tokio::process::Command::new("msmtp")
+            .args(&args)
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::piped())
+            .spawn()
+            .map_err(|e| crate::error::Error::Channel(format!("cannot spawn msmtp: {e}")))?;
+
+        if let Some(mut stdin) = child.stdin.take() {
+            use tokio::io::AsyncWriteExt;
+            stdin.write_all(email.as_bytes()).await.map_err(|e| {
+                crate::error::Error::Channel(format!("msmtp stdin write failed: {e}"))
+            })?;
+        }
+
+        let output =
+            tokio::time::timeout(std::time::Duration::from_secs(60), child.wait_with_output())
+                .await
+                .map_err(|_| crate::error::Error::Channel("msmtp timed out".to_string()))?
+                .map_err(|e| crate::error::Error::Channel(format!("msmtp send failed: {e}")))?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(crate::error::Error::Channel(format!(
+                "msmtp send failed: {}",
+                stderr.trim()
+            )));
+        }
+
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        "maildir"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_maildir() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        for sub in ["new", "cur", "tmp"] {
+            std::fs::create_dir(dir.path().join(sub)).unwrap();
+        }
+        dir
+    }
+
+    #[test]
+    fn test_maildir_channel_name() {
+        let ch = MaildirChannel::new("/tmp/mail", 5, None, "/tmp/attachments".into());
+        assert_eq!(ch.name(), "maildir");
+    }
+
+    #[test]
+    fn test_maildir_expands_tilde() {
+        let ch = MaildirChannel::new("~/Mail/inbox", 5, None, "/tmp/attachments".into());
+        assert!(!ch.path.starts_with("~"));
+        assert!(ch.path.ends_with("Mail/inbox"));
+    }
+
+    #[test]
+    fn test_seen_name_no_info() {
+        assert_eq!(seen_name("1700000000.123.host"), "1700000000.123.host:2,S");
+    }
+
+    #[test]
+    fn test_seen_name_existing_flags() {
+        assert_eq!(
+            seen_name("1700000000.123.host:2,RF"),
+            "1700000000.123.host:2,FRS"
+        );
+    }
+
+    #[test]
+    fn test_seen_name_already_seen() {
+        assert_eq!(
+            seen_name("1700000000.123.host:2,S"),
+            "1700000000.123.host:2,S"
+        );
+    }
+
+    #[test]
+    fn test_pending_messages_skips_dotfiles() {
+        let dir = make_maildir();
+        std::fs::write(dir.path().join("new").join("2.host"), "x").unwrap();
+        std::fs::write(dir.path().join("new").join("1.host"), "x").unwrap();
+        std::fs::write(dir.path().join("new").join(".lock"), "x").unwrap();
+        let pending = pending_messages(dir.path()).unwrap();
+        let names: Vec<_> = pending
+            .iter()
+            .map(|p| p.file_name().unwrap().to_str().unwrap())
+            .collect();
+        assert_eq!(names, vec!["1.host", "2.host"]);
+    }
+
+    #[test]
+    fn test_take_message_moves_to_cur() {
+        let dir = make_maildir();
+        let path = dir.path().join("new").join("1.host");
+        std::fs::write(
+            &path,
+            "From: Jane <jane@test.org>\r\nSubject: Hello\r\nMessage-ID: <m1@test.org>\r\n\r\nHow are you?",
+        )
+        .unwrap();
+
+        let ch = MaildirChannel::new(
+            dir.path().to_str().unwrap(),
+            5,
+            None,
+            dir.path().join("attachments"),
+        );
+        let msg = ch.take_message(&path).unwrap().unwrap();
+
+        assert_eq!(msg.channel, "maildir");
+        assert_eq!(msg.sender, "jane@test.org");
+        assert_eq!(msg.text, "Hello\n\nHow are you?");
+        assert!(!path.exists());
+        assert!(dir.path().join("cur").join("1.host:2,S").exists());
+        assert_eq!(msg.reply_to.as_deref(), Some("<m1@test.org>"));
+        let (to, reply) = ch.replies.lock().unwrap().resolve("<m1@test.org>");
+        assert_eq!(to, "jane@test.org");
+        assert_eq!(reply.unwrap().message_id.as_deref(), Some("m1@test.org"));
+    }
+
+    #[test]
+    fn test_take_message_without_sender_is_skipped() {
+        let dir = make_maildir();
+        let path = dir.path().join("new").join("1.host");
+        std::fs::write(&path, "Subject: Hello\r\n\r\nbody").unwrap();
+
+        let ch = MaildirChannel::new(
+            dir.path().to_str().unwrap(),
+            5,
+            None,
+            dir.path().join("attachments"),
+        );
+        assert!(ch.take_message(&path).unwrap().is_none());
+        assert!(dir.path().join("cur").join("1.host:2,S").exists());
+    }
+}
@@ -96,6 +96,7 @@ impl Channel for MatrixChannel {
                         channel: "matrix".to_string(),
                         sender,
                         text,
+                        ..Default::default()
                     };
 
                     let _ = tx.send(msg).await;
@@ -1,17 +1,50 @@
+pub mod calendar;
+pub mod discord;
 pub mod email;
+pub mod feed;
+pub mod gotify;
+pub mod http;
+pub mod imap;
+pub mod irc;
+pub mod maildir;
 pub mod matrix;
+pub mod mqtt;
+pub mod ntfy;
+pub mod signal;
+pub mod slack;
 pub mod stdin;
 pub mod telegram;
+pub mod watch;
+pub mod webhook;
 #[cfg(feature = "whatsapp")]
 pub mod whatsapp;
+pub mod xmpp;
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
 
 use tokio::sync::mpsc;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct IncomingMessage {
     pub channel: String,
     pub sender: String,
     pub text: String,
+    /// Files that arrived with the message, already saved to disk.
+    pub attachments: Vec<PathBuf>,
+    /// Channel-specific fields for templates, e.g. a flattened webhook payload.
+    pub meta: HashMap<String, String>,
+    /// Where replies go if not simply `sender`, e.g. a specific email to
+    /// thread onto.
+    pub reply_to: Option<String>,
+}
+
+impl IncomingMessage {
+    /// The `to` a reply on the same channel should use.
+    pub fn reply_address(&self) -> &str {
+        self.reply_to.as_deref().unwrap_or(&self.sender)
+    }
 }
 
 #[async_trait::async_trait]
@@ -24,4 +57,115 @@ pub trait Channel: Send + Sync {
 
     /// Identifier used for routing and logging.
     fn name(&self) -> &str;
+
+    /// No job took `msg`. Channels that hold the sender open waiting for a
+    /// reply (sync webhooks) can let go right away.
+    fn unclaimed(&self, _msg: &IncomingMessage) {}
+}
+
+/// Running channels by config file name, shared with the output layer.
+pub type ChannelRegistry = HashMap<String, Arc<dyn Channel>>;
+
+/// Split text into chunks of at most `max` bytes for size-limited protocols.
+/// Lines are packed together where they fit; overlong lines break at spaces,
+/// and words longer than `max` are cut at a char boundary.
+pub fn split_message(text: &str, max: usize) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+
+    for line in text.lines() {
+        for piece in split_line(line, max) {
+            if !current.is_empty() && current.len() + 1 + piece.len() > max {
+                chunks.push(std::mem::take(&mut current));
+            }
+            if !current.is_empty() {
+                current.push('\n');
+            }
+            current.push_str(&piece);
+        }
+    }
+
+    if !current.trim().is_empty() {
+        chunks.push(current);
+    }
+    chunks.retain(|c| !c.trim().is_empty());
+    chunks
+}
+
+fn split_line(line: &str, max: usize) -> Vec<String> {
+    if line.len() <= max {
+        return vec![line.to_string()];
+    }
+
+    let mut pieces = Vec::new();
+    let mut current = String::new();
+    for word in line.split(' ') {
+        let mut word = word;
+        // Hard-split words that can never fit
+        while word.len() > max {
+            if !current.is_empty() {
+                pieces.push(std::mem::take(&mut current));
+            }
+            let mut cut = max;
+            while !word.is_char_boundary(cut) {
+                cut -= 1;
+            }
+            pieces.push(word[..cut].to_string());
+            word = &word[cut..];
+        }
+        if !current.is_empty() && current.len() + 1 + word.len() > max {
+            pieces.push(std::mem::take(&mut current));
+        }
+        if !current.is_empty() {
+            current.push(' ');
+        }
+        current.push_str(word);
+    }
+    if !current.is_empty() {
+        pieces.push(current);
+    }
+    pieces
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_message_short() {
+        assert_eq!(split_message("hello\nworld", 100), vec!["hello\nworld"]);
+    }
+
+    #[test]
+    fn test_split_message_packs_lines() {
+        assert_eq!(
+            split_message("aaaa\nbbbb\ncccc", 9),
+            vec!["aaaa\nbbbb", "cccc"]
+        );
+    }
+
+    #[test]
+    fn test_split_message_breaks_at_spaces() {
+        assert_eq!(
+            split_message("one two three four", 9),
+            vec!["one two", "three", "four"]
+        );
+    }
+
+    #[test]
+    fn test_split_message_long_word() {
+        assert_eq!(split_message("abcdefghij", 4), vec!["abcd", "efgh", "ij"]);
+    }
+
+    #[test]
+    fn test_split_message_char_boundary() {
+        let chunks = split_message("ääää", 3);
+        assert!(chunks.iter().all(|c| c.len() <= 3));
+        assert_eq!(chunks.concat(), "ääää");
+    }
+
+    #[test]
+    fn test_split_message_empty() {
+        assert!(split_message("\n\n", 10).is_empty());
+    }
 }
@@ -0,0 +1,345 @@
+use std::collections::{HashMap, VecDeque};
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use rumqttc::{
+    AsyncClient, Event, EventLoop, MqttOptions, Packet, Publish, QoS, SubscribeFilter,
+    TlsConfiguration, Transport,
+};
+use tokio::sync::mpsc;
+
+use super::{Channel, IncomingMessage};
+
+/// Pause between polls while rumqttc reconnects on its own.
+const POLL_RETRY: Duration = Duration::from_secs(5);
+/// Failed polls in a row before the channel reports itself down.
+const MAX_FAILED_POLLS: u32 = 3;
+const KEEP_ALIVE: Duration = Duration::from_secs(30);
+
+/// Connection details, resolved from the channel config and `secrets.toml`.
+#[derive(Clone)]
+pub struct MqttSettings {
+    pub host: String,
+    pub port: u16,
+    pub tls: bool,
+    pub client_id: String,
+    pub user: Option<String>,
+    pub password: Option<String>,
+    /// Topic filters to subscribe to, wildcards allowed.
+    pub topics: Vec<String>,
+    /// Where replies go; `{topic}` is the topic the message came in on.
+    pub reply_topic: String,
+    pub qos: u8,
+}
+
+/// Resolve the topic a reply to `to` is published on.
+fn reply_topic(pattern: &str, to: &str) -> String {
+    pattern.replace("{topic}", to)
+}
+
+/// Each publish is one message from its topic. Retained messages are
+/// broker state replayed on subscribe, not events, so they're skipped.
+fn to_incoming(publish: &Publish) -> Option<IncomingMessage> {
+    if publish.retain {
+        return None;
+    }
+    let text = String::from_utf8_lossy(&publish.payload).trim().to_string();
+    if text.is_empty() {
+        return None;
+    }
+    let mut meta = HashMap::new();
+    meta.insert("topic".to_string(), publish.topic.clone());
+    Some(IncomingMessage {
+        channel: "mqtt".to_string(),
+        sender: publish.topic.clone(),
+        text,
+        meta,
+        ..Default::default()
+    })
+}
+
+/// How long a reply we published is remembered for spotting its echo.
+const ECHO_TTL: Duration = Duration::from_secs(60);
+/// Most replies remembered at once.
+const MAX_ECHOES: usize = 256;
+
+fn payload_hash(payload: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    payload.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Replies we published recently, so a broad subscription doesn't feed them
+/// back in. Only the exact topic and payload count, and only for a while:
+/// other clients publishing on a reply topic still get through.
+#[derive(Default)]
+struct Echoes {
+    sent: VecDeque<(String, u64, Instant)>,
+}
+
+impl Echoes {
+    fn record(&mut self, topic: &str, payload: &[u8], now: Instant) {
+        if self.sent.len() >= MAX_ECHOES {
+            self.sent.pop_front();
+        }
+        self.sent
+            .push_back((topic.to_string(), payload_hash(payload), now));
+    }
+
+    /// Whether this is one of ours; each recorded reply matches once.
+    fn take(&mut self, topic: &str, payload: &[u8], now: Instant) -> bool {
+        self.sent
+            .retain(|(_, _, at)| now.duration_since(*at) < ECHO_TTL);
+        let hash = payload_hash(payload);
+        match self
+            .sent
+            .iter()
+            .position(|(t, h, _)| t == topic && *h == hash)
+        {
+            Some(i) => {
+                self.sent.remove(i);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// MQTT subscriber/publisher for sensor events and device commands.
+pub struct MqttChannel {
+    settings: MqttSettings,
+    client: Mutex<Option<AsyncClient>>,
+    published: Mutex<Echoes>,
+}
+
+impl MqttChannel {
+    pub fn new(settings: MqttSettings) -> Self {
+        Self {
+            settings,
+            client: Mutex::new(None),
+            published: Mutex::new(Echoes::default()),
+        }
+    }
+
+    fn qos(&self) -> QoS {
+        rumqttc::qos(self.settings.qos).unwrap_or(QoS::AtLeastOnce)
+    }
+
+    fn connect(&self) -> (AsyncClient, EventLoop) {
+        let s = &self.settings;
+        let mut options = MqttOptions::new(&s.client_id, &s.host, s.port);
+        options.set_keep_alive(KEEP_ALIVE);
+        if let Some(user) = &s.user {
+            options.set_credentials(user, s.password.clone().unwrap_or_default());
+        }
+        if s.tls {
+            options.set_transport(Transport::tls_with_config(TlsConfiguration::Native));
+        }
+        AsyncClient::new(options, 16)
+    }
+}
+
+#[async_trait::async_trait]
+impl Channel for MqttChannel {
+    async fn start(&self, tx: mpsc::Sender<IncomingMessage>) -> crate::error::Result<()> {
+        // The event loop reconnects on the next poll after an error; only a
+        // run of failures goes back to the supervisor
+        let (client, mut eventloop) = self.connect();
+        *self.client.lock().unwrap() = Some(client.clone());
+
+        let filters: Vec<SubscribeFilter> = self
+            .settings
+            .topics
+            .iter()
+            .map(|t| SubscribeFilter::new(t.clone(), self.qos()))
+            .collect();
+
+        let mut failed = 0;
+        let result = loop {
+            match eventloop.poll().await {
+                Ok(Event::Incoming(Packet::ConnAck(_))) => {
+                    failed = 0;
+                    tracing::info!(
+                        "mqtt connected to {}:{}",
+                        self.settings.host,
+                        self.settings.port
+                    );
+                    // Clean sessions forget subscriptions, so redo them on every connect
+                    if !filters.is_empty() {
+                        if let Err(e) = client.try_subscribe_many(filters.clone()) {
+                            tracing::error!("mqtt subscribe failed: {e}");
+                        }
+                    }
+                }
+                Ok(Event::Incoming(Packet::Publish(publish))) => {
+                    let echo = self.published.lock().unwrap().take(
+                        &publish.topic,
+                        &publish.payload,
+                        Instant::now(),
+                    );
+                    if echo {
+                        continue;
+                    }
+                    let Some(msg) = to_incoming(&publish) else {
+                        continue;
+                    };
+                    if tx.send(msg).await.is_err() {
+                        break Ok(()); // receiver dropped
+                    }
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    failed += 1;
+                    if failed >= MAX_FAILED_POLLS {
+                        break Err(crate::error::Error::Channel(format!(
+                            "mqtt connection failed: {e}"
+                        )));
+                    }
+                    tracing::warn!("mqtt connection error: {e}");
+                    tokio::time::sleep(POLL_RETRY).await;
+                }
+            }
+        };
+
+        *self.client.lock().unwrap() = None;
+        let _ = client.try_disconnect();
+        result
+    }
+
+    async fn send(&self, to: &str, message: &str) -> crate::error::Result<()> {
+        let topic = reply_topic(&self.settings.reply_topic, to);
+        let Some(client) = self.client.lock().unwrap().clone() else {
+            return Err(crate::error::Error::Channel(
+                "mqtt is not connected".to_string(),
+            ));
+        };
+        self.published
+            .lock()
+            .unwrap()
+            .record(&topic, message.as_bytes(), Instant::now());
+        client
+            .publish(topic, self.qos(), false, message.as_bytes().to_vec())
+            .await
+            .map_err(|e| crate::error::Error::Channel(format!("mqtt publish failed: {e}")))
+    }
+
+    fn name(&self) -> &str {
+        "mqtt"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings() -> MqttSettings {
+        MqttSettings {
+            host: "127.0.0.1".into(),
+            port: 1883,
+            tls: false,
+            client_id: "vatic-test".into(),
+            user: None,
+            password: None,
+            topics: vec!["home/#".into()],
+            reply_topic: "{topic}/reply".into(),
+            qos: 1,
+        }
+    }
+
+    #[test]
+    fn test_mqtt_channel_name() {
+        assert_eq!(MqttChannel::new(settings()).name(), "mqtt");
+    }
+
+    #[test]
+    fn test_reply_topic() {
+        assert_eq!(reply_topic("{topic}/reply", "home/door"), "home/door/reply");
+        assert_eq!(reply_topic("alerts/out", "home/door"), "alerts/out");
+    }
+
+    #[test]
+    fn test_publish_to_incoming() {
+        let publish = Publish::new("home/door", QoS::AtLeastOnce, "opened at 03:12\n");
+        let msg = to_incoming(&publish).unwrap();
+        assert_eq!(msg.channel, "mqtt");
+        assert_eq!(msg.sender, "home/door");
+        assert_eq!(msg.text, "opened at 03:12");
+        assert_eq!(msg.meta["topic"], "home/door");
+    }
+
+    #[test]
+    fn test_retained_and_empty_skipped() {
+        let mut retained = Publish::new("home/door", QoS::AtLeastOnce, "closed");
+        retained.retain = true;
+        assert!(to_incoming(&retained).is_none());
+        assert!(to_incoming(&Publish::new("home/door", QoS::AtMostOnce, "  ")).is_none());
+    }
+
+    #[test]
+    fn test_echoes() {
+        let mut echoes = Echoes::default();
+        let now = Instant::now();
+        echoes.record("home/door/reply", b"ok", now);
+        assert!(!echoes.take("home/door/reply", b"other", now));
+        assert!(!echoes.take("home/door", b"ok", now));
+        assert!(echoes.take("home/door/reply", b"ok", now));
+        assert!(!echoes.take("home/door/reply", b"ok", now));
+
+        echoes.record("home/door/reply", b"ok", now);
+        assert!(!echoes.take("home/door/reply", b"ok", now + ECHO_TTL));
+
+        for i in 0..MAX_ECHOES + 10 {
+            echoes.record(&format!("t/{i}"), b"x", now);
+        }
+        assert_eq!(echoes.sent.len(), MAX_ECHOES);
+        assert!(!echoes.take("t/0", b"x", now));
+    }
+
+    #[tokio::test]
+    async fn test_send_without_connection() {
+        let ch = MqttChannel::new(settings());
+        assert!(ch.send("home/door", "hi").await.is_err());
+    }
+
+    /// Against a local mosquitto on 1883 with anonymous access.
+    /// Run with `cargo test -- --ignored mqtt_local`.
+    #[tokio::test]
+    #[ignore]
+    async fn test_mqtt_local_broker() {
+        let ch = std::sync::Arc::new(MqttChannel::new(MqttSettings {
+            topics: vec!["vatic-test/in".into()],
+            ..settings()
+        }));
+        let (tx, mut rx) = mpsc::channel(10);
+        let runner = std::sync::Arc::clone(&ch);
+        tokio::spawn(async move { runner.start(tx).await });
+        tokio::time::sleep(Duration::from_secs(1)).await;
+
+        // A second client plays the sensor and listens for the reply
+        let mut options = MqttOptions::new("vatic-sensor", "127.0.0.1", 1883);
+        options.set_keep_alive(KEEP_ALIVE);
+        let (sensor, mut events) = AsyncClient::new(options, 10);
+        sensor
+            .subscribe("vatic-test/in/reply", QoS::AtLeastOnce)
+            .await
+            .unwrap();
+        sensor
+            .publish("vatic-test/in", QoS::AtLeastOnce, false, "power spiked")
+            .await
+            .unwrap();
+        let reply = tokio::spawn(async move {
+            loop {
+                if let Event::Incoming(Packet::Publish(p)) = events.poll().await.unwrap() {
+                    return String::from_utf8_lossy(&p.payload).to_string();
+                }
+            }
+        });
+
+        let msg = rx.recv().await.unwrap();
+        assert_eq!(msg.sender, "vatic-test/in");
+        assert_eq!(msg.text, "power spiked");
+        ch.send(&msg.sender, "ignore it").await.unwrap();
+        assert_eq!(reply.await.unwrap(), "ignore it");
+    }
+}
@@ -0,0 +1,295 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use serde::Deserialize;
+use tokio::sync::mpsc;
+
+use super::{Channel, IncomingMessage};
+
+/// ntfy sends keepalives every 45s; anything longer is a dead stream.
+const READ_TIMEOUT: Duration = Duration::from_secs(120);
+/// Tag on everything we publish, so our replies coming back on the stream
+/// are skipped even before the publish has returned their id.
+const OWN_TAG: &str = "vatic-reply";
+
+/// How published notifications look. Shared with Gotify.
+#[derive(Clone, Debug, Default)]
+pub struct PushOptions {
+    pub title: Option<String>,
+    /// ntfy's 1 (min) to 5 (max); Gotify gets it mapped onto its 0–10.
+    pub priority: Option<u8>,
+    pub tags: Vec<String>,
+    /// URL opened when the notification is tapped.
+    pub click: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct NtfyEvent {
+    id: String,
+    event: String,
+    topic: String,
+    #[serde(default)]
+    message: String,
+    title: Option<String>,
+    priority: Option<u8>,
+    #[serde(default)]
+    tags: Vec<String>,
+    click: Option<String>,
+}
+
+/// The topic is the sender, so replies go back to where the message came from.
+fn to_incoming(event: NtfyEvent) -> Option<IncomingMessage> {
+    if event.event != "message" || event.message.trim().is_empty() {
+        return None;
+    }
+    let mut meta = HashMap::new();
+    meta.insert("id".to_string(), event.id);
+    meta.insert("title".to_string(), event.title.unwrap_or_default());
+    meta.insert(
+        "priority".to_string(),
+        event.priority.unwrap_or(3).to_string(),
+    );
+    meta.insert("tags".to_string(), event.tags.join(","));
+    meta.insert("click".to_string(), event.click.unwrap_or_default());
+    Some(IncomingMessage {
+        channel: "ntfy".to_string(),
+        sender: event.topic,
+        text: event.message.trim().to_string(),
+        meta,
+        ..Default::default()
+    })
+}
+
+/// ntfy topics in both directions: the JSON stream in, plain publishes out.
+pub struct NtfyChannel {
+    server: String,
+    topic: String,
+    token: Option<String>,
+    push: PushOptions,
+    client: reqwest::Client,
+    /// Last seen message id, so a reconnect picks up where the old stream
+    /// left off.
+    since: Mutex<Option<String>>,
+}
+
+impl NtfyChannel {
+    pub fn new(server: String, topic: String, token: Option<String>, push: PushOptions) -> Self {
+        Self {
+            server: server.trim_end_matches('/').to_string(),
+            topic,
+            token,
+            push,
+            client: reqwest::Client::new(),
+            since: Mutex::new(None),
+        }
+    }
+
+    fn authorize(&self, req: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        match &self.token {
+            Some(token) => req.bearer_auth(token),
+            None => req,
+        }
+    }
+
+    /// Follow the stream until it ends. `Ok(true)` means the receiver is
+    /// gone.
+    async fn subscribe(&self, tx: &mpsc::Sender<IncomingMessage>) -> crate::error::Result<bool> {
+        let err = |e: reqwest::Error| crate::error::Error::Channel(format!("ntfy stream: {e}"));
+        let mut url = format!("{}/{}/json", self.server, self.topic);
+        if let Some(id) = self.since.lock().unwrap().as_deref() {
+            url.push_str(&format!("?since={id}"));
+        }
+        let mut resp = self
+            .authorize(self.client.get(url))
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(err)?;
+        tracing::info!("ntfy subscribed to {}/{}", self.server, self.topic);
+
+        let mut buf = Vec::new();
+        loop {
+            let chunk = match tokio::time::timeout(READ_TIMEOUT, resp.chunk()).await {
+                Ok(chunk) => chunk.map_err(err)?,
+                Err(_) => {
+                    return Err(crate::error::Error::Channel(
+                        "ntfy stream timed out".to_string(),
+                    ))
+                }
+            };
+            let Some(chunk) = chunk else {
+                return Ok(false);
+            };
+            buf.extend_from_slice(&chunk);
+
+            while let Some(pos) = buf.iter().position(|&b| b == b'\n') {
+                let line: Vec<u8> = buf.drain(..=pos).collect();
+                let Ok(event) = serde_json::from_slice::<NtfyEvent>(&line) else {
+                    continue;
+                };
+                if event.event == "message" {
+                    *self.since.lock().unwrap() = Some(event.id.clone());
+                }
+                if event.tags.iter().any(|t| t == OWN_TAG) {
+                    continue;
+                }
+                if let Some(msg) = to_incoming(event) {
+                    if tx.send(msg).await.is_err() {
+                        return Ok(true); // receiver dropped
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Channel for NtfyChannel {
+    async fn start(&self, tx: mpsc::Sender<IncomingMessage>) -> crate::error::Result<()> {
+        if self.subscribe(&tx).await? {
+            return Ok(()); // receiver dropped
+        }
+        Err(crate::error::Error::Channel(
+            "ntfy stream ended".to_string(),
+        ))
+    }
+
+    async fn send(&self, to: &str, message: &str) -> crate::error::Result<()> {
+        let topic = if to.is_empty() { &self.topic } else { to };
+        let mut req = self
+            .client
+            .post(format!("{}/{topic}", self.server))
+            .body(message.to_string());
+        if let Some(title) = &self.push.title {
+            req = req.header("Title", title);
+        }
+        if let Some(priority) = self.push.priority {
+            req = req.header("Priority", priority.to_string());
+        }
+        let mut tags = self.push.tags.clone();
+        tags.push(OWN_TAG.to_string());
+        req = req.header("Tags", tags.join(","));
+        if let Some(click) = &self.push.click {
+            req = req.header("Click", click);
+        }
+
+        self.authorize(req)
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| crate::error::Error::Channel(format!("ntfy publish failed: {e}")))?;
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        "ntfy"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::{Body, Bytes};
+    use axum::http::HeaderMap;
+    use axum::routing::get;
+    use axum::{Json, Router};
+    use futures_util::stream::{self, StreamExt};
+    use std::convert::Infallible;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn test_ntfy_channel_name() {
+        let ch = NtfyChannel::new(
+            "https://ntfy.sh".into(),
+            "t".into(),
+            None,
+            Default::default(),
+        );
+        assert_eq!(ch.name(), "ntfy");
+    }
+
+    #[test]
+    fn test_event_to_incoming() {
+        let event: NtfyEvent = serde_json::from_str(
+            r#"{"id":"abc","time":1,"event":"message","topic":"ops","message":"disk full","title":"nas","priority":4,"tags":["warning","disk"]}"#,
+        )
+        .unwrap();
+        let msg = to_incoming(event).unwrap();
+        assert_eq!(msg.sender, "ops");
+        assert_eq!(msg.text, "disk full");
+        assert_eq!(msg.meta["title"], "nas");
+        assert_eq!(msg.meta["priority"], "4");
+        assert_eq!(msg.meta["tags"], "warning,disk");
+        assert_eq!(msg.meta["click"], "");
+    }
+
+    #[test]
+    fn test_keepalive_skipped() {
+        let event: NtfyEvent =
+            serde_json::from_str(r#"{"id":"k","time":1,"event":"keepalive","topic":"ops"}"#)
+                .unwrap();
+        assert!(to_incoming(event).is_none());
+    }
+
+    /// Local stand-in for ntfy: one streamed message (plus the echo of our
+    /// own publish), and a publish endpoint recording headers.
+    #[tokio::test]
+    async fn test_stream_and_publish() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let published: Arc<Mutex<Vec<(HeaderMap, String)>>> = Arc::default();
+
+        let published_clone = Arc::clone(&published);
+        let app = Router::new().route(
+            "/ops",
+            axum::routing::post(move |headers: HeaderMap, body: String| async move {
+                published_clone.lock().unwrap().push((headers, body));
+                Json(serde_json::json!({ "id": "mine", "event": "message" }))
+            }),
+        );
+        let app = app.route(
+            "/ops/json",
+            get(|| async {
+                let lines = [
+                    r#"{"id":"o","time":1,"event":"open","topic":"ops"}"#,
+                    r#"{"id":"mine","time":2,"event":"message","topic":"ops","message":"echo","tags":["robot","vatic-reply"]}"#,
+                    r#"{"id":"m1","time":3,"event":"message","topic":"ops","message":"backup failed"}"#,
+                ];
+                let frames = lines.map(|l| Ok::<_, Infallible>(Bytes::from(format!("{l}\n"))));
+                Body::from_stream(stream::iter(frames).chain(stream::pending()))
+            }),
+        );
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let push = PushOptions {
+            title: Some("vatic".into()),
+            priority: Some(4),
+            tags: vec!["robot".into()],
+            click: Some("https://example.org".into()),
+        };
+        let ch = Arc::new(NtfyChannel::new(
+            format!("http://{addr}/"),
+            "ops".into(),
+            Some("tk_test".into()),
+            push,
+        ));
+        // The echo arrives before the publish returns, and is still skipped
+        let (tx, mut rx) = mpsc::channel(10);
+        let runner = Arc::clone(&ch);
+        tokio::spawn(async move { runner.start(tx).await });
+        ch.send("ops", "on it").await.unwrap();
+        let msg = rx.recv().await.unwrap();
+        assert_eq!(msg.sender, "ops");
+        assert_eq!(msg.text, "backup failed");
+
+        let published = published.lock().unwrap();
+        let (headers, body) = &published[0];
+        assert_eq!(body, "on it");
+        assert_eq!(headers["title"], "vatic");
+        assert_eq!(headers["priority"], "4");
+        assert_eq!(headers["tags"], "robot,vatic-reply");
+        assert_eq!(headers["click"], "https://example.org");
+        assert_eq!(headers["authorization"], "Bearer tk_test");
+    }
+}
@@ -0,0 +1,395 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use serde_json::{json, Value};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::net::{TcpStream, UnixStream};
+use tokio::sync::{mpsc, oneshot};
+
+use super::{Channel, IncomingMessage};
+
+/// How long to wait for signal-cli to confirm a send.
+const SEND_TIMEOUT: Duration = Duration::from_secs(30);
+/// Group conversations are addressed as `group:<base64 id>`.
+const GROUP_PREFIX: &str = "group:";
+
+type Reader = Box<dyn AsyncRead + Send + Unpin>;
+type Writer = Box<dyn AsyncWrite + Send + Unpin>;
+type Pending = Arc<Mutex<HashMap<u64, oneshot::Sender<Result<(), String>>>>>;
+
+/// Where the signal-cli daemon listens (`--socket` or `--tcp`).
+#[derive(Debug, Clone, PartialEq)]
+pub enum SignalEndpoint {
+    Unix(PathBuf),
+    Tcp(String),
+}
+
+/// Turn a `receive` notification into a message. Receipts, typing
+/// indicators and empty messages yield `None`.
+fn parse_receive(params: &Value, attachments_dir: &Path) -> Option<IncomingMessage> {
+    let envelope = params.get("envelope")?;
+    let data = envelope.get("dataMessage")?;
+
+    let text = data
+        .get("message")
+        .and_then(|m| m.as_str())
+        .unwrap_or_default()
+        .to_string();
+
+    let attachments: Vec<PathBuf> = data
+        .get("attachments")
+        .and_then(|a| a.as_array())
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(|a| a.get("id").and_then(|id| id.as_str()))
+                .map(|id| attachments_dir.join(id))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    if text.trim().is_empty() && attachments.is_empty() {
+        return None;
+    }
+
+    let source = ["sourceNumber", "source", "sourceUuid"]
+        .iter()
+        .find_map(|k| envelope.get(*k).and_then(|v| v.as_str()))?
+        .to_string();
+
+    // Reply to the group, not the individual who wrote in it
+    let reply_to = data
+        .get("groupInfo")
+        .and_then(|g| g.get("groupId"))
+        .and_then(|id| id.as_str())
+        .map(|group_id| format!("{GROUP_PREFIX}{group_id}"));
+
+    let mut meta = HashMap::new();
+    meta.insert("source".to_string(), source.clone());
+    if let Some(name) = envelope.get("sourceName").and_then(|n| n.as_str()) {
+        meta.insert("source_name".to_string(), name.to_string());
+    }
+
+    Some(IncomingMessage {
+        channel: "signal".to_string(),
+        sender: source,
+        text,
+        attachments,
+        meta,
+        reply_to,
+    })
+}
+
+/// JSON-RPC `send` params for a direct recipient or a `group:` id.
+fn send_params(account: Option<&str>, to: &str, message: &str) -> Value {
+    let mut params = match to.strip_prefix(GROUP_PREFIX) {
+        Some(group_id) => json!({ "groupId": group_id, "message": message }),
+        None => json!({ "recipient": [to], "message": message }),
+    };
+    if let Some(account) = account {
+        params["account"] = json!(account);
+    }
+    params
+}
+
+/// Talks to a running `signal-cli daemon` over newline-delimited JSON-RPC.
+pub struct SignalChannel {
+    endpoint: SignalEndpoint,
+    account: Option<String>,
+    attachments_dir: PathBuf,
+    writer: Arc<tokio::sync::Mutex<Option<Writer>>>,
+    pending: Pending,
+    next_id: AtomicU64,
+}
+
+impl SignalChannel {
+    pub fn new(
+        endpoint: SignalEndpoint,
+        account: Option<String>,
+        attachments_dir: PathBuf,
+    ) -> Self {
+        Self {
+            endpoint,
+            account,
+            attachments_dir,
+            writer: Arc::new(tokio::sync::Mutex::new(None)),
+            pending: Arc::new(Mutex::new(HashMap::new())),
+            next_id: AtomicU64::new(1),
+        }
+    }
+
+    async fn connect(&self) -> std::io::Result<(Reader, Writer)> {
+        match &self.endpoint {
+            SignalEndpoint::Unix(path) => {
+                let (r, w) = UnixStream::connect(path).await?.into_split();
+                Ok((Box::new(r), Box::new(w)))
+            }
+            SignalEndpoint::Tcp(addr) => {
+                let (r, w) = TcpStream::connect(addr).await?.into_split();
+                Ok((Box::new(r), Box::new(w)))
+            }
+        }
+    }
+
+    /// Read notifications and responses until the connection drops.
+    /// Returns `Ok(true)` when the receiver is gone and we should stop.
+    async fn read_loop(
+        &self,
+        reader: Reader,
+        tx: &mpsc::Sender<IncomingMessage>,
+    ) -> std::io::Result<bool> {
+        let mut lines = BufReader::new(reader).lines();
+        while let Some(line) = lines.next_line().await? {
+            let Ok(value) = serde_json::from_str::<Value>(&line) else {
+                tracing::debug!("signal-cli sent invalid json: {}", line);
+                continue;
+            };
+
+            // Response to one of our requests
+            if let Some(id) = value.get("id").and_then(|id| id.as_u64()) {
+                let outcome = match value.get("error") {
+                    Some(err) => Err(err
+                        .get("message")
+                        .and_then(|m| m.as_str())
+                        .unwrap_or("unknown error")
+                        .to_string()),
+                    None => Ok(()),
+                };
+                if let Some(waiter) = self.pending.lock().unwrap().remove(&id) {
+                    let _ = waiter.send(outcome);
+                }
+                continue;
+            }
+
+            if value.get("method").and_then(|m| m.as_str()) != Some("receive") {
+                continue;
+            }
+            let Some(msg) = value
+                .get("params")
+                .and_then(|p| parse_receive(p, &self.attachments_dir))
+            else {
+                continue;
+            };
+            if tx.send(msg).await.is_err() {
+                return Ok(true); // receiver dropped
+            }
+        }
+        Ok(false)
+    }
+}
+
+#[async_trait::async_trait]
+impl Channel for SignalChannel {
+    async fn start(&self, tx: mpsc::Sender<IncomingMessage>) -> crate::error::Result<()> {
+        let (reader, writer) = self.connect().await.map_err(|e| {
+            crate::error::Error::Channel(format!(
+                "cannot connect to signal-cli {:?}: {e}",
+                self.endpoint
+            ))
+        })?;
+        tracing::info!("signal connected to {:?}", self.endpoint);
+        *self.writer.lock().await = Some(writer);
+        let result = self.read_loop(reader, &tx).await;
+        *self.writer.lock().await = None;
+        // Nobody will answer requests sent on the old connection
+        self.pending.lock().unwrap().clear();
+        match result {
+            Ok(true) => Ok(()), // receiver dropped
+            Ok(false) => Err(crate::error::Error::Channel(
+                "signal-cli closed the connection".to_string(),
+            )),
+            Err(e) => Err(crate::error::Error::Channel(format!(
+                "signal-cli read failed: {e}"
+            ))),
+        }
+    }
+
+    async fn send(&self, to: &str, message: &str) -> crate::error::Result<()> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let request = json!({
+            "jsonrpc": "2.0",
+            "method": "send",
+            "id": id,
+            "params": send_params(self.account.as_deref(), to, message),
+        });
+        let mut line = request.to_string();
+        line.push('\n');
+
+        let (done_tx, done_rx) = oneshot::channel();
+        self.pending.lock().unwrap().insert(id, done_tx);
+
+        {
+            let mut writer = self.writer.lock().await;
+            let Some(w) = writer.as_mut() else {
+                self.pending.lock().unwrap().remove(&id);
+                return Err(crate::error::Error::Channel(
+                    "signal-cli is not connected".to_string(),
+                ));
+            };
+            if let Err(e) = w.write_all(line.as_bytes()).await {
+                self.pending.lock().unwrap().remove(&id);
+                return Err(crate::error::Error::Channel(format!(
+                    "signal send failed: {e}"
+                )));
+            }
+        }
+
+        match tokio::time::timeout(SEND_TIMEOUT, done_rx).await {
+            Ok(Ok(Ok(()))) => Ok(()),
+            Ok(Ok(Err(e))) => Err(crate::error::Error::Channel(format!(
+                "signal send failed: {e}"
+            ))),
+            Ok(Err(_)) => Err(crate::error::Error::Channel(
+                "signal-cli disconnected before confirming send".to_string(),
+            )),
+            Err(_) => {
+                self.pending.lock().unwrap().remove(&id);
+                Err(crate::error::Error::Channel(
+                    "signal send timed out".to_string(),
+                ))
+            }
+        }
+    }
+
+    fn name(&self) -> &str {
+        "signal"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::UnixListener;
+
+    #[test]
+    fn test_signal_channel_name() {
+        let ch = SignalChannel::new(
+            SignalEndpoint::Tcp("127.0.0.1:7583".into()),
+            None,
+            PathBuf::from("/tmp"),
+        );
+        assert_eq!(ch.name(), "signal");
+    }
+
+    #[test]
+    fn test_parse_direct_message() {
+        let params = json!({
+            "envelope": {
+                "source": "+491701234567",
+                "sourceNumber": "+491701234567",
+                "sourceName": "Jane",
+                "dataMessage": { "message": "hello", "attachments": [] }
+            }
+        });
+        let msg = parse_receive(&params, Path::new("/att")).unwrap();
+        assert_eq!(msg.channel, "signal");
+        assert_eq!(msg.sender, "+491701234567");
+        assert_eq!(msg.reply_address(), "+491701234567");
+        assert_eq!(msg.text, "hello");
+        assert_eq!(msg.meta["source_name"], "Jane");
+    }
+
+    #[test]
+    fn test_parse_group_message_with_attachment() {
+        let params = json!({
+            "envelope": {
+                "sourceUuid": "8f2c-uuid",
+                "dataMessage": {
+                    "message": "",
+                    "groupInfo": { "groupId": "aGVsbG8=", "type": "DELIVER" },
+                    "attachments": [{ "contentType": "image/png", "id": "x1.png" }]
+                }
+            }
+        });
+        let msg = parse_receive(&params, Path::new("/att")).unwrap();
+        assert_eq!(msg.sender, "8f2c-uuid");
+        assert_eq!(msg.reply_address(), "group:aGVsbG8=");
+        assert_eq!(msg.meta["source"], "8f2c-uuid");
+        assert_eq!(msg.attachments, vec![PathBuf::from("/att/x1.png")]);
+    }
+
+    #[test]
+    fn test_parse_skips_receipts() {
+        let params = json!({
+            "envelope": { "source": "+49", "receiptMessage": { "isRead": true } }
+        });
+        assert!(parse_receive(&params, Path::new("/att")).is_none());
+    }
+
+    #[test]
+    fn test_send_params() {
+        assert_eq!(
+            send_params(None, "+49123", "hi"),
+            json!({ "recipient": ["+49123"], "message": "hi" })
+        );
+        assert_eq!(
+            send_params(Some("+49999"), "group:abc=", "hi"),
+            json!({ "groupId": "abc=", "message": "hi", "account": "+49999" })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_send_without_connection() {
+        let ch = SignalChannel::new(
+            SignalEndpoint::Tcp("127.0.0.1:1".into()),
+            None,
+            PathBuf::from("/tmp"),
+        );
+        let err = ch.send("+49", "hi").await.unwrap_err();
+        assert!(err.to_string().contains("not connected"));
+    }
+
+    /// Fake signal-cli: pushes one message, then answers our `send`.
+    #[tokio::test]
+    async fn test_roundtrip_with_fake_daemon() {
+        let dir = tempfile::tempdir().unwrap();
+        let socket = dir.path().join("socket");
+        let listener = UnixListener::bind(&socket).unwrap();
+
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let (r, mut w) = stream.into_split();
+            let notification = json!({
+                "jsonrpc": "2.0",
+                "method": "receive",
+                "params": { "envelope": {
+                    "sourceNumber": "+49123",
+                    "dataMessage": { "message": "ping" }
+                }}
+            });
+            w.write_all(format!("{notification}\n").as_bytes())
+                .await
+                .unwrap();
+
+            let mut lines = BufReader::new(r).lines();
+            let request: Value =
+                serde_json::from_str(&lines.next_line().await.unwrap().unwrap()).unwrap();
+            let reply = json!({ "jsonrpc": "2.0", "id": request["id"], "result": {} });
+            w.write_all(format!("{reply}\n").as_bytes()).await.unwrap();
+            request
+        });
+
+        let ch = Arc::new(SignalChannel::new(
+            SignalEndpoint::Unix(socket),
+            Some("+49999".into()),
+            dir.path().to_path_buf(),
+        ));
+        let (tx, mut rx) = mpsc::channel(10);
+        let runner = Arc::clone(&ch);
+        tokio::spawn(async move { runner.start(tx).await });
+
+        let msg = rx.recv().await.unwrap();
+        assert_eq!(msg.sender, "+49123");
+        assert_eq!(msg.text, "ping");
+
+        ch.send(msg.reply_address(), "pong").await.unwrap();
+        let request = server.await.unwrap();
+        assert_eq!(request["method"], "send");
+        assert_eq!(request["params"]["recipient"][0], "+49123");
+        assert_eq!(request["params"]["message"], "pong");
+        assert_eq!(request["params"]["account"], "+49999");
+    }
+}
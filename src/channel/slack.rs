@@ -0,0 +1,495 @@
+use std::collections::HashMap;
+
+use futures_util::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::Message as WsMessage;
+
+use super::{split_message, Channel, IncomingMessage};
+
+const API_BASE: &str = "https://slack.com/api";
+/// Slack truncates longer `text` fields.
+const MAX_MESSAGE: usize = 3900;
+
+/// Escape the three characters Slack treats as control sequences.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+fn starts_with(chars: &[char], i: usize, pat: &str) -> bool {
+    pat.chars()
+        .enumerate()
+        .all(|(k, p)| chars.get(i + k) == Some(&p))
+}
+
+fn find(chars: &[char], from: usize, pat: &str) -> Option<usize> {
+    (from..chars.len()).find(|&i| starts_with(chars, i, pat))
+}
+
+/// Inline Markdown → mrkdwn: bold, italics, strikethrough, links, code spans.
+fn convert_inline(chars: &[char]) -> String {
+    let mut out = String::new();
+    let mut i = 0;
+    let text = |range: &[char]| range.iter().collect::<String>();
+
+    while i < chars.len() {
+        let c = chars[i];
+
+        if c == '`' {
+            if let Some(end) = find(chars, i + 1, "`") {
+                out.push('`');
+                out.push_str(&escape(&text(&chars[i + 1..end])));
+                out.push('`');
+                i = end + 1;
+                continue;
+            }
+        }
+
+        let mut wrapped = false;
+        for (open, slack) in [("**", '*'), ("__", '*'), ("~~", '~')] {
+            if starts_with(chars, i, open) {
+                if let Some(end) = find(chars, i + 2, open).filter(|&e| e > i + 2) {
+                    out.push(slack);
+                    out.push_str(&convert_inline(&chars[i + 2..end]));
+                    out.push(slack);
+                    i = end + 2;
+                    wrapped = true;
+                    break;
+                }
+            }
+        }
+        if wrapped {
+            continue;
+        }
+
+        // Single * or _ is italic, but not inside snake_case or around spaces
+        if (c == '*' || c == '_')
+            && chars.get(i + 1).is_some_and(|n| !n.is_whitespace())
+            && (i == 0 || !chars[i - 1].is_alphanumeric())
+        {
+            let close = (i + 2..chars.len()).find(|&e| {
+                chars[e] == c
+                    && !chars[e - 1].is_whitespace()
+                    && chars.get(e + 1).is_none_or(|n| !n.is_alphanumeric())
+            });
+            if let Some(end) = close {
+                out.push('_');
+                out.push_str(&convert_inline(&chars[i + 1..end]));
+                out.push('_');
+                i = end + 1;
+                continue;
+            }
+        }
+
+        if c == '[' {
+            if let Some(mid) = find(chars, i + 1, "](") {
+                if let Some(end) = find(chars, mid + 2, ")") {
+                    let label = text(&chars[i + 1..mid]);
+                    let url = text(&chars[mid + 2..end]);
+                    out.push_str(&format!("<{}|{}>", escape(&url), escape(&label)));
+                    i = end + 1;
+                    continue;
+                }
+            }
+        }
+
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            _ => out.push(c),
+        }
+        i += 1;
+    }
+    out
+}
+
+fn inline(s: &str) -> String {
+    convert_inline(&s.chars().collect::<Vec<_>>())
+}
+
+/// Agents answer in Markdown; Slack speaks its own mrkdwn dialect.
+pub fn markdown_to_mrkdwn(md: &str) -> String {
+    let mut out = Vec::new();
+    let mut in_code = false;
+
+    for line in md.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") {
+            // Slack ignores the language tag and would show it as text
+            in_code = !in_code;
+            out.push("```".to_string());
+            continue;
+        }
+        if in_code {
+            out.push(escape(line));
+            continue;
+        }
+
+        let heading = trimmed.trim_start_matches('#');
+        if heading.len() < trimmed.len() && heading.starts_with(' ') {
+            out.push(format!("*{}*", inline(heading.trim())));
+            continue;
+        }
+
+        let indent = &line[..line.len() - trimmed.len()];
+        if let Some(item) = ["- ", "* ", "+ "]
+            .iter()
+            .find_map(|b| trimmed.strip_prefix(b))
+        {
+            out.push(format!("{indent}• {}", inline(item)));
+            continue;
+        }
+
+        out.push(inline(line));
+    }
+    out.join("\n")
+}
+
+/// Drop the leading `<@U123>` mention from app_mention text.
+fn strip_mention(text: &str) -> &str {
+    let trimmed = text.trim_start();
+    match trimmed.strip_prefix("<@").and_then(|r| r.split_once('>')) {
+        Some((_, rest)) => rest.trim(),
+        None => trimmed.trim(),
+    }
+}
+
+/// The sender is the author's user id. Mentions are answered in a thread
+/// (`<channel>/<thread_ts>`); DMs reply in the conversation unless the
+/// message was already threaded.
+fn event_to_message(event: &Value) -> Option<IncomingMessage> {
+    let kind = event.get("type")?.as_str()?;
+    // Edits, joins, and our own posts
+    if event.get("subtype").is_some() || event.get("bot_id").is_some() {
+        return None;
+    }
+
+    let user = event.get("user")?.as_str()?;
+    let channel = event.get("channel")?.as_str()?;
+    let ts = event.get("ts")?.as_str()?;
+    let thread_ts = event.get("thread_ts").and_then(|t| t.as_str());
+    let text = event.get("text")?.as_str()?;
+
+    let (reply_to, text) = match kind {
+        "app_mention" => (
+            format!("{channel}/{}", thread_ts.unwrap_or(ts)),
+            strip_mention(text),
+        ),
+        "message" if event.get("channel_type").and_then(|t| t.as_str()) == Some("im") => {
+            let reply_to = match thread_ts {
+                Some(t) => format!("{channel}/{t}"),
+                None => channel.to_string(),
+            };
+            (reply_to, text.trim())
+        }
+        _ => return None,
+    };
+    if text.is_empty() {
+        return None;
+    }
+
+    let mut meta = HashMap::new();
+    meta.insert("user".to_string(), user.to_string());
+    Some(IncomingMessage {
+        channel: "slack".to_string(),
+        sender: user.to_string(),
+        text: text.to_string(),
+        meta,
+        reply_to: Some(reply_to),
+        ..Default::default()
+    })
+}
+
+/// Slack over Socket Mode: no public URL, events arrive on a WebSocket.
+pub struct SlackChannel {
+    app_token: String,
+    bot_token: String,
+    api_base: String,
+    client: reqwest::Client,
+}
+
+impl SlackChannel {
+    pub fn new(app_token: String, bot_token: String) -> Self {
+        Self {
+            app_token,
+            bot_token,
+            api_base: API_BASE.to_string(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    async fn api(&self, method: &str, token: &str, body: Value) -> crate::error::Result<Value> {
+        let resp: Value = self
+            .client
+            .post(format!("{}/{method}", self.api_base))
+            .bearer_auth(token)
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| crate::error::Error::Channel(format!("slack {method} failed: {e}")))?
+            .json()
+            .await
+            .map_err(|e| crate::error::Error::Channel(format!("slack {method} failed: {e}")))?;
+
+        if resp.get("ok").and_then(|ok| ok.as_bool()) != Some(true) {
+            let err = resp
+                .get("error")
+                .and_then(|e| e.as_str())
+                .unwrap_or("unknown error");
+            return Err(crate::error::Error::Channel(format!(
+                "slack {method} failed: {err}"
+            )));
+        }
+        Ok(resp)
+    }
+
+    /// One Socket Mode session. `Ok(true)` means the receiver is gone,
+    /// `Ok(false)` that Slack asked us to reconnect.
+    async fn session(&self, tx: &mpsc::Sender<IncomingMessage>) -> crate::error::Result<bool> {
+        let resp = self
+            .api("apps.connections.open", &self.app_token, json!({}))
+            .await?;
+        let url = resp
+            .get("url")
+            .and_then(|u| u.as_str())
+            .ok_or_else(|| crate::error::Error::Channel("slack returned no socket url".into()))?;
+
+        let (mut ws, _) = tokio_tungstenite::connect_async(url)
+            .await
+            .map_err(|e| crate::error::Error::Channel(format!("slack socket failed: {e}")))?;
+        tracing::info!("slack socket connected");
+
+        while let Some(frame) = ws.next().await {
+            let frame =
+                frame.map_err(|e| crate::error::Error::Channel(format!("slack socket: {e}")))?;
+            let WsMessage::Text(text) = frame else {
+                continue;
+            };
+            let Ok(envelope) = serde_json::from_str::<Value>(&text) else {
+                continue;
+            };
+
+            // Ack first, or Slack redelivers the event
+            if let Some(id) = envelope.get("envelope_id").and_then(|id| id.as_str()) {
+                let ack = json!({ "envelope_id": id }).to_string();
+                ws.send(WsMessage::Text(ack.into()))
+                    .await
+                    .map_err(|e| crate::error::Error::Channel(format!("slack ack failed: {e}")))?;
+            }
+
+            match envelope.get("type").and_then(|t| t.as_str()) {
+                Some("disconnect") => {
+                    tracing::debug!("slack asked us to reconnect");
+                    return Ok(false);
+                }
+                Some("events_api") => {
+                    let Some(msg) = envelope
+                        .pointer("/payload/event")
+                        .and_then(event_to_message)
+                    else {
+                        continue;
+                    };
+                    if tx.send(msg).await.is_err() {
+                        return Ok(true); // receiver dropped
+                    }
+                }
+                _ => {}
+            }
+        }
+        Err(crate::error::Error::Channel(
+            "slack socket closed".to_string(),
+        ))
+    }
+}
+
+#[async_trait::async_trait]
+impl Channel for SlackChannel {
+    async fn start(&self, tx: mpsc::Sender<IncomingMessage>) -> crate::error::Result<()> {
+        // Slack refreshes sockets every few hours; only failures go back to
+        // the supervisor
+        while !self.session(&tx).await? {}
+        Ok(())
+    }
+
+    async fn send(&self, to: &str, message: &str) -> crate::error::Result<()> {
+        let (channel, thread_ts) = match to.split_once('/') {
+            Some((c, t)) => (c, Some(t)),
+            None => (to, None),
+        };
+
+        for chunk in split_message(&markdown_to_mrkdwn(message), MAX_MESSAGE) {
+            let mut body = json!({ "channel": channel, "text": chunk });
+            if let Some(ts) = thread_ts {
+                body["thread_ts"] = json!(ts);
+            }
+            self.api("chat.postMessage", &self.bot_token, body).await?;
+        }
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        "slack"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::extract::ws::{Message as AxumWs, WebSocketUpgrade};
+    use axum::routing::{any, post};
+    use axum::{Json, Router};
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn test_slack_channel_name() {
+        assert_eq!(SlackChannel::new("a".into(), "b".into()).name(), "slack");
+    }
+
+    #[test]
+    fn test_mrkdwn_emphasis() {
+        assert_eq!(markdown_to_mrkdwn("**bold** and *it*"), "*bold* and _it_");
+        assert_eq!(markdown_to_mrkdwn("__b__ ~~gone~~"), "*b* ~gone~");
+        assert_eq!(markdown_to_mrkdwn("snake_case_name"), "snake_case_name");
+        assert_eq!(markdown_to_mrkdwn("2 * 3 * 4"), "2 * 3 * 4");
+    }
+
+    #[test]
+    fn test_mrkdwn_links_and_escaping() {
+        assert_eq!(
+            markdown_to_mrkdwn("see [docs](https://x.org/a?b=1&c=2) & <this>"),
+            "see <https://x.org/a?b=1&amp;c=2|docs> &amp; &lt;this&gt;"
+        );
+    }
+
+    #[test]
+    fn test_mrkdwn_blocks() {
+        let md = "# Title\n- one\n  * two\n```rust\nlet x = a**b;\n```\n`**raw**`";
+        assert_eq!(
+            markdown_to_mrkdwn(md),
+            "*Title*\n• one\n  • two\n```\nlet x = a**b;\n```\n`**raw**`"
+        );
+    }
+
+    #[test]
+    fn test_app_mention_threads() {
+        let event = json!({
+            "type": "app_mention", "user": "U1", "channel": "C1",
+            "ts": "1700.01", "text": "<@UBOT> deploy status"
+        });
+        let msg = event_to_message(&event).unwrap();
+        assert_eq!(msg.sender, "U1");
+        assert_eq!(msg.reply_address(), "C1/1700.01");
+        assert_eq!(msg.text, "deploy status");
+        assert_eq!(msg.meta["user"], "U1");
+
+        let threaded = json!({
+            "type": "app_mention", "user": "U2", "channel": "C1", "ts": "1700.05",
+            "thread_ts": "1700.01", "text": "<@UBOT> again"
+        });
+        let msg = event_to_message(&threaded).unwrap();
+        assert_eq!(msg.sender, "U2");
+        assert_eq!(msg.reply_address(), "C1/1700.01");
+    }
+
+    #[test]
+    fn test_direct_message() {
+        let event = json!({
+            "type": "message", "channel_type": "im", "channel": "D1",
+            "user": "U1", "ts": "1.0", "text": "hi"
+        });
+        let msg = event_to_message(&event).unwrap();
+        assert_eq!(msg.sender, "U1");
+        assert_eq!(msg.reply_address(), "D1");
+    }
+
+    #[test]
+    fn test_ignores_bots_and_channel_chatter() {
+        let bot = json!({
+            "type": "message", "channel_type": "im", "channel": "D1",
+            "bot_id": "B1", "ts": "1.0", "text": "echo"
+        });
+        assert!(event_to_message(&bot).is_none());
+        let chatter = json!({
+            "type": "message", "channel_type": "channel", "channel": "C1",
+            "user": "U1", "ts": "1.0", "text": "hi all"
+        });
+        assert!(event_to_message(&chatter).is_none());
+    }
+
+    /// Fake Slack: connections.open hands out a local socket that delivers
+    /// one mention; chat.postMessage calls are recorded.
+    #[tokio::test]
+    async fn test_socket_mode_roundtrip() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let posted: Arc<Mutex<Vec<Value>>> = Arc::default();
+        let acks: Arc<Mutex<Vec<String>>> = Arc::default();
+
+        let posted_clone = Arc::clone(&posted);
+        let acks_clone = Arc::clone(&acks);
+        let app = Router::new()
+            .route(
+                "/apps.connections.open",
+                post(move || async move {
+                    Json(json!({ "ok": true, "url": format!("ws://{addr}/socket") }))
+                }),
+            )
+            .route(
+                "/chat.postMessage",
+                post(move |Json(body): Json<Value>| async move {
+                    posted_clone.lock().unwrap().push(body);
+                    Json(json!({ "ok": true }))
+                }),
+            )
+            .route(
+                "/socket",
+                any(move |ws: WebSocketUpgrade| async move {
+                    ws.on_upgrade(move |mut socket| async move {
+                        let event = json!({
+                            "type": "events_api",
+                            "envelope_id": "env-1",
+                            "payload": { "event": {
+                                "type": "app_mention", "channel": "C1", "user": "U1",
+                                "ts": "1700.01", "text": "<@UBOT> ping"
+                            }}
+                        });
+                        socket
+                            .send(AxumWs::Text(event.to_string().into()))
+                            .await
+                            .unwrap();
+                        if let Some(Ok(AxumWs::Text(ack))) = socket.next().await {
+                            acks_clone.lock().unwrap().push(ack.to_string());
+                        }
+                        // Hold the socket open
+                        while socket.next().await.is_some() {}
+                    })
+                }),
+            );
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let mut ch = SlackChannel::new("xapp".into(), "xoxb".into());
+        ch.api_base = format!("http://{addr}");
+        let ch = Arc::new(ch);
+        let (tx, mut rx) = mpsc::channel(10);
+        let runner = Arc::clone(&ch);
+        tokio::spawn(async move { runner.start(tx).await });
+
+        let msg = rx.recv().await.unwrap();
+        assert_eq!(msg.sender, "U1");
+        assert_eq!(msg.text, "ping");
+
+        ch.send(msg.reply_address(), "**pong**").await.unwrap();
+        let posted = posted.lock().unwrap().clone();
+        assert_eq!(
+            posted,
+            vec![json!({ "channel": "C1", "text": "*pong*", "thread_ts": "1700.01" })]
+        );
+        assert_eq!(
+            acks.lock().unwrap().as_slice(),
+            [r#"{"envelope_id":"env-1"}"#]
+        );
+    }
+}
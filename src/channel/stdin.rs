@@ -20,6 +20,7 @@ impl Channel for StdinChannel {
                 channel: "stdin".to_string(),
                 sender: "local".to_string(),
                 text: line,
+                ..Default::default()
             };
             if tx.send(msg).await.is_err() {
                 break; // receiver dropped
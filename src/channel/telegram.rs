@@ -101,6 +101,7 @@ impl Channel for TelegramChannel {
                             channel: "telegram".to_string(),
                             sender,
                             text,
+                            ..Default::default()
                         };
 
                         if tx.send(msg).await.is_err() {
@@ -0,0 +1,317 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use notify::event::{AccessKind, AccessMode, ModifyKind};
+use notify::{EventKind, PollWatcher, RecursiveMode, Watcher};
+use tokio::sync::mpsc;
+use tokio::time::Instant;
+
+use super::{Channel, IncomingMessage};
+
+/// Only this much of a file ends up in `{% meta:contents %}`.
+const MAX_CONTENTS: u64 = 256 * 1024;
+/// Scan interval when inotify isn't available.
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// What to watch, resolved from the channel config.
+#[derive(Clone)]
+pub struct WatchSettings {
+    pub paths: Vec<PathBuf>,
+    /// Globs matched against the path relative to the watched directory.
+    pub patterns: Vec<String>,
+    pub recursive: bool,
+    /// Quiet time after the last event before a file is reported.
+    pub debounce: Duration,
+    /// Skip inotify and scan instead (network filesystems).
+    pub poll: bool,
+}
+
+fn build_globs(patterns: &[String]) -> crate::error::Result<GlobSet> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let glob = Glob::new(pattern).map_err(|e| {
+            crate::error::Error::Channel(format!("invalid watch pattern '{pattern}': {e}"))
+        })?;
+        builder.add(glob);
+    }
+    builder
+        .build()
+        .map_err(|e| crate::error::Error::Channel(format!("invalid watch patterns: {e}")))
+}
+
+/// Writes, new files and files moved in. Metadata-only changes (and our
+/// own reads) are ignored.
+fn is_change(kind: &EventKind) -> bool {
+    match kind {
+        EventKind::Create(_) => true,
+        EventKind::Modify(ModifyKind::Metadata(_)) => false,
+        EventKind::Modify(_) => true,
+        EventKind::Access(AccessKind::Close(AccessMode::Write)) => true,
+        _ => false,
+    }
+}
+
+/// Editor swap files and partial downloads.
+fn is_noise(path: &Path) -> bool {
+    path.file_name().and_then(|n| n.to_str()).is_none_or(|n| {
+        n.starts_with('.') || n.ends_with('~') || n.ends_with(".part") || n.ends_with(".tmp")
+    })
+}
+
+/// UTF-8 text up to `MAX_CONTENTS`; binary files (PDFs, images) get nothing,
+/// the agent can read them from the path.
+fn read_contents(path: &Path) -> String {
+    use std::io::Read;
+    let mut buf = Vec::new();
+    let read = std::fs::File::open(path).and_then(|f| f.take(MAX_CONTENTS).read_to_end(&mut buf));
+    match read {
+        Ok(_) => String::from_utf8(buf).unwrap_or_default(),
+        Err(_) => String::new(),
+    }
+}
+
+fn to_incoming(path: &Path) -> IncomingMessage {
+    let display = path.display().to_string();
+    let mut meta = HashMap::new();
+    meta.insert("path".to_string(), display.clone());
+    meta.insert(
+        "name".to_string(),
+        path.file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default(),
+    );
+    meta.insert("contents".to_string(), read_contents(path));
+    IncomingMessage {
+        channel: "watch".to_string(),
+        sender: display.clone(),
+        text: display,
+        attachments: vec![path.to_path_buf()],
+        meta,
+        ..Default::default()
+    }
+}
+
+/// Watches directories and reports files that were created or changed.
+pub struct WatchChannel {
+    settings: WatchSettings,
+}
+
+impl WatchChannel {
+    pub fn new(settings: WatchSettings) -> Self {
+        Self { settings }
+    }
+
+    /// Whether `path` matches a pattern, relative to the directory it's in.
+    fn matches(&self, globs: &GlobSet, path: &Path) -> bool {
+        if is_noise(path) {
+            return false;
+        }
+        self.settings.paths.iter().any(|root| {
+            path.strip_prefix(root)
+                .is_ok_and(|rel| globs.is_empty() || globs.is_match(rel))
+        })
+    }
+
+    /// inotify where possible, scanning otherwise.
+    fn watcher(
+        &self,
+        events: mpsc::UnboundedSender<notify::Result<notify::Event>>,
+    ) -> crate::error::Result<Box<dyn Watcher + Send>> {
+        let mode = if self.settings.recursive {
+            RecursiveMode::Recursive
+        } else {
+            RecursiveMode::NonRecursive
+        };
+        let watch_all = |watcher: &mut dyn Watcher| {
+            self.settings
+                .paths
+                .iter()
+                .try_for_each(|p| watcher.watch(p, mode))
+        };
+
+        if !self.settings.poll {
+            let tx = events.clone();
+            let native = notify::recommended_watcher(move |e| {
+                let _ = tx.send(e);
+            })
+            .and_then(|mut w| watch_all(&mut w).map(|_| w));
+            match native {
+                Ok(w) => return Ok(Box::new(w)),
+                Err(e) => tracing::warn!("file watch unavailable ({e}), falling back to polling"),
+            }
+        }
+
+        let config = notify::Config::default().with_poll_interval(POLL_INTERVAL);
+        let mut poller = PollWatcher::new(
+            move |e| {
+                let _ = events.send(e);
+            },
+            config,
+        )
+        .map_err(|e| crate::error::Error::Channel(format!("cannot watch files: {e}")))?;
+        watch_all(&mut poller)
+            .map_err(|e| crate::error::Error::Channel(format!("cannot watch files: {e}")))?;
+        Ok(Box::new(poller))
+    }
+}
+
+#[async_trait::async_trait]
+impl Channel for WatchChannel {
+    async fn start(&self, tx: mpsc::Sender<IncomingMessage>) -> crate::error::Result<()> {
+        let globs = build_globs(&self.settings.patterns)?;
+        let (events_tx, mut events) = mpsc::unbounded_channel();
+        let _watcher = self.watcher(events_tx)?;
+        for path in &self.settings.paths {
+            tracing::info!("watching {}", path.display());
+        }
+
+        // Path → when it last changed; reported once it's been quiet long enough
+        let mut pending: HashMap<PathBuf, Instant> = HashMap::new();
+        loop {
+            let next_due = pending.values().min().map(|t| *t + self.settings.debounce);
+            tokio::select! {
+                event = events.recv() => match event {
+                    None => break,
+                    Some(Ok(event)) if is_change(&event.kind) => {
+                        // Renames report [from, to]; the destination is what counts
+                        if let Some(path) = event.paths.last() {
+                            if self.matches(&globs, path) {
+                                pending.insert(path.clone(), Instant::now());
+                            }
+                        }
+                    }
+                    Some(Ok(_)) => {}
+                    Some(Err(e)) => tracing::warn!("file watch error: {e}"),
+                },
+                _ = tokio::time::sleep_until(next_due.unwrap_or_else(Instant::now)), if next_due.is_some() => {
+                    let now = Instant::now();
+                    let due: Vec<PathBuf> = pending
+                        .iter()
+                        .filter(|(_, t)| now.duration_since(**t) >= self.settings.debounce)
+                        .map(|(p, _)| p.clone())
+                        .collect();
+                    for path in due {
+                        pending.remove(&path);
+                        // Deleted again before it settled
+                        if !path.is_file() {
+                            continue;
+                        }
+                        if tx.send(to_incoming(&path)).await.is_err() {
+                            return Ok(()); // receiver dropped
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn send(&self, to: &str, _message: &str) -> crate::error::Result<()> {
+        tracing::debug!("watched file {to} can't be replied to, dropping reply");
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        "watch"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(dir: &Path, poll: bool) -> WatchSettings {
+        WatchSettings {
+            paths: vec![dir.to_path_buf()],
+            patterns: vec!["*.txt".into(), "*.pdf".into()],
+            recursive: false,
+            debounce: Duration::from_millis(300),
+            poll,
+        }
+    }
+
+    #[test]
+    fn test_watch_channel_name() {
+        let ch = WatchChannel::new(settings(Path::new("/tmp"), false));
+        assert_eq!(ch.name(), "watch");
+    }
+
+    #[test]
+    fn test_matches_globs_and_noise() {
+        let ch = WatchChannel::new(settings(Path::new("/inbox"), false));
+        let globs = build_globs(&ch.settings.patterns).unwrap();
+        assert!(ch.matches(&globs, Path::new("/inbox/notes.txt")));
+        assert!(ch.matches(&globs, Path::new("/inbox/scan.pdf")));
+        assert!(!ch.matches(&globs, Path::new("/inbox/photo.jpg")));
+        assert!(!ch.matches(&globs, Path::new("/inbox/.notes.txt.swp")));
+        assert!(!ch.matches(&globs, Path::new("/elsewhere/notes.txt")));
+    }
+
+    #[test]
+    fn test_empty_patterns_match_everything() {
+        let globs = build_globs(&[]).unwrap();
+        let ch = WatchChannel::new(WatchSettings {
+            patterns: vec![],
+            ..settings(Path::new("/inbox"), false)
+        });
+        assert!(ch.matches(&globs, Path::new("/inbox/anything.bin")));
+    }
+
+    #[test]
+    fn test_invalid_pattern() {
+        assert!(build_globs(&["[".into()]).is_err());
+    }
+
+    #[test]
+    fn test_incoming_fields() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("transcript.txt");
+        std::fs::write(&path, "Alice: ship it").unwrap();
+        let msg = to_incoming(&path);
+        assert_eq!(msg.channel, "watch");
+        assert_eq!(msg.meta["name"], "transcript.txt");
+        assert_eq!(msg.meta["contents"], "Alice: ship it");
+        assert_eq!(msg.attachments, vec![path.clone()]);
+
+        let pdf = dir.path().join("scan.pdf");
+        std::fs::write(&pdf, [0x25, 0x50, 0x44, 0x46, 0xff, 0xfe]).unwrap();
+        assert_eq!(to_incoming(&pdf).meta["contents"], "");
+    }
+
+    async fn burst_reported_once(poll: bool) {
+        let dir = tempfile::tempdir().unwrap();
+        let ch = std::sync::Arc::new(WatchChannel::new(settings(dir.path(), poll)));
+        let (tx, mut rx) = mpsc::channel(10);
+        let runner = std::sync::Arc::clone(&ch);
+        tokio::spawn(async move { runner.start(tx).await });
+        tokio::time::sleep(Duration::from_millis(300)).await;
+
+        let path = dir.path().join("notes.txt");
+        std::fs::write(dir.path().join("ignored.jpg"), "x").unwrap();
+        for i in 0..5 {
+            std::fs::write(&path, format!("draft {i}")).unwrap();
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+
+        let msg = tokio::time::timeout(Duration::from_secs(10), rx.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(msg.sender, path.display().to_string());
+        assert_eq!(msg.meta["contents"], "draft 4");
+        let again = tokio::time::timeout(Duration::from_secs(1), rx.recv()).await;
+        assert!(again.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_native_watch_debounces() {
+        burst_reported_once(false).await;
+    }
+
+    #[tokio::test]
+    async fn test_polling_fallback() {
+        burst_reported_once(true).await;
+    }
+}
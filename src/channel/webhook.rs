@@ -0,0 +1,410 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use axum::body::Bytes;
+use axum::extract::State;
+use axum::http::{HeaderMap, Method, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::Router;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use tokio::net::TcpListener;
+use tokio::sync::{mpsc, oneshot};
+
+use super::{Channel, IncomingMessage};
+use crate::config::types::WebhookAuth;
+
+type PendingReplies = Arc<Mutex<HashMap<String, oneshot::Sender<String>>>>;
+
+/// Check a hex HMAC-SHA256 signature, with or without the `sha256=` prefix
+/// (GitHub sends it prefixed, Gitea doesn't).
+fn verify_signature(key: &str, body: &[u8], signature: &str) -> bool {
+    let hex_sig = signature.trim();
+    let hex_sig = hex_sig.strip_prefix("sha256=").unwrap_or(hex_sig);
+    let Ok(expected) = hex::decode(hex_sig) else {
+        return false;
+    };
+    let Ok(mut mac) = Hmac::<Sha256>::new_from_slice(key.as_bytes()) else {
+        return false;
+    };
+    mac.update(body);
+    mac.verify_slice(&expected).is_ok()
+}
+
+/// Compare without bailing out on the first differing byte.
+pub(super) fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+fn verify_bearer(token: &str, header: Option<&str>) -> bool {
+    header
+        .and_then(|h| h.strip_prefix("Bearer "))
+        .is_some_and(|t| constant_time_eq(t.trim().as_bytes(), token.as_bytes()))
+}
+
+/// Flatten JSON into dotted keys (`pull_request.head.ref`, `alerts.0.status`).
+/// Objects and arrays also get an entry with their compact JSON.
+pub fn flatten_json(value: &serde_json::Value, prefix: &str, out: &mut HashMap<String, String>) {
+    let key = |k: &str| {
+        if prefix.is_empty() {
+            k.to_string()
+        } else {
+            format!("{prefix}.{k}")
+        }
+    };
+
+    match value {
+        serde_json::Value::Object(map) => {
+            for (k, v) in map {
+                flatten_json(v, &key(k), out);
+            }
+        }
+        serde_json::Value::Array(items) => {
+            for (i, v) in items.iter().enumerate() {
+                flatten_json(v, &key(&i.to_string()), out);
+            }
+        }
+        _ => {}
+    }
+
+    if prefix.is_empty() {
+        return;
+    }
+    let text = match value {
+        serde_json::Value::Null => String::new(),
+        serde_json::Value::String(s) => s.clone(),
+        other => other.to_string(),
+    };
+    out.insert(prefix.to_string(), text);
+}
+
+struct HookState {
+    auth: WebhookAuth,
+    key: String,
+    signature_header: String,
+    sync: bool,
+    timeout: Duration,
+    pending: PendingReplies,
+    next_id: AtomicU64,
+    tx: mpsc::Sender<IncomingMessage>,
+}
+
+impl HookState {
+    fn authorized(&self, headers: &HeaderMap, body: &[u8]) -> bool {
+        match self.auth {
+            WebhookAuth::Hmac => headers
+                .get(self.signature_header.as_str())
+                .and_then(|v| v.to_str().ok())
+                .is_some_and(|sig| verify_signature(&self.key, body, sig)),
+            WebhookAuth::Bearer => verify_bearer(
+                &self.key,
+                headers
+                    .get(axum::http::header::AUTHORIZATION)
+                    .and_then(|v| v.to_str().ok()),
+            ),
+        }
+    }
+}
+
+async fn handle(
+    State(state): State<Arc<HookState>>,
+    method: Method,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    if method != Method::POST {
+        return StatusCode::METHOD_NOT_ALLOWED.into_response();
+    }
+    if !state.authorized(&headers, &body) {
+        tracing::warn!("webhook request rejected: bad signature or token");
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+
+    // Unique per request, so replies find their way back to the right caller
+    let id = format!(
+        "{}-{}",
+        chrono::Local::now().timestamp_millis(),
+        state.next_id.fetch_add(1, Ordering::Relaxed)
+    );
+
+    let mut meta = HashMap::new();
+    if let Ok(json) = serde_json::from_slice::<serde_json::Value>(&body) {
+        flatten_json(&json, "", &mut meta);
+    }
+
+    let msg = IncomingMessage {
+        channel: "webhook".to_string(),
+        sender: id.clone(),
+        text: String::from_utf8_lossy(&body).into_owned(),
+        meta,
+        ..Default::default()
+    };
+
+    if !state.sync {
+        return match state.tx.send(msg).await {
+            Ok(()) => StatusCode::ACCEPTED.into_response(),
+            Err(_) => StatusCode::SERVICE_UNAVAILABLE.into_response(),
+        };
+    }
+
+    let (reply_tx, reply_rx) = oneshot::channel();
+    state.pending.lock().unwrap().insert(id.clone(), reply_tx);
+    if state.tx.send(msg).await.is_err() {
+        state.pending.lock().unwrap().remove(&id);
+        return StatusCode::SERVICE_UNAVAILABLE.into_response();
+    }
+
+    match tokio::time::timeout(state.timeout, reply_rx).await {
+        Ok(Ok(reply)) => (StatusCode::OK, reply).into_response(),
+        // Dropped unanswered: no job takes this webhook
+        Ok(Err(_)) => StatusCode::NOT_FOUND.into_response(),
+        Err(_) => {
+            // No job replied in time — still accepted
+            state.pending.lock().unwrap().remove(&id);
+            StatusCode::ACCEPTED.into_response()
+        }
+    }
+}
+
+/// HTTP listener turning authenticated POSTs into messages.
+pub struct WebhookChannel {
+    listen: String,
+    auth: WebhookAuth,
+    key: String,
+    signature_header: String,
+    sync: bool,
+    timeout: Duration,
+    pending: PendingReplies,
+}
+
+impl WebhookChannel {
+    pub fn new(
+        listen: String,
+        auth: WebhookAuth,
+        key: String,
+        signature_header: String,
+        sync: bool,
+        timeout: Duration,
+    ) -> Self {
+        Self {
+            listen,
+            auth,
+            key,
+            signature_header,
+            sync,
+            timeout,
+            pending: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    async fn serve(
+        &self,
+        listener: TcpListener,
+        tx: mpsc::Sender<IncomingMessage>,
+    ) -> crate::error::Result<()> {
+        let state = Arc::new(HookState {
+            auth: self.auth,
+            key: self.key.clone(),
+            signature_header: self.signature_header.clone(),
+            sync: self.sync,
+            timeout: self.timeout,
+            pending: Arc::clone(&self.pending),
+            next_id: AtomicU64::new(0),
+            tx,
+        });
+        let app = Router::new().fallback(handle).with_state(state);
+
+        axum::serve(listener, app)
+            .await
+            .map_err(|e| crate::error::Error::Channel(format!("webhook server failed: {e}")))
+    }
+}
+
+#[async_trait::async_trait]
+impl Channel for WebhookChannel {
+    async fn start(&self, tx: mpsc::Sender<IncomingMessage>) -> crate::error::Result<()> {
+        let listener = TcpListener::bind(&self.listen).await.map_err(|e| {
+            crate::error::Error::Channel(format!("cannot listen on {}: {e}", self.listen))
+        })?;
+        tracing::info!("webhook listening on {}", self.listen);
+        self.serve(listener, tx).await
+    }
+
+    async fn send(&self, to: &str, message: &str) -> crate::error::Result<()> {
+        match self.pending.lock().unwrap().remove(to) {
+            Some(reply) => {
+                // Caller may have timed out in the meantime
+                let _ = reply.send(message.to_string());
+            }
+            None => tracing::debug!("webhook request {to} no longer waiting, dropping reply"),
+        }
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        "webhook"
+    }
+
+    fn unclaimed(&self, msg: &IncomingMessage) {
+        self.pending.lock().unwrap().remove(&msg.sender);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sign(key: &str, body: &[u8]) -> String {
+        let mut mac = Hmac::<Sha256>::new_from_slice(key.as_bytes()).unwrap();
+        mac.update(body);
+        format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+    }
+
+    fn channel(auth: WebhookAuth, sync: bool) -> Arc<WebhookChannel> {
+        Arc::new(WebhookChannel::new(
+            "127.0.0.1:0".into(),
+            auth,
+            "s3cret".into(),
+            "X-Hub-Signature-256".into(),
+            sync,
+            Duration::from_secs(5),
+        ))
+    }
+
+    async fn spawn(ch: &Arc<WebhookChannel>) -> (String, mpsc::Receiver<IncomingMessage>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let (tx, rx) = mpsc::channel(10);
+        let ch = Arc::clone(ch);
+        tokio::spawn(async move { ch.serve(listener, tx).await });
+        (url, rx)
+    }
+
+    #[test]
+    fn test_webhook_channel_name() {
+        assert_eq!(channel(WebhookAuth::Hmac, false).name(), "webhook");
+    }
+
+    #[test]
+    fn test_verify_signature() {
+        let sig = sign("key", b"payload");
+        assert!(verify_signature("key", b"payload", &sig));
+        assert!(verify_signature(
+            "key",
+            b"payload",
+            sig.trim_start_matches("sha256=")
+        ));
+        assert!(!verify_signature("other", b"payload", &sig));
+        assert!(!verify_signature("key", b"tampered", &sig));
+        assert!(!verify_signature("key", b"payload", "sha256=zz"));
+    }
+
+    #[test]
+    fn test_verify_bearer() {
+        assert!(verify_bearer("tok", Some("Bearer tok")));
+        assert!(!verify_bearer("tok", Some("Bearer nope")));
+        assert!(!verify_bearer("tok", Some("tok")));
+        assert!(!verify_bearer("tok", None));
+    }
+
+    #[test]
+    fn test_flatten_json() {
+        let json: serde_json::Value = serde_json::json!({
+            "action": "opened",
+            "number": 7,
+            "draft": false,
+            "pull_request": {"title": "Fix", "labels": [{"name": "bug"}]},
+            "closed_at": null
+        });
+        let mut out = HashMap::new();
+        flatten_json(&json, "", &mut out);
+        assert_eq!(out["action"], "opened");
+        assert_eq!(out["number"], "7");
+        assert_eq!(out["draft"], "false");
+        assert_eq!(out["pull_request.title"], "Fix");
+        assert_eq!(out["pull_request.labels.0.name"], "bug");
+        assert_eq!(out["pull_request.labels"], r#"[{"name":"bug"}]"#);
+        assert_eq!(out["closed_at"], "");
+    }
+
+    #[tokio::test]
+    async fn test_webhook_rejects_bad_signature() {
+        let ch = channel(WebhookAuth::Hmac, false);
+        let (url, _rx) = spawn(&ch).await;
+        let resp = reqwest::Client::new()
+            .post(&url)
+            .header("X-Hub-Signature-256", "sha256=00")
+            .body("{}")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), 401);
+    }
+
+    #[tokio::test]
+    async fn test_webhook_accepts_signed_post() {
+        let ch = channel(WebhookAuth::Hmac, false);
+        let (url, mut rx) = spawn(&ch).await;
+        let body = r#"{"repository":{"full_name":"me/vatic"}}"#;
+        let resp = reqwest::Client::new()
+            .post(&url)
+            .header("X-Hub-Signature-256", sign("s3cret", body.as_bytes()))
+            .body(body)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), 202);
+
+        let msg = rx.recv().await.unwrap();
+        assert_eq!(msg.channel, "webhook");
+        assert_eq!(msg.text, body);
+        assert_eq!(msg.meta["repository.full_name"], "me/vatic");
+    }
+
+    #[tokio::test]
+    async fn test_webhook_sync_reply() {
+        let ch = channel(WebhookAuth::Bearer, true);
+        let (url, mut rx) = spawn(&ch).await;
+
+        let responder = Arc::clone(&ch);
+        tokio::spawn(async move {
+            let msg = rx.recv().await.unwrap();
+            responder.send(&msg.sender, "job done").await.unwrap();
+        });
+
+        let resp = reqwest::Client::new()
+            .post(&url)
+            .bearer_auth("s3cret")
+            .body("ping")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), 200);
+        assert_eq!(resp.text().await.unwrap(), "job done");
+    }
+
+    #[tokio::test]
+    async fn test_webhook_sync_unclaimed() {
+        let ch = channel(WebhookAuth::Bearer, true);
+        let (url, mut rx) = spawn(&ch).await;
+
+        let responder = Arc::clone(&ch);
+        tokio::spawn(async move {
+            let msg = rx.recv().await.unwrap();
+            responder.unclaimed(&msg);
+        });
+
+        let started = std::time::Instant::now();
+        let resp = reqwest::Client::new()
+            .post(&url)
+            .bearer_auth("s3cret")
+            .body("ping")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), 404);
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+}
@@ -94,6 +94,7 @@ impl Channel for WhatsAppChannel {
                                 channel: "whatsapp".to_string(),
                                 sender,
                                 text,
+                                ..Default::default()
                             };
                             let _ = tx.send(msg).await;
                         }
@@ -111,7 +112,10 @@ impl Channel for WhatsAppChannel {
             .await
             .map_err(|e| crate::error::Error::Channel(format!("whatsapp bot task failed: {e}")))?;
 
-        Ok(())
+        // The bot only returns when the connection is gone for good
+        Err(crate::error::Error::Channel(
+            "whatsapp bot task ended".to_string(),
+        ))
     }
 
     async fn send(&self, to: &str, message: &str) -> crate::error::Result<()> {
@@ -0,0 +1,613 @@
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+use futures_util::{SinkExt, StreamExt};
+use sasl::client::mechanisms::{Plain, Scram};
+use sasl::client::Mechanism;
+use sasl::common::scram::{Sha1, Sha256};
+use sasl::common::Credentials;
+use tokio::sync::mpsc;
+use tokio_xmpp::connect::{
+    DnsConfig, ServerConnector, StartTlsServerConnector, TcpServerConnector,
+};
+use tokio_xmpp::jid::Jid;
+use tokio_xmpp::parsers::bind::{BindQuery, BindResponse};
+use tokio_xmpp::parsers::iq::Iq;
+use tokio_xmpp::parsers::message::{Lang, Message, MessageType};
+use tokio_xmpp::parsers::muc::muc::{History, Muc};
+use tokio_xmpp::parsers::ns;
+use tokio_xmpp::parsers::ping::Ping;
+use tokio_xmpp::parsers::presence::Presence;
+use tokio_xmpp::parsers::sasl::{Auth, Nonza, Response};
+use tokio_xmpp::stanzastream::XmppStream;
+use tokio_xmpp::xmlstream::{ReadError, StreamHeader, Timeouts, XmppStreamElement};
+use tokio_xmpp::Stanza;
+
+use super::{Channel, IncomingMessage};
+
+const DELAY_NS: &str = "urn:xmpp:delay";
+const BIND_ID: &str = "vatic-bind";
+const PING_ID: &str = "vatic-ping";
+
+fn xmpp_err(context: &str, e: impl std::fmt::Display) -> crate::error::Error {
+    crate::error::Error::Channel(format!("xmpp {context}: {e}"))
+}
+
+/// Connection details, resolved from the channel config and `secrets.toml`.
+#[derive(Clone)]
+pub struct XmppSettings {
+    pub jid: String,
+    pub password: String,
+    /// Bare room JIDs to join.
+    pub rooms: Vec<String>,
+    /// Nickname in rooms, defaults to the JID's local part.
+    pub nick: String,
+    /// `host:port` to connect to instead of looking up SRV records.
+    pub server: Option<String>,
+    /// STARTTLS; off only for a local test server.
+    pub tls: bool,
+}
+
+/// Chats come from the peer's bare JID; in rooms the sender is the
+/// occupant JID (`room/nick`) and replies go to the room.
+/// Our own room echoes, delayed history and body-less stanzas are dropped.
+fn to_incoming(msg: &Message, rooms: &HashSet<String>, own_nick: &str) -> Option<IncomingMessage> {
+    let (_, body) = msg.get_best_body_cloned(vec![])?;
+    let from = msg.from.as_ref()?;
+    if msg.payloads.iter().any(|p| p.is("delay", DELAY_NS)) {
+        return None;
+    }
+
+    let bare = from.to_bare().to_string();
+    let (sender, reply_to, nick) = match msg.type_ {
+        MessageType::Groupchat if rooms.contains(&bare) => {
+            let nick = from.resource()?.to_string();
+            if nick == own_nick {
+                return None;
+            }
+            (from.to_string(), Some(bare), nick)
+        }
+        MessageType::Chat | MessageType::Normal => {
+            let nick = from.node().map(|n| n.to_string()).unwrap_or_default();
+            (bare, None, nick)
+        }
+        _ => return None,
+    };
+
+    let text = body.trim().to_string();
+    if text.is_empty() {
+        return None;
+    }
+
+    let mut meta = HashMap::new();
+    meta.insert("nick".to_string(), nick);
+    meta.insert("from".to_string(), from.to_string());
+    Some(IncomingMessage {
+        channel: "xmpp".to_string(),
+        sender,
+        text,
+        meta,
+        reply_to,
+        ..Default::default()
+    })
+}
+
+fn outgoing(to: &str, body: &str, rooms: &HashSet<String>) -> crate::error::Result<Message> {
+    let jid = Jid::new(to)
+        .map_err(|e| crate::error::Error::Channel(format!("invalid xmpp address '{to}': {e}")))?;
+    let message = if rooms.contains(&jid.to_bare().to_string()) {
+        Message::groupchat(jid)
+    } else {
+        Message::chat(jid)
+    };
+    Ok(message.with_body(Lang::new(), body.to_string()))
+}
+
+/// One-to-one chats and MUC rooms over a regular client connection.
+pub struct XmppChannel {
+    settings: XmppSettings,
+    rooms: HashSet<String>,
+    outbox: Arc<Mutex<Option<mpsc::UnboundedSender<Message>>>>,
+}
+
+impl XmppChannel {
+    pub fn new(settings: XmppSettings) -> Self {
+        let rooms = settings.rooms.iter().cloned().collect();
+        Self {
+            settings,
+            rooms,
+            outbox: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Connect, authenticate and bind a resource. tokio-xmpp's `Client`
+    /// would retry all of this forever in the background, so we drive the
+    /// stream ourselves and leave retries to the supervisor.
+    async fn connect(&self) -> crate::error::Result<(Jid, XmppStream)> {
+        let s = &self.settings;
+        let jid = Jid::new(&s.jid).map_err(|e| {
+            crate::error::Error::Channel(format!("invalid xmpp jid '{}': {e}", s.jid))
+        })?;
+
+        let dns = match s.server.as_deref().and_then(|a| a.rsplit_once(':')) {
+            Some((host, port)) => {
+                let port = port.parse().map_err(|_| {
+                    crate::error::Error::Channel(format!("invalid xmpp server port '{port}'"))
+                })?;
+                DnsConfig::no_srv(host, port)
+            }
+            None => DnsConfig::srv_default_client(jid.domain().as_ref()),
+        };
+
+        if s.tls {
+            login(StartTlsServerConnector::from(dns), jid, &s.password).await
+        } else {
+            login(TcpServerConnector::from(dns), jid, &s.password).await
+        }
+    }
+
+    /// Announce ourselves and join rooms without replaying their history.
+    async fn go_online(&self, stream: &mut XmppStream) -> crate::error::Result<()> {
+        let mut stanzas: Vec<Stanza> = vec![Presence::available().into()];
+        for room in &self.settings.rooms {
+            match Jid::new(&format!("{room}/{}", self.settings.nick)) {
+                Ok(occupant) => stanzas.push(
+                    Presence::available()
+                        .with_to(occupant)
+                        .with_payload(Muc::new().with_history(History::new().with_maxstanzas(0)))
+                        .into(),
+                ),
+                Err(e) => tracing::error!("invalid xmpp room '{room}': {e}"),
+            }
+        }
+        for stanza in stanzas {
+            send_stanza(stream, stanza).await?;
+        }
+        Ok(())
+    }
+
+    /// Run one session. `Ok(())` means the receiver is gone.
+    async fn session(
+        &self,
+        stream: &mut XmppStream,
+        mut out_rx: mpsc::UnboundedReceiver<Message>,
+        tx: &mpsc::Sender<IncomingMessage>,
+    ) -> crate::error::Result<()> {
+        self.go_online(stream).await?;
+        loop {
+            tokio::select! {
+                item = stream.next() => match item {
+                    Some(Ok(XmppStreamElement::Stanza(Stanza::Message(msg)))) => {
+                        if let Some(incoming) = to_incoming(&msg, &self.rooms, &self.settings.nick) {
+                            if tx.send(incoming).await.is_err() {
+                                return Ok(()); // receiver dropped
+                            }
+                        }
+                    }
+                    Some(Ok(XmppStreamElement::StreamError(e))) => return Err(xmpp_err("stream failed", e)),
+                    Some(Ok(_)) => {}
+                    // Quiet for a while: make the server answer before the
+                    // hard timeout declares the connection dead
+                    Some(Err(ReadError::SoftTimeout)) => {
+                        send_stanza(stream, Iq::from_get(PING_ID, Ping).into()).await?;
+                    }
+                    Some(Err(ReadError::ParseError(e))) => tracing::warn!("xmpp skipped invalid element: {e}"),
+                    Some(Err(e)) => return Err(xmpp_err("connection lost", e)),
+                    None => return Err(xmpp_err("connection lost", "stream closed")),
+                },
+                Some(msg) = out_rx.recv() => send_stanza(stream, msg.into()).await?,
+            }
+        }
+    }
+}
+
+async fn send_stanza(stream: &mut XmppStream, stanza: Stanza) -> crate::error::Result<()> {
+    stream
+        .send(&XmppStreamElement::Stanza(stanza))
+        .await
+        .map_err(|e| xmpp_err("send failed", e))
+}
+
+/// Best of SCRAM-SHA-256, SCRAM-SHA-1 and PLAIN that the server offers.
+fn sasl_mechanism(offered: &[String], creds: Credentials) -> Option<Box<dyn Mechanism + Send>> {
+    let candidates = [
+        Scram::<Sha256>::from_credentials(creds.clone())
+            .map(|m| Box::new(m) as Box<dyn Mechanism + Send>),
+        Scram::<Sha1>::from_credentials(creds.clone())
+            .map(|m| Box::new(m) as Box<dyn Mechanism + Send>),
+        Plain::from_credentials(creds).map(|m| Box::new(m) as Box<dyn Mechanism + Send>),
+    ];
+    candidates
+        .into_iter()
+        .flatten()
+        .find(|m| offered.iter().any(|o| o == m.name()))
+}
+
+async fn login<C: ServerConnector>(
+    connector: C,
+    jid: Jid,
+    password: &str,
+) -> crate::error::Result<(Jid, XmppStream)> {
+    let (stream, binding) = connector
+        .connect(&jid, ns::JABBER_CLIENT, Timeouts::default())
+        .await
+        .map_err(|e| xmpp_err("connect failed", e))?;
+    let (features, mut stream) = stream
+        .recv_features::<XmppStreamElement>()
+        .await
+        .map_err(|e| xmpp_err("connect failed", e))?;
+
+    let creds = Credentials::default()
+        .with_username(jid.node().map(|n| n.as_str()).unwrap_or_default())
+        .with_password(password)
+        .with_channel_binding(binding);
+    let mut mechanism = sasl_mechanism(&features.sasl_mechanisms.mechanisms, creds)
+        .ok_or_else(|| xmpp_err("login failed", "no supported SASL mechanism"))?;
+    let auth = Auth {
+        mechanism: mechanism
+            .name()
+            .parse()
+            .map_err(|e| xmpp_err("login failed", e))?,
+        data: mechanism.initial(),
+    };
+    stream
+        .send(&XmppStreamElement::Sasl(Nonza::Auth(auth)))
+        .await
+        .map_err(|e| xmpp_err("login failed", e))?;
+
+    loop {
+        let reply = match stream.next().await {
+            Some(Ok(XmppStreamElement::Sasl(Nonza::Challenge(challenge)))) => mechanism
+                .response(&challenge.data)
+                .map_err(|e| xmpp_err("login failed", e))?,
+            Some(Ok(XmppStreamElement::Sasl(Nonza::Success(success)))) => {
+                mechanism
+                    .success(&success.data)
+                    .map_err(|e| xmpp_err("login failed", e))?;
+                break;
+            }
+            Some(Ok(XmppStreamElement::Sasl(Nonza::Failure(failure)))) => {
+                return Err(xmpp_err(
+                    "login failed",
+                    format!("{:?}", failure.defined_condition),
+                ));
+            }
+            Some(Ok(_)) | Some(Err(ReadError::SoftTimeout)) => continue,
+            Some(Err(e)) => return Err(xmpp_err("login failed", e)),
+            None => return Err(xmpp_err("login failed", "stream closed")),
+        };
+        stream
+            .send(&XmppStreamElement::Sasl(Nonza::Response(Response {
+                data: reply,
+            })))
+            .await
+            .map_err(|e| xmpp_err("login failed", e))?;
+    }
+
+    let header = StreamHeader {
+        to: Some(Cow::Borrowed(jid.domain().as_str())),
+        ..Default::default()
+    };
+    let (_, mut stream) = stream
+        .initiate_reset()
+        .send_header(header)
+        .await
+        .map_err(|e| xmpp_err("login failed", e))?
+        .recv_features::<XmppStreamElement>()
+        .await
+        .map_err(|e| xmpp_err("login failed", e))?;
+
+    let bind = Iq::from_set(
+        BIND_ID,
+        BindQuery::new(jid.resource().map(|r| r.to_string())),
+    );
+    stream
+        .send(&XmppStreamElement::Stanza(bind.into()))
+        .await
+        .map_err(|e| xmpp_err("resource binding failed", e))?;
+    loop {
+        match stream.next().await {
+            Some(Ok(XmppStreamElement::Stanza(Stanza::Iq(Iq::Result {
+                id,
+                payload: Some(payload),
+                ..
+            }))))
+                if id == BIND_ID =>
+            {
+                let bound = BindResponse::try_from(payload)
+                    .map_err(|e| xmpp_err("resource binding failed", e))?;
+                return Ok((Jid::from(bound), stream.box_stream()));
+            }
+            Some(Ok(XmppStreamElement::Stanza(Stanza::Iq(Iq::Error { id, error, .. }))))
+                if id == BIND_ID =>
+            {
+                return Err(xmpp_err(
+                    "resource binding failed",
+                    format!("{:?}", error.defined_condition),
+                ));
+            }
+            Some(Ok(_)) | Some(Err(ReadError::SoftTimeout)) => {}
+            Some(Err(e)) => return Err(xmpp_err("resource binding failed", e)),
+            None => return Err(xmpp_err("resource binding failed", "stream closed")),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Channel for XmppChannel {
+    async fn start(&self, tx: mpsc::Sender<IncomingMessage>) -> crate::error::Result<()> {
+        let (jid, mut stream) = self.connect().await?;
+        tracing::info!("xmpp online as {jid}");
+
+        let (out_tx, out_rx) = mpsc::unbounded_channel::<Message>();
+        *self.outbox.lock().unwrap() = Some(out_tx);
+        let result = self.session(&mut stream, out_rx, &tx).await;
+        *self.outbox.lock().unwrap() = None;
+
+        if result.is_ok() {
+            let _ = stream.shutdown().await;
+        }
+        result
+    }
+
+    async fn send(&self, to: &str, message: &str) -> crate::error::Result<()> {
+        let stanza = outgoing(to, message, &self.rooms)?;
+        let outbox = self.outbox.lock().unwrap();
+        let Some(out) = outbox.as_ref() else {
+            return Err(crate::error::Error::Channel(
+                "xmpp is not connected".to_string(),
+            ));
+        };
+        out.send(stanza)
+            .map_err(|_| crate::error::Error::Channel("xmpp send failed".to_string()))
+    }
+
+    fn name(&self) -> &str {
+        "xmpp"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+    use tokio_xmpp::minidom::Element;
+
+    fn parse(xml: &str) -> Message {
+        let elem: Element = xml.parse().unwrap();
+        Message::try_from(elem).unwrap()
+    }
+
+    fn rooms() -> HashSet<String> {
+        HashSet::from(["dev@conference.example.org".to_string()])
+    }
+
+    fn settings() -> XmppSettings {
+        XmppSettings {
+            jid: "vatic@example.org".into(),
+            password: "pw".into(),
+            rooms: vec!["dev@conference.example.org".into()],
+            nick: "vatic".into(),
+            server: None,
+            tls: true,
+        }
+    }
+
+    #[test]
+    fn test_xmpp_channel_name() {
+        assert_eq!(XmppChannel::new(settings()).name(), "xmpp");
+    }
+
+    #[test]
+    fn test_direct_chat_uses_bare_jid() {
+        let msg = parse(
+            "<message xmlns='jabber:client' from='jane@example.org/phone' type='chat'><body>hi</body></message>",
+        );
+        let incoming = to_incoming(&msg, &rooms(), "vatic").unwrap();
+        assert_eq!(incoming.channel, "xmpp");
+        assert_eq!(incoming.sender, "jane@example.org");
+        assert_eq!(incoming.reply_address(), "jane@example.org");
+        assert_eq!(incoming.text, "hi");
+        assert_eq!(incoming.meta["nick"], "jane");
+    }
+
+    #[test]
+    fn test_groupchat_replies_to_room() {
+        let msg = parse(
+            "<message xmlns='jabber:client' from='dev@conference.example.org/jane' type='groupchat'><body>status?</body></message>",
+        );
+        let incoming = to_incoming(&msg, &rooms(), "vatic").unwrap();
+        assert_eq!(incoming.sender, "dev@conference.example.org/jane");
+        assert_eq!(incoming.reply_address(), "dev@conference.example.org");
+        assert_eq!(incoming.meta["nick"], "jane");
+    }
+
+    #[test]
+    fn test_groupchat_skips_own_and_history() {
+        let own = parse(
+            "<message xmlns='jabber:client' from='dev@conference.example.org/vatic' type='groupchat'><body>x</body></message>",
+        );
+        assert!(to_incoming(&own, &rooms(), "vatic").is_none());
+
+        let delayed = parse(
+            "<message xmlns='jabber:client' from='dev@conference.example.org/jane' type='groupchat'><body>old</body><delay xmlns='urn:xmpp:delay' stamp='2026-01-01T00:00:00Z'/></message>",
+        );
+        assert!(to_incoming(&delayed, &rooms(), "vatic").is_none());
+    }
+
+    #[test]
+    fn test_chat_state_without_body_skipped() {
+        let msg = parse(
+            "<message xmlns='jabber:client' from='jane@example.org/phone' type='chat'><composing xmlns='http://jabber.org/protocol/chatstates'/></message>",
+        );
+        assert!(to_incoming(&msg, &rooms(), "vatic").is_none());
+    }
+
+    #[test]
+    fn test_outgoing_type() {
+        let room = outgoing("dev@conference.example.org", "hi", &rooms()).unwrap();
+        assert_eq!(room.type_, MessageType::Groupchat);
+        let chat = outgoing("jane@example.org", "hi", &rooms()).unwrap();
+        assert_eq!(chat.type_, MessageType::Chat);
+        assert_eq!(chat.get_best_body_cloned(vec![]).unwrap().1, "hi");
+    }
+
+    #[tokio::test]
+    async fn test_send_without_connection() {
+        let ch = XmppChannel::new(settings());
+        assert!(ch.send("jane@example.org", "hi").await.is_err());
+    }
+
+    fn local(port: u16) -> XmppSettings {
+        XmppSettings {
+            jid: "vatic@localhost".into(),
+            password: "vatic".into(),
+            rooms: vec![],
+            nick: "vatic".into(),
+            server: Some(format!("127.0.0.1:{port}")),
+            tls: false,
+        }
+    }
+
+    /// Refused and dropped connections go back to the supervisor instead
+    /// of being retried here.
+    #[tokio::test]
+    async fn test_start_fails_without_server() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            drop(stream);
+        });
+        let (tx, _rx) = mpsc::channel(10);
+        let err = XmppChannel::new(local(port)).start(tx).await.unwrap_err();
+        assert!(err.to_string().contains("xmpp connect failed"), "{err}");
+
+        let (tx, _rx) = mpsc::channel(10);
+        let err = XmppChannel::new(local(port)).start(tx).await.unwrap_err();
+        assert!(err.to_string().contains("xmpp connect failed"), "{err}");
+    }
+
+    const SERVER_HEADER: &[u8] = b"<?xml version='1.0'?><stream:stream xmlns='jabber:client' \
+        xmlns:stream='http://etherx.jabber.org/streams' id='s1' from='localhost' version='1.0'>";
+
+    /// Read until `pattern` shows up, keeping everything seen so far.
+    async fn read_until(stream: &mut TcpStream, seen: &mut String, pattern: &str) {
+        let mut buf = [0u8; 1024];
+        while !seen.contains(pattern) {
+            let read = tokio::time::timeout(Duration::from_secs(5), stream.read(&mut buf));
+            let n = read.await.expect(seen).unwrap();
+            assert!(n > 0, "client hung up waiting for {pattern}: {seen}");
+            seen.push_str(&String::from_utf8_lossy(&buf[..n]));
+        }
+    }
+
+    /// Fake server up to the SASL exchange, answering `auth` with `reply`.
+    async fn fake_login(listener: TcpListener, reply: &[u8]) -> (TcpStream, String) {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let mut seen = String::new();
+        read_until(&mut stream, &mut seen, "<stream:stream").await;
+        stream.write_all(SERVER_HEADER).await.unwrap();
+        stream
+            .write_all(
+                b"<stream:features><mechanisms xmlns='urn:ietf:params:xml:ns:xmpp-sasl'>\
+                  <mechanism>PLAIN</mechanism></mechanisms></stream:features>",
+            )
+            .await
+            .unwrap();
+        read_until(&mut stream, &mut seen, "</auth>").await;
+        stream.write_all(reply).await.unwrap();
+        (stream, seen)
+    }
+
+    #[tokio::test]
+    async fn test_start_fails_on_rejected_login() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let failure =
+                b"<failure xmlns='urn:ietf:params:xml:ns:xmpp-sasl'><not-authorized/></failure>";
+            let (mut stream, _) = fake_login(listener, failure).await;
+            // Hold the connection open; only the failure may end the login
+            let _ = stream.read(&mut [0u8; 64]).await;
+        });
+
+        let (tx, _rx) = mpsc::channel(10);
+        let err = XmppChannel::new(local(port)).start(tx).await.unwrap_err();
+        assert!(err.to_string().contains("xmpp login failed"), "{err}");
+    }
+
+    /// Logs in, binds, relays a chat message and sends the reply; the
+    /// server closing the stream then ends `start` with an error.
+    #[tokio::test]
+    async fn test_roundtrip_with_fake_server() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = tokio::spawn(async move {
+            let success = b"<success xmlns='urn:ietf:params:xml:ns:xmpp-sasl'/>";
+            let (mut stream, _) = fake_login(listener, success).await;
+            let mut seen = String::new();
+            read_until(&mut stream, &mut seen, "<stream:stream").await;
+            stream.write_all(SERVER_HEADER).await.unwrap();
+            stream
+                .write_all(
+                    b"<stream:features><bind xmlns='urn:ietf:params:xml:ns:xmpp-bind'/>\
+                      </stream:features>",
+                )
+                .await
+                .unwrap();
+            read_until(&mut stream, &mut seen, "</iq>").await;
+            stream
+                .write_all(
+                    b"<iq type='result' id='vatic-bind'><bind xmlns='urn:ietf:params:xml:ns:xmpp-bind'>\
+                      <jid>vatic@localhost/r1</jid></bind></iq>",
+                )
+                .await
+                .unwrap();
+            read_until(&mut stream, &mut seen, "<presence").await;
+            stream
+                .write_all(
+                    b"<message from='jane@localhost/phone' type='chat'><body>ping</body></message>",
+                )
+                .await
+                .unwrap();
+            read_until(&mut stream, &mut seen, "pong</body>").await;
+            stream.write_all(b"</stream:stream>").await.unwrap();
+            seen
+        });
+
+        let ch = Arc::new(XmppChannel::new(local(port)));
+        let (tx, mut rx) = mpsc::channel(10);
+        let runner = Arc::clone(&ch);
+        let started = tokio::spawn(async move { runner.start(tx).await });
+
+        let msg = rx.recv().await.unwrap();
+        assert_eq!(msg.sender, "jane@localhost");
+        assert_eq!(msg.text, "ping");
+        ch.send("jane@localhost", "pong").await.unwrap();
+
+        let seen = server.await.unwrap();
+        assert!(seen.contains("to='jane@localhost'") || seen.contains("to=\"jane@localhost\""));
+        assert!(started.await.unwrap().is_err());
+        assert!(ch.send("jane@localhost", "late").await.is_err());
+    }
+
+    /// Against a local Prosody with an account `vatic@localhost` / `vatic`
+    /// and plaintext c2s allowed. Run with `cargo test -- --ignored xmpp_local`.
+    #[tokio::test]
+    #[ignore]
+    async fn test_xmpp_local_server() {
+        let ch = Arc::new(XmppChannel::new(local(5222)));
+        let (tx, _rx) = mpsc::channel(10);
+        let runner = Arc::clone(&ch);
+        tokio::spawn(async move { runner.start(tx).await });
+
+        tokio::time::sleep(Duration::from_secs(3)).await;
+        ch.send("vatic@localhost", "hello from vatic")
+            .await
+            .unwrap();
+    }
+}
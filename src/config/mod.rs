@@ -8,7 +8,10 @@ use crate::error::{Error, Result};
 
 use self::dictionary::Dictionary;
 use self::secrets::Secrets;
-use self::types::{parse_channel_config, parse_job_config, ChannelConfig, JobConfig};
+use self::types::{
+    parse_channel_config, parse_daemon_config, parse_job_config, ChannelConfig, DaemonConfig,
+    JobConfig,
+};
 
 #[derive(Debug, Clone)]
 pub struct AppConfig {
@@ -18,6 +21,7 @@ pub struct AppConfig {
     pub secrets: Secrets,
     pub jobs: Vec<(String, JobConfig)>,
     pub channels: Vec<(String, ChannelConfig)>,
+    pub daemon: DaemonConfig,
 }
 
 impl AppConfig {
@@ -34,6 +38,7 @@ impl AppConfig {
 
         let jobs = load_jobs(&config_dir)?;
         let channels = load_channels(&config_dir)?;
+        let daemon = load_daemon(&config_dir.join("daemon.toml"))?;
 
         Ok(Self {
             config_dir,
@@ -42,6 +47,7 @@ impl AppConfig {
             secrets,
             jobs,
             channels,
+            daemon,
         })
     }
 
@@ -63,7 +69,7 @@ fn resolve_config_dir() -> Result<PathBuf> {
 }
 
 /// `$XDG_DATA_HOME/vatic` or `~/.local/share/vatic/`.
-fn resolve_data_dir() -> Result<PathBuf> {
+pub fn resolve_data_dir() -> Result<PathBuf> {
     if let Ok(xdg) = std::env::var("XDG_DATA_HOME") {
         return Ok(PathBuf::from(xdg).join("vatic"));
     }
@@ -118,6 +124,16 @@ fn filename_key(path: &Path) -> String {
         .to_string()
 }
 
+/// Defaults if the file doesn't exist.
+fn load_daemon(path: &Path) -> Result<DaemonConfig> {
+    if !path.exists() {
+        return Ok(DaemonConfig::default());
+    }
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| Error::Config(format!("cannot read {}: {e}", path.display())))?;
+    parse_daemon_config(&content)
+}
+
 /// Load `config_dir/channels/*.toml`, keyed by filename.
 fn load_channels(config_dir: &Path) -> Result<Vec<(String, ChannelConfig)>> {
     load_toml_dir(&config_dir.join("channels"), |content, path| {
@@ -130,7 +146,7 @@ fn load_channels(config_dir: &Path) -> Result<Vec<(String, ChannelConfig)>> {
 /// Load `config_dir/jobs/*.toml`, keyed by alias or filename.
 fn load_jobs(config_dir: &Path) -> Result<Vec<(String, JobConfig)>> {
     load_toml_dir(&config_dir.join("jobs"), |content, path| {
-        let table: toml::Table = toml::from_str(&content)
+        let table: toml::Table = toml::from_str(content)
             .map_err(|e| Error::Config(format!("invalid TOML in {}: {e}", path.display())))?;
         let value = toml::Value::Table(table);
         let config = parse_job_config(&value)?;
@@ -4,7 +4,7 @@ use std::path::Path;
 
 use crate::error::{Error, Result};
 
-#[derive(Clone)]
+#[derive(Clone, PartialEq)]
 pub struct Secret {
     pub key: String,
     pub header: String,
@@ -21,7 +21,7 @@ impl std::fmt::Debug for Secret {
     }
 }
 
-#[derive(Clone, Default)]
+#[derive(Clone, Default, PartialEq)]
 pub struct Secrets {
     pub entries: HashMap<String, Secret>,
 }
@@ -98,6 +98,13 @@ impl Secrets {
     pub fn get(&self, name: &str) -> Option<&Secret> {
         self.entries.get(name)
     }
+
+    /// The `key` of a named secret, for configs that reference credentials by name.
+    pub fn resolve(&self, name: &str) -> Result<&str> {
+        self.get(name)
+            .map(|s| s.key.as_str())
+            .ok_or_else(|| Error::Config(format!("unknown secret '{name}' in secrets.toml")))
+    }
 }
 
 #[cfg(test)]
@@ -166,6 +173,22 @@ match = "https://api.github.com"
         assert!(secrets.get("missing").is_none());
     }
 
+    #[test]
+    fn test_resolve_secret() {
+        let mut secrets = Secrets::default();
+        secrets.entries.insert(
+            "mail".into(),
+            Secret {
+                key: "hunter2".into(),
+                header: "bearer".into(),
+                match_url: String::new(),
+            },
+        );
+        assert_eq!(secrets.resolve("mail").unwrap(), "hunter2");
+        let err = secrets.resolve("missing").unwrap_err();
+        assert!(err.to_string().contains("unknown secret 'missing'"));
+    }
+
     #[test]
     fn test_secret_with_defaults() {
         let dir = std::env::temp_dir().join("vatic_test_secrets_defaults");
@@ -3,12 +3,12 @@ use serde::Deserialize;
 use crate::error::{Error, Result};
 
 /// Loaded from `~/.config/vatic/channels/*.toml`.
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize)]
 pub struct ChannelConfig {
     pub channel: ChannelSection,
 }
 
-#[derive(Clone, Deserialize)]
+#[derive(Clone, PartialEq, Deserialize)]
 #[serde(tag = "type")]
 pub enum ChannelSection {
     #[serde(rename = "stdin")]
@@ -26,10 +26,188 @@ pub enum ChannelSection {
         poll_interval: Option<u64>,
         account: Option<String>,
     },
+    #[serde(rename = "maildir")]
+    Maildir {
+        path: String,
+        poll_interval: Option<u64>,
+        /// msmtp account used for replies.
+        account: Option<String>,
+    },
+    #[serde(rename = "imap")]
+    Imap {
+        host: String,
+        port: Option<u16>,
+        user: String,
+        /// Name of the `secrets.toml` entry holding the password.
+        secret: String,
+        mailbox: Option<String>,
+        /// Defaults to `host`.
+        smtp_host: Option<String>,
+        smtp_port: Option<u16>,
+        /// Defaults to `user`.
+        from: Option<String>,
+        /// Only turn off for a local test server.
+        tls: Option<bool>,
+    },
+    #[serde(rename = "webhook")]
+    Webhook {
+        /// Address to listen on, defaults to `127.0.0.1:8787`.
+        listen: Option<String>,
+        /// Name of the `secrets.toml` entry holding the HMAC key or bearer token.
+        secret: String,
+        auth: Option<WebhookAuth>,
+        /// Header carrying the HMAC signature, defaults to `X-Hub-Signature-256`.
+        signature_header: Option<String>,
+        /// Hold the HTTP response until a job replies.
+        sync: Option<bool>,
+        /// Seconds to wait for a sync reply before answering 202.
+        timeout: Option<u64>,
+    },
+    #[serde(rename = "http")]
+    Http {
+        /// Address to serve the chat UI on, defaults to `127.0.0.1:8788`.
+        listen: Option<String>,
+        /// `secrets.toml` entry holding the token. Without one a token is
+        /// generated on first start and kept in the data dir.
+        secret: Option<String>,
+    },
+    #[serde(rename = "signal")]
+    Signal {
+        /// Account number, needed when signal-cli runs in multi-account mode.
+        account: Option<String>,
+        /// Unix socket of `signal-cli daemon --socket`.
+        socket: Option<String>,
+        /// `host:port` of `signal-cli daemon --tcp`, used instead of `socket`.
+        tcp: Option<String>,
+        /// Where signal-cli stores received attachments.
+        attachments_dir: Option<String>,
+    },
+    #[serde(rename = "irc")]
+    Irc {
+        server: String,
+        port: Option<u16>,
+        tls: Option<bool>,
+        nick: String,
+        /// SASL account, defaults to `nick`.
+        user: Option<String>,
+        /// `secrets.toml` entry with the SASL password; enables SASL.
+        secret: Option<String>,
+        #[serde(default)]
+        channels: Vec<String>,
+    },
+    #[serde(rename = "xmpp")]
+    Xmpp {
+        jid: String,
+        password: Option<String>,
+        /// `secrets.toml` entry with the password, preferred over `password`.
+        secret: Option<String>,
+        #[serde(default)]
+        rooms: Vec<String>,
+        /// Room nickname, defaults to the JID's local part.
+        nick: Option<String>,
+        /// `host:port`, skips the SRV lookup.
+        server: Option<String>,
+        tls: Option<bool>,
+    },
+    #[serde(rename = "slack")]
+    Slack {
+        /// `secrets.toml` entry with the app-level token (`xapp-...`).
+        app_secret: String,
+        /// `secrets.toml` entry with the bot token (`xoxb-...`).
+        bot_secret: String,
+    },
+    #[serde(rename = "discord")]
+    Discord {
+        /// `secrets.toml` entry with the bot token.
+        secret: String,
+        /// Guild ids to answer in; empty means all.
+        #[serde(default)]
+        guilds: Vec<String>,
+        /// Channel ids to answer in; empty means all.
+        #[serde(default)]
+        channels: Vec<String>,
+    },
+    #[serde(rename = "mqtt")]
+    Mqtt {
+        host: String,
+        port: Option<u16>,
+        tls: Option<bool>,
+        client_id: Option<String>,
+        user: Option<String>,
+        /// `secrets.toml` entry with the broker password.
+        secret: Option<String>,
+        /// Topic filters to subscribe to.
+        #[serde(default)]
+        topics: Vec<String>,
+        /// Reply topic, `{topic}` is replaced by the incoming topic.
+        reply_topic: Option<String>,
+        qos: Option<u8>,
+    },
+    #[serde(rename = "ntfy")]
+    Ntfy {
+        /// Defaults to https://ntfy.sh.
+        server: Option<String>,
+        topic: String,
+        /// `secrets.toml` entry with an access token.
+        secret: Option<String>,
+        title: Option<String>,
+        priority: Option<u8>,
+        #[serde(default)]
+        tags: Vec<String>,
+        click: Option<String>,
+    },
+    #[serde(rename = "gotify")]
+    Gotify {
+        server: String,
+        /// `secrets.toml` entry with the application token (for sending).
+        app_secret: String,
+        /// `secrets.toml` entry with the client token (for the stream).
+        client_secret: String,
+        title: Option<String>,
+        priority: Option<u8>,
+        click: Option<String>,
+    },
+    #[serde(rename = "feed")]
+    Feed {
+        /// RSS/Atom feed URLs.
+        urls: Vec<String>,
+        /// Seconds between polls.
+        poll_interval: Option<u64>,
+    },
+    #[serde(rename = "watch")]
+    Watch {
+        /// Directories to watch.
+        paths: Vec<String>,
+        /// Globs for the files to report; empty means all.
+        #[serde(default)]
+        patterns: Vec<String>,
+        recursive: Option<bool>,
+        /// Milliseconds of quiet before a changed file is reported.
+        debounce: Option<u64>,
+        /// Scan instead of using inotify.
+        poll: Option<bool>,
+    },
+    #[serde(rename = "calendar")]
+    Calendar {
+        /// `.ics` files or vdir directories.
+        paths: Vec<String>,
+        /// Minutes before an event starts to fire.
+        lead: Option<u64>,
+        /// Seconds between checks.
+        poll_interval: Option<u64>,
+    },
     #[serde(rename = "whatsapp")]
     Whatsapp,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WebhookAuth {
+    #[default]
+    Hmac,
+    Bearer,
+}
+
 impl std::fmt::Debug for ChannelSection {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -53,11 +231,244 @@ impl std::fmt::Debug for ChannelSection {
                 .field("poll_interval", poll_interval)
                 .field("account", account)
                 .finish(),
+            ChannelSection::Maildir {
+                path,
+                poll_interval,
+                account,
+            } => f
+                .debug_struct("Maildir")
+                .field("path", path)
+                .field("poll_interval", poll_interval)
+                .field("account", account)
+                .finish(),
+            ChannelSection::Imap {
+                host,
+                port,
+                user,
+                mailbox,
+                smtp_host,
+                smtp_port,
+                from,
+                tls,
+                ..
+            } => f
+                .debug_struct("Imap")
+                .field("host", host)
+                .field("port", port)
+                .field("user", user)
+                .field("secret", &"***")
+                .field("mailbox", mailbox)
+                .field("smtp_host", smtp_host)
+                .field("smtp_port", smtp_port)
+                .field("from", from)
+                .field("tls", tls)
+                .finish(),
+            ChannelSection::Webhook {
+                listen,
+                auth,
+                signature_header,
+                sync,
+                timeout,
+                ..
+            } => f
+                .debug_struct("Webhook")
+                .field("listen", listen)
+                .field("secret", &"***")
+                .field("auth", auth)
+                .field("signature_header", signature_header)
+                .field("sync", sync)
+                .field("timeout", timeout)
+                .finish(),
+            ChannelSection::Http { listen, secret } => f
+                .debug_struct("Http")
+                .field("listen", listen)
+                .field("secret", secret)
+                .finish(),
+            ChannelSection::Signal {
+                account,
+                socket,
+                tcp,
+                attachments_dir,
+            } => f
+                .debug_struct("Signal")
+                .field("account", account)
+                .field("socket", socket)
+                .field("tcp", tcp)
+                .field("attachments_dir", attachments_dir)
+                .finish(),
+            ChannelSection::Irc {
+                server,
+                port,
+                tls,
+                nick,
+                user,
+                secret,
+                channels,
+            } => f
+                .debug_struct("Irc")
+                .field("server", server)
+                .field("port", port)
+                .field("tls", tls)
+                .field("nick", nick)
+                .field("user", user)
+                .field("secret", secret)
+                .field("channels", channels)
+                .finish(),
+            ChannelSection::Xmpp {
+                jid,
+                secret,
+                rooms,
+                nick,
+                server,
+                tls,
+                ..
+            } => f
+                .debug_struct("Xmpp")
+                .field("jid", jid)
+                .field("password", &"***")
+                .field("secret", secret)
+                .field("rooms", rooms)
+                .field("nick", nick)
+                .field("server", server)
+                .field("tls", tls)
+                .finish(),
+            ChannelSection::Slack {
+                app_secret,
+                bot_secret,
+            } => f
+                .debug_struct("Slack")
+                .field("app_secret", app_secret)
+                .field("bot_secret", bot_secret)
+                .finish(),
+            ChannelSection::Discord {
+                secret,
+                guilds,
+                channels,
+            } => f
+                .debug_struct("Discord")
+                .field("secret", secret)
+                .field("guilds", guilds)
+                .field("channels", channels)
+                .finish(),
+            ChannelSection::Mqtt {
+                host,
+                port,
+                tls,
+                client_id,
+                user,
+                secret,
+                topics,
+                reply_topic,
+                qos,
+            } => f
+                .debug_struct("Mqtt")
+                .field("host", host)
+                .field("port", port)
+                .field("tls", tls)
+                .field("client_id", client_id)
+                .field("user", user)
+                .field("secret", secret)
+                .field("topics", topics)
+                .field("reply_topic", reply_topic)
+                .field("qos", qos)
+                .finish(),
+            ChannelSection::Ntfy {
+                server,
+                topic,
+                secret,
+                title,
+                priority,
+                tags,
+                click,
+            } => f
+                .debug_struct("Ntfy")
+                .field("server", server)
+                .field("topic", topic)
+                .field("secret", secret)
+                .field("title", title)
+                .field("priority", priority)
+                .field("tags", tags)
+                .field("click", click)
+                .finish(),
+            ChannelSection::Gotify {
+                server,
+                app_secret,
+                client_secret,
+                title,
+                priority,
+                click,
+            } => f
+                .debug_struct("Gotify")
+                .field("server", server)
+                .field("app_secret", app_secret)
+                .field("client_secret", client_secret)
+                .field("title", title)
+                .field("priority", priority)
+                .field("click", click)
+                .finish(),
+            ChannelSection::Feed {
+                urls,
+                poll_interval,
+            } => f
+                .debug_struct("Feed")
+                .field("urls", urls)
+                .field("poll_interval", poll_interval)
+                .finish(),
+            ChannelSection::Watch {
+                paths,
+                patterns,
+                recursive,
+                debounce,
+                poll,
+            } => f
+                .debug_struct("Watch")
+                .field("paths", paths)
+                .field("patterns", patterns)
+                .field("recursive", recursive)
+                .field("debounce", debounce)
+                .field("poll", poll)
+                .finish(),
+            ChannelSection::Calendar {
+                paths,
+                lead,
+                poll_interval,
+            } => f
+                .debug_struct("Calendar")
+                .field("paths", paths)
+                .field("lead", lead)
+                .field("poll_interval", poll_interval)
+                .finish(),
             ChannelSection::Whatsapp => f.debug_struct("Whatsapp").finish(),
         }
     }
 }
 
+/// Loaded from `~/.config/vatic/daemon.toml`; everything is optional.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct DaemonConfig {
+    /// Reload when files in the config directory change, not just on SIGHUP.
+    #[serde(default)]
+    pub watch_config: bool,
+    /// Seconds to let running jobs finish on shutdown. Defaults to 30.
+    pub shutdown_grace: Option<u64>,
+    pub channel_alert: Option<ChannelAlert>,
+}
+
+/// Where to report a channel that stays down, e.g.
+/// `[channel_alert]` with `after = 10`, `channel = "telegram"`, `to = "..."`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ChannelAlert {
+    /// Minutes a channel has to be down first. Defaults to 10.
+    pub after: Option<u64>,
+    #[serde(flatten)]
+    pub output: OutputSection,
+}
+
+pub fn parse_daemon_config(toml_str: &str) -> Result<DaemonConfig> {
+    toml::from_str(toml_str)
+        .map_err(|e| Error::Config(format!("failed to parse daemon config: {e}")))
+}
+
 pub fn parse_channel_config(toml_str: &str) -> Result<ChannelConfig> {
     toml::from_str(toml_str)
         .map_err(|e| Error::Config(format!("failed to parse channel config: {e}")))
@@ -101,6 +512,40 @@ pub struct JobSection {
     pub exclusive: Option<bool>,
     /// Minimum seconds between job starts.
     pub cooldown: Option<u64>,
+    /// What to do about scheduled runs missed while the daemon was down or
+    /// the machine asleep.
+    pub catch_up: Option<CatchUp>,
+    /// What to do with a trigger while the job is running or cooling down.
+    pub on_busy: Option<OnBusy>,
+    /// Waiting triggers per sender for `on_busy = "queue"`. Defaults to 5.
+    pub queue_limit: Option<usize>,
+    /// Answer for `on_busy = "reply"`.
+    pub busy_message: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OnBusy {
+    #[default]
+    Drop,
+    /// Run it once the job is free.
+    Queue,
+    /// Like `queue`, but only the newest trigger per sender waits.
+    Replace,
+    /// Tell the sender to try again; other triggers are dropped.
+    Reply,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CatchUp {
+    /// Skip them.
+    #[default]
+    None,
+    /// One run for however many were missed.
+    Once,
+    /// Every missed run, one after another.
+    All,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
@@ -134,21 +579,16 @@ pub struct EnvironmentSection {
     pub image: Option<String>,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Default, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum OutputName {
+    #[default]
     Notification,
     Msmtp,
     Command,
     Channel,
 }
 
-impl Default for OutputName {
-    fn default() -> Self {
-        Self::Notification
-    }
-}
-
 impl std::fmt::Display for OutputName {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -160,7 +600,7 @@ impl std::fmt::Display for OutputName {
     }
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize)]
 pub struct OutputSection {
     pub name: Option<OutputName>,
     pub channel: Option<String>,
@@ -170,20 +610,27 @@ pub struct OutputSection {
     pub command: Option<String>,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+impl OutputSection {
+    /// `name` if given; a bare `channel = "..."` means a channel output,
+    /// anything else a desktop notification.
+    pub fn kind(&self) -> OutputName {
+        match (self.name, &self.channel) {
+            (Some(name), _) => name,
+            (None, Some(_)) => OutputName::Channel,
+            (None, None) => OutputName::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum TriggerMatch {
+    #[default]
     Anywhere,
     Start,
     End,
 }
 
-impl Default for TriggerMatch {
-    fn default() -> Self {
-        Self::Anywhere
-    }
-}
-
 #[derive(Debug, Clone, Deserialize)]
 pub struct InputSection {
     pub channel: String,
@@ -297,6 +744,35 @@ pub fn parse_job_config_str(toml_str: &str) -> Result<JobConfig> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_parse_daemon_config() {
+        assert_eq!(parse_daemon_config("").unwrap(), DaemonConfig::default());
+        assert!(
+            parse_daemon_config("watch_config = true")
+                .unwrap()
+                .watch_config
+        );
+        assert!(parse_daemon_config("watch_config = 1").is_err());
+
+        let alert = parse_daemon_config(
+            "[channel_alert]\nafter = 5\nchannel = \"telegram\"\nto = \"42\"\n",
+        )
+        .unwrap()
+        .channel_alert
+        .unwrap();
+        assert_eq!(alert.after, Some(5));
+        assert_eq!(alert.output.kind(), OutputName::Channel);
+        assert_eq!(alert.output.to.as_deref(), Some("42"));
+    }
+
+    #[test]
+    fn test_output_kind() {
+        let parse = |s: &str| toml::from_str::<OutputSection>(s).unwrap().kind();
+        assert_eq!(parse(r#"channel = "telegram""#), OutputName::Channel);
+        assert_eq!(parse(r#"name = "command""#), OutputName::Command);
+        assert_eq!(parse(""), OutputName::Notification);
+    }
+
     #[test]
     fn test_parse_job_config() {
         let toml_str = r#"
@@ -310,6 +786,8 @@ prompt = "You are a weather reporter."
 [job]
 interval = "0 8 * * *"
 prompt = "What's the weather for {% date %} in Lisbon?"
+catch_up = "once"
+on_busy = "reply"
 
 [environment]
 name = "guix-shell"
@@ -329,6 +807,8 @@ message = "Good morning {% custom:name %}; {% result %}"
         assert!(config.job.is_some());
         let job = config.job.as_ref().unwrap();
         assert_eq!(job.interval.as_deref(), Some("0 8 * * *"));
+        assert_eq!(job.catch_up, Some(CatchUp::Once));
+        assert_eq!(job.on_busy, Some(OnBusy::Reply));
         assert!(config.environment.is_some());
         assert_eq!(config.outputs.len(), 1);
         assert_eq!(config.outputs[0].name, Some(OutputName::Notification));
@@ -482,6 +962,442 @@ account = "personal"
         }
     }
 
+    #[test]
+    fn test_parse_channel_config_maildir() {
+        let toml_str = r#"
+[channel]
+type = "maildir"
+path = "~/Mail/inbox"
+poll_interval = 2
+account = "personal"
+"#;
+        let config = parse_channel_config(toml_str).unwrap();
+        match &config.channel {
+            ChannelSection::Maildir {
+                path,
+                poll_interval,
+                account,
+            } => {
+                assert_eq!(path, "~/Mail/inbox");
+                assert_eq!(*poll_interval, Some(2));
+                assert_eq!(account.as_deref(), Some("personal"));
+            }
+            other => panic!("expected Maildir, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_missing_maildir_path() {
+        let toml_str = r#"
+[channel]
+type = "maildir"
+"#;
+        let err = parse_channel_config(toml_str).unwrap_err();
+        assert!(err.to_string().contains("failed to parse channel config"));
+    }
+
+    #[test]
+    fn test_parse_channel_config_imap() {
+        let toml_str = r#"
+[channel]
+type = "imap"
+host = "imap.example.com"
+user = "bot@example.com"
+secret = "imap_password"
+smtp_port = 587
+"#;
+        let config = parse_channel_config(toml_str).unwrap();
+        match &config.channel {
+            ChannelSection::Imap {
+                host,
+                port,
+                user,
+                secret,
+                mailbox,
+                smtp_port,
+                tls,
+                ..
+            } => {
+                assert_eq!(host, "imap.example.com");
+                assert_eq!(*port, None);
+                assert_eq!(user, "bot@example.com");
+                assert_eq!(secret, "imap_password");
+                assert_eq!(*mailbox, None);
+                assert_eq!(*smtp_port, Some(587));
+                assert_eq!(*tls, None);
+            }
+            other => panic!("expected Imap, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_imap_debug_hides_secret() {
+        let toml_str = r#"
+[channel]
+type = "imap"
+host = "imap.example.com"
+user = "bot@example.com"
+secret = "imap_password"
+"#;
+        let config = parse_channel_config(toml_str).unwrap();
+        let debug = format!("{:?}", config.channel);
+        assert!(!debug.contains("imap_password"));
+        assert!(debug.contains("imap.example.com"));
+    }
+
+    #[test]
+    fn test_parse_channel_config_webhook() {
+        let toml_str = r#"
+[channel]
+type = "webhook"
+listen = "0.0.0.0:9000"
+secret = "github_hook"
+auth = "bearer"
+sync = true
+"#;
+        let config = parse_channel_config(toml_str).unwrap();
+        match &config.channel {
+            ChannelSection::Webhook {
+                listen,
+                secret,
+                auth,
+                signature_header,
+                sync,
+                timeout,
+            } => {
+                assert_eq!(listen.as_deref(), Some("0.0.0.0:9000"));
+                assert_eq!(secret, "github_hook");
+                assert_eq!(*auth, Some(WebhookAuth::Bearer));
+                assert_eq!(*signature_header, None);
+                assert_eq!(*sync, Some(true));
+                assert_eq!(*timeout, None);
+            }
+            other => panic!("expected Webhook, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_invalid_webhook_auth() {
+        let toml_str = r#"
+[channel]
+type = "webhook"
+secret = "hook"
+auth = "basic"
+"#;
+        assert!(parse_channel_config(toml_str).is_err());
+    }
+
+    #[test]
+    fn test_parse_channel_config_http() {
+        let toml_str = r#"
+[channel]
+type = "http"
+"#;
+        let config = parse_channel_config(toml_str).unwrap();
+        match &config.channel {
+            ChannelSection::Http { listen, secret } => {
+                assert_eq!(*listen, None);
+                assert_eq!(*secret, None);
+            }
+            other => panic!("expected Http, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_channel_config_signal() {
+        let toml_str = r#"
+[channel]
+type = "signal"
+account = "+491701234567"
+tcp = "127.0.0.1:7583"
+"#;
+        let config = parse_channel_config(toml_str).unwrap();
+        match &config.channel {
+            ChannelSection::Signal {
+                account,
+                socket,
+                tcp,
+                attachments_dir,
+            } => {
+                assert_eq!(account.as_deref(), Some("+491701234567"));
+                assert_eq!(*socket, None);
+                assert_eq!(tcp.as_deref(), Some("127.0.0.1:7583"));
+                assert_eq!(*attachments_dir, None);
+            }
+            other => panic!("expected Signal, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_channel_config_irc() {
+        let toml_str = r##"
+[channel]
+type = "irc"
+server = "irc.libera.chat"
+nick = "vatic-bot"
+secret = "libera"
+channels = ["#vatic", "#vatic-dev"]
+"##;
+        let config = parse_channel_config(toml_str).unwrap();
+        match &config.channel {
+            ChannelSection::Irc {
+                server,
+                port,
+                tls,
+                nick,
+                secret,
+                channels,
+                ..
+            } => {
+                assert_eq!(server, "irc.libera.chat");
+                assert_eq!(*port, None);
+                assert_eq!(*tls, None);
+                assert_eq!(nick, "vatic-bot");
+                assert_eq!(secret.as_deref(), Some("libera"));
+                assert_eq!(channels, &vec!["#vatic", "#vatic-dev"]);
+            }
+            other => panic!("expected Irc, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_channel_config_xmpp() {
+        let toml_str = r#"
+[channel]
+type = "xmpp"
+jid = "vatic@example.org"
+secret = "xmpp"
+rooms = ["dev@conference.example.org"]
+"#;
+        let config = parse_channel_config(toml_str).unwrap();
+        match &config.channel {
+            ChannelSection::Xmpp {
+                jid,
+                password,
+                secret,
+                rooms,
+                nick,
+                ..
+            } => {
+                assert_eq!(jid, "vatic@example.org");
+                assert_eq!(*password, None);
+                assert_eq!(secret.as_deref(), Some("xmpp"));
+                assert_eq!(rooms, &vec!["dev@conference.example.org"]);
+                assert_eq!(*nick, None);
+            }
+            other => panic!("expected Xmpp, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_xmpp_debug_hides_password() {
+        let toml_str = r#"
+[channel]
+type = "xmpp"
+jid = "vatic@example.org"
+password = "hunter2"
+"#;
+        let config = parse_channel_config(toml_str).unwrap();
+        assert!(!format!("{:?}", config.channel).contains("hunter2"));
+    }
+
+    #[test]
+    fn test_parse_channel_config_slack() {
+        let toml_str = r#"
+[channel]
+type = "slack"
+app_secret = "slack_app"
+bot_secret = "slack_bot"
+"#;
+        let config = parse_channel_config(toml_str).unwrap();
+        match &config.channel {
+            ChannelSection::Slack {
+                app_secret,
+                bot_secret,
+            } => {
+                assert_eq!(app_secret, "slack_app");
+                assert_eq!(bot_secret, "slack_bot");
+            }
+            other => panic!("expected Slack, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_channel_config_discord() {
+        let toml_str = r#"
+[channel]
+type = "discord"
+secret = "discord"
+guilds = ["123"]
+"#;
+        let config = parse_channel_config(toml_str).unwrap();
+        match &config.channel {
+            ChannelSection::Discord {
+                secret,
+                guilds,
+                channels,
+            } => {
+                assert_eq!(secret, "discord");
+                assert_eq!(guilds, &vec!["123".to_string()]);
+                assert!(channels.is_empty());
+            }
+            other => panic!("expected Discord, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_channel_config_mqtt() {
+        let toml_str = r#"
+[channel]
+type = "mqtt"
+host = "broker.lan"
+topics = ["home/+/door", "home/power"]
+reply_topic = "vatic/{topic}"
+"#;
+        let config = parse_channel_config(toml_str).unwrap();
+        match &config.channel {
+            ChannelSection::Mqtt {
+                host,
+                port,
+                topics,
+                reply_topic,
+                secret,
+                ..
+            } => {
+                assert_eq!(host, "broker.lan");
+                assert!(port.is_none());
+                assert_eq!(topics.len(), 2);
+                assert_eq!(reply_topic.as_deref(), Some("vatic/{topic}"));
+                assert!(secret.is_none());
+            }
+            other => panic!("expected Mqtt, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_channel_config_ntfy() {
+        let toml_str = r#"
+[channel]
+type = "ntfy"
+topic = "vatic-ops"
+priority = 4
+tags = ["robot"]
+"#;
+        let config = parse_channel_config(toml_str).unwrap();
+        match &config.channel {
+            ChannelSection::Ntfy {
+                server,
+                topic,
+                priority,
+                tags,
+                ..
+            } => {
+                assert!(server.is_none());
+                assert_eq!(topic, "vatic-ops");
+                assert_eq!(*priority, Some(4));
+                assert_eq!(tags, &vec!["robot".to_string()]);
+            }
+            other => panic!("expected Ntfy, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_channel_config_gotify() {
+        let toml_str = r#"
+[channel]
+type = "gotify"
+server = "https://push.example.org"
+app_secret = "gotify_app"
+client_secret = "gotify_client"
+"#;
+        let config = parse_channel_config(toml_str).unwrap();
+        match &config.channel {
+            ChannelSection::Gotify {
+                server,
+                app_secret,
+                client_secret,
+                ..
+            } => {
+                assert_eq!(server, "https://push.example.org");
+                assert_eq!(app_secret, "gotify_app");
+                assert_eq!(client_secret, "gotify_client");
+            }
+            other => panic!("expected Gotify, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_channel_config_feed() {
+        let toml_str = r#"
+[channel]
+type = "feed"
+urls = ["https://blog.example.org/rss", "https://github.com/rust-lang/rust/releases.atom"]
+poll_interval = 1800
+"#;
+        let config = parse_channel_config(toml_str).unwrap();
+        match &config.channel {
+            ChannelSection::Feed {
+                urls,
+                poll_interval,
+            } => {
+                assert_eq!(urls.len(), 2);
+                assert_eq!(*poll_interval, Some(1800));
+            }
+            other => panic!("expected Feed, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_channel_config_watch() {
+        let toml_str = r#"
+[channel]
+type = "watch"
+paths = ["~/inbox"]
+patterns = ["*.pdf", "*.txt"]
+debounce = 5000
+"#;
+        let config = parse_channel_config(toml_str).unwrap();
+        match &config.channel {
+            ChannelSection::Watch {
+                paths,
+                patterns,
+                recursive,
+                debounce,
+                poll,
+            } => {
+                assert_eq!(paths, &vec!["~/inbox".to_string()]);
+                assert_eq!(patterns.len(), 2);
+                assert!(recursive.is_none());
+                assert_eq!(*debounce, Some(5000));
+                assert!(poll.is_none());
+            }
+            other => panic!("expected Watch, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_channel_config_calendar() {
+        let toml_str = r#"
+[channel]
+type = "calendar"
+paths = ["~/.calendars/work"]
+lead = 30
+"#;
+        let config = parse_channel_config(toml_str).unwrap();
+        match &config.channel {
+            ChannelSection::Calendar {
+                paths,
+                lead,
+                poll_interval,
+            } => {
+                assert_eq!(paths, &vec!["~/.calendars/work".to_string()]);
+                assert_eq!(*lead, Some(30));
+                assert!(poll_interval.is_none());
+            }
+            other => panic!("expected Calendar, got {:?}", other),
+        }
+    }
+
     #[test]
     fn test_parse_channel_config_telegram() {
         let toml_str = r#"
@@ -0,0 +1,214 @@
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{UnixListener, UnixStream};
+use tokio::sync::{mpsc, oneshot};
+
+use crate::error::{Error, Result};
+
+/// Pause after a failed accept, so running out of file descriptors doesn't
+/// spin the loop and flood the log.
+const ACCEPT_RETRY: Duration = Duration::from_millis(100);
+
+/// One line of JSON in each direction per connection.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "cmd", rename_all = "lowercase")]
+pub enum Request {
+    Status,
+    Jobs,
+    Trigger { alias: String },
+    Pause { alias: String },
+    Resume { alias: String },
+    Reload,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Response {
+    pub ok: bool,
+    /// Human-readable, printed as is by `vatic ctl`.
+    pub output: String,
+}
+
+impl Response {
+    pub fn ok(output: impl Into<String>) -> Self {
+        Self {
+            ok: true,
+            output: output.into(),
+        }
+    }
+
+    pub fn error(output: impl Into<String>) -> Self {
+        Self {
+            ok: false,
+            output: output.into(),
+        }
+    }
+}
+
+/// A request on its way to the daemon loop, with somewhere to put the answer.
+pub struct ControlRequest {
+    pub request: Request,
+    pub reply: oneshot::Sender<Response>,
+}
+
+/// `<data dir>/vatic.sock`
+pub fn socket_path(data_dir: &Path) -> PathBuf {
+    data_dir.join("vatic.sock")
+}
+
+/// Bind the socket and hand requests to `tx`. A leftover socket from a
+/// crashed daemon is replaced; a live one means we're not alone. The
+/// directory is made owner-only first, so nobody can connect between the
+/// bind and the chmod.
+pub async fn listen(path: &Path, tx: mpsc::Sender<ControlRequest>) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    if let Some(dir) = path.parent() {
+        std::fs::set_permissions(dir, std::fs::Permissions::from_mode(0o700))?;
+    }
+    if path.exists() {
+        if UnixStream::connect(path).await.is_ok() {
+            return Err(Error::Control(format!(
+                "another daemon is listening on {}",
+                path.display()
+            )));
+        }
+        std::fs::remove_file(path)?;
+    }
+    let listener = UnixListener::bind(path)
+        .map_err(|e| Error::Control(format!("cannot bind {}: {e}", path.display())))?;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+    tracing::info!("control socket: {}", path.display());
+
+    tokio::spawn(async move {
+        loop {
+            let stream = match listener.accept().await {
+                Ok((stream, _)) => stream,
+                Err(e) => {
+                    tracing::warn!("control socket accept failed: {e}");
+                    tokio::time::sleep(ACCEPT_RETRY).await;
+                    continue;
+                }
+            };
+            let tx = tx.clone();
+            tokio::spawn(async move {
+                if let Err(e) = serve(stream, tx).await {
+                    tracing::debug!("control connection: {e}");
+                }
+            });
+        }
+    });
+    Ok(())
+}
+
+async fn serve(stream: UnixStream, tx: mpsc::Sender<ControlRequest>) -> Result<()> {
+    let (read, mut write) = stream.into_split();
+    let mut line = String::new();
+    BufReader::new(read).read_line(&mut line).await?;
+
+    let response = match serde_json::from_str::<Request>(&line) {
+        Ok(request) => {
+            let (reply, answer) = oneshot::channel();
+            if tx.send(ControlRequest { request, reply }).await.is_err() {
+                Response::error("daemon is shutting down")
+            } else {
+                answer
+                    .await
+                    .unwrap_or_else(|_| Response::error("daemon dropped the request"))
+            }
+        }
+        Err(e) => Response::error(format!("bad request: {e}")),
+    };
+
+    let mut out = serde_json::to_string(&response)
+        .map_err(|e| Error::Control(format!("cannot encode response: {e}")))?;
+    out.push('\n');
+    write.write_all(out.as_bytes()).await?;
+    Ok(())
+}
+
+/// Client side, used by `vatic ctl`.
+pub async fn send(path: &Path, request: &Request) -> Result<Response> {
+    let stream = UnixStream::connect(path).await.map_err(|e| {
+        Error::Control(format!(
+            "cannot reach daemon at {} ({e}), is `vatic daemon` running?",
+            path.display()
+        ))
+    })?;
+    let (read, mut write) = stream.into_split();
+
+    let mut line = serde_json::to_string(request)
+        .map_err(|e| Error::Control(format!("cannot encode request: {e}")))?;
+    line.push('\n');
+    write.write_all(line.as_bytes()).await?;
+
+    let mut answer = String::new();
+    BufReader::new(read).read_line(&mut answer).await?;
+    serde_json::from_str(&answer)
+        .map_err(|e| Error::Control(format!("bad response from daemon: {e}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_wire_format() {
+        let json = serde_json::to_string(&Request::Trigger {
+            alias: "weather".into(),
+        })
+        .unwrap();
+        assert_eq!(json, r#"{"cmd":"trigger","alias":"weather"}"#);
+        let parsed: Request = serde_json::from_str(r#"{"cmd":"status"}"#).unwrap();
+        assert_eq!(parsed, Request::Status);
+    }
+
+    #[tokio::test]
+    async fn test_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = socket_path(dir.path());
+        let (tx, mut rx) = mpsc::channel(4);
+        listen(&path, tx.clone()).await.unwrap();
+
+        tokio::spawn(async move {
+            while let Some(req) = rx.recv().await {
+                let out = format!("{:?}", req.request);
+                let _ = req.reply.send(Response::ok(out));
+            }
+        });
+
+        let resp = send(&path, &Request::Pause { alias: "x".into() })
+            .await
+            .unwrap();
+        assert_eq!(resp, Response::ok(r#"Pause { alias: "x" }"#));
+
+        use std::os::unix::fs::PermissionsExt;
+        let mode = |p: &Path| std::fs::metadata(p).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode(dir.path()), 0o700);
+        assert_eq!(mode(&path), 0o600);
+
+        // A second daemon must not steal the socket
+        assert!(listen(&path, tx).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_stale_socket_replaced() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = socket_path(dir.path());
+        drop(std::os::unix::net::UnixListener::bind(&path).unwrap());
+        assert!(path.exists());
+
+        let (tx, _rx) = mpsc::channel(1);
+        listen(&path, tx).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_no_daemon() {
+        let dir = tempfile::tempdir().unwrap();
+        let err = send(&socket_path(dir.path()), &Request::Status)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("is `vatic daemon` running?"));
+    }
+}
@@ -1,64 +1,106 @@
+pub mod control;
+pub mod queue;
+pub mod reload;
 pub mod scheduler;
+pub mod supervisor;
 
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 use std::time::Instant;
 
-use chrono::Local;
+use std::future::Future;
+use std::time::Duration;
 
-use crate::agent;
+use chrono::{DateTime, Local, NaiveDateTime, Utc};
+use tokio::signal::unix::{signal, Signal, SignalKind};
+use tokio::task::{JoinError, JoinSet};
+
+use crate::channel::calendar::{CalendarChannel, CalendarSettings};
+use crate::channel::discord::{DiscordChannel, DiscordFilter};
 use crate::channel::email::EmailChannel;
+use crate::channel::feed::FeedChannel;
+use crate::channel::gotify::GotifyChannel;
+use crate::channel::http::HttpChannel;
+use crate::channel::imap::{ImapChannel, ImapSettings};
+use crate::channel::irc::{IrcChannel, IrcSettings};
+use crate::channel::maildir::MaildirChannel;
 use crate::channel::matrix::MatrixChannel;
+use crate::channel::mqtt::{MqttChannel, MqttSettings};
+use crate::channel::ntfy::{NtfyChannel, PushOptions};
+use crate::channel::signal::{SignalChannel, SignalEndpoint};
+use crate::channel::slack::SlackChannel;
 use crate::channel::stdin::StdinChannel;
 use crate::channel::telegram::TelegramChannel;
+use crate::channel::watch::{WatchChannel, WatchSettings};
+use crate::channel::webhook::WebhookChannel;
 #[cfg(feature = "whatsapp")]
 use crate::channel::whatsapp::WhatsAppChannel;
-use crate::channel::{Channel, IncomingMessage};
-use crate::config::types::{ChannelSection, JobConfig, TriggerMatch};
+use crate::channel::xmpp::{XmppChannel, XmppSettings};
+use crate::channel::{Channel, ChannelRegistry, IncomingMessage};
+use crate::config::types::{ChannelConfig, ChannelSection, JobConfig, OnBusy, TriggerMatch};
 use crate::config::AppConfig;
-use crate::env;
+use crate::daemon::control::{ControlRequest, Request, Response};
+use crate::daemon::queue::TriggerQueue;
+use crate::daemon::supervisor::{ChannelHealth, ChannelHealths};
 use crate::error::Result;
 use crate::output;
+use crate::run::{JobRunner, Trigger};
 use crate::store::{SessionMessage, Store};
-use crate::template;
-use crate::template::functions::RenderContext;
 use tokio::sync::mpsc;
 
 use self::scheduler::CronSchedule;
 
+#[derive(Default)]
 struct JobState {
     running: bool,
+    paused: bool,
     last_started: Option<Instant>,
+    /// Wall-clock start of the last run, for `vatic ctl status`.
+    last_run: Option<DateTime<Local>>,
+    /// Why the last run failed, if it did.
+    last_error: Option<String>,
 }
 
 type JobTracker = Arc<Mutex<HashMap<String, JobState>>>;
 
-/// Try to acquire a job slot. Returns true if the job should proceed.
-fn try_acquire(tracker: &JobTracker, alias: &str, exclusive: bool, cooldown: Option<u64>) -> bool {
+/// Claim a job slot, or say why not.
+fn acquire(
+    tracker: &JobTracker,
+    alias: &str,
+    exclusive: bool,
+    cooldown: Option<u64>,
+) -> std::result::Result<(), &'static str> {
     let mut map = tracker.lock().unwrap();
-    let state = map.entry(alias.to_string()).or_insert(JobState {
-        running: false,
-        last_started: None,
-    });
+    let state = map.entry(alias.to_string()).or_default();
 
     if exclusive && state.running {
-        tracing::debug!("[{alias}] skipped: already running");
-        return false;
+        return Err("already running");
     }
 
     if let Some(cd) = cooldown {
         if let Some(started) = state.last_started {
             if started.elapsed().as_secs() < cd {
-                tracing::debug!("[{alias}] skipped: cooldown ({cd}s) not elapsed");
-                return false;
+                return Err("cooldown not elapsed");
             }
         }
     }
 
     state.running = true;
     state.last_started = Some(Instant::now());
-    true
+    state.last_run = Some(Local::now());
+    Ok(())
+}
+
+/// Try to acquire a job slot. Returns true if the job should proceed.
+fn try_acquire(tracker: &JobTracker, alias: &str, exclusive: bool, cooldown: Option<u64>) -> bool {
+    match acquire(tracker, alias, exclusive, cooldown) {
+        Ok(()) => true,
+        Err(reason) => {
+            tracing::debug!("[{alias}] skipped: {reason}");
+            false
+        }
+    }
 }
 
 /// Release a job slot after completion.
@@ -68,16 +110,351 @@ fn release(tracker: &JobTracker, alias: &str) {
     }
 }
 
+fn is_paused(tracker: &JobTracker, alias: &str) -> bool {
+    tracker
+        .lock()
+        .unwrap()
+        .get(alias)
+        .is_some_and(|state| state.paused)
+}
+
+/// `exclusive` (default true) and `cooldown` from the `[job]` section.
+fn job_limits(job: &JobConfig) -> (bool, Option<u64>) {
+    let section = job.job.as_ref();
+    (
+        section.and_then(|j| j.exclusive).unwrap_or(true),
+        section.and_then(|j| j.cooldown),
+    )
+}
+
+/// Job runs in progress, so shutdown can wait for them and record the ones
+/// it has to cut off.
+#[derive(Default)]
+struct InFlight {
+    tasks: JoinSet<()>,
+    /// Alias, trigger and start (UTC) per task.
+    runs: HashMap<tokio::task::Id, (String, &'static str, String)>,
+}
+
+impl InFlight {
+    /// Run an acquired job in the background, then release it and note how it went.
+    fn spawn(
+        &mut self,
+        runner: &Arc<JobRunner>,
+        tracker: &JobTracker,
+        alias: &str,
+        trigger: Trigger,
+    ) {
+        let label = trigger.label();
+        let runner = Arc::clone(runner);
+        let tracker = Arc::clone(tracker);
+        let name = alias.to_string();
+        let handle = self.tasks.spawn(async move {
+            let result = runner.run(&name, &trigger).await;
+            if let Err(e) = &result {
+                tracing::error!("[{}] job failed: {}", name, e);
+            }
+            release(&tracker, &name);
+            if let Some(state) = tracker.lock().unwrap().get_mut(&name) {
+                state.last_error = result.err().map(|e| e.to_string());
+            }
+        });
+        let started = Utc::now().format("%Y-%m-%d %H:%M:%S").to_string();
+        self.runs
+            .insert(handle.id(), (alias.to_string(), label, started));
+    }
+
+    fn finished(&mut self, done: std::result::Result<(tokio::task::Id, ()), JoinError>) {
+        let id = match done {
+            Ok((id, ())) => id,
+            Err(e) => {
+                if e.is_panic() {
+                    tracing::error!("job task panicked: {e}");
+                }
+                e.id()
+            }
+        };
+        self.runs.remove(&id);
+    }
+
+    /// Wait for running jobs until `grace` is over or `interrupt` fires.
+    /// Whatever is still going then is aborted and stored as interrupted.
+    async fn drain(
+        &mut self,
+        grace: Duration,
+        interrupt: impl Future<Output = ()>,
+        db_path: &Path,
+    ) {
+        if self.runs.is_empty() {
+            return;
+        }
+        tracing::info!(
+            "waiting up to {}s for {} running job(s)",
+            grace.as_secs(),
+            self.runs.len()
+        );
+        tokio::select! {
+            _ = async {
+                while let Some(done) = self.tasks.join_next_with_id().await {
+                    self.finished(done);
+                }
+            } => {}
+            _ = tokio::time::sleep(grace) => {
+                tracing::warn!("grace period over");
+            }
+            _ = interrupt => {
+                tracing::warn!("received second shutdown signal, not waiting");
+            }
+        }
+        if self.runs.is_empty() {
+            tracing::info!("all jobs finished");
+            return;
+        }
+
+        self.tasks.abort_all();
+        let store = match Store::open(db_path) {
+            Ok(store) => store,
+            Err(e) => {
+                tracing::error!("cannot record interrupted runs: {e}");
+                return;
+            }
+        };
+        for (alias, trigger, started) in self.runs.values() {
+            tracing::warn!("[{}] interrupted by shutdown", alias);
+            if let Err(e) = store.record_interrupted(alias, trigger, started) {
+                tracing::error!("[{}] cannot record interrupted run: {}", alias, e);
+            }
+        }
+    }
+}
+
+/// Start a run, or handle the job being busy the way its `on_busy` says.
+/// Returns whether the trigger was taken: started, queued or answered.
+fn start_or_wait(
+    in_flight: &mut InFlight,
+    queue: &mut TriggerQueue,
+    runner: &Arc<JobRunner>,
+    tracker: &JobTracker,
+    alias: &str,
+    job_config: &JobConfig,
+    trigger: Trigger,
+) -> bool {
+    let (exclusive, cooldown) = job_limits(job_config);
+    let reason = match acquire(tracker, alias, exclusive, cooldown) {
+        Ok(()) => {
+            in_flight.spawn(runner, tracker, alias, trigger);
+            return true;
+        }
+        Err(reason) => reason,
+    };
+    let job = job_config.job.as_ref();
+    match job.and_then(|j| j.on_busy).unwrap_or_default() {
+        policy @ (OnBusy::Queue | OnBusy::Replace) => {
+            let limit = job.and_then(|j| j.queue_limit).unwrap_or(5);
+            let label = trigger.label();
+            if queue.push(alias, trigger, limit, policy == OnBusy::Replace) {
+                tracing::info!("[{}] {}, {} trigger queued", alias, reason, label);
+                return true;
+            }
+            tracing::warn!(
+                "[{}] {}, queue full, {} trigger dropped",
+                alias,
+                reason,
+                label
+            );
+            false
+        }
+        OnBusy::Reply => {
+            let Trigger::Message(msg) = trigger else {
+                tracing::debug!(
+                    "[{}] {}, {} trigger dropped",
+                    alias,
+                    reason,
+                    trigger.label()
+                );
+                return false;
+            };
+            let Some(channel) = runner.channels().get(&msg.channel).cloned() else {
+                return false;
+            };
+            let text = job
+                .and_then(|j| j.busy_message.clone())
+                .unwrap_or_else(|| format!("Busy ({reason}), try again in a moment."));
+            tracing::info!("[{}] {}, told {} to try again", alias, reason, msg.sender);
+            tokio::spawn(async move {
+                if let Err(e) = channel.send(msg.reply_address(), &text).await {
+                    tracing::warn!("busy reply to {} failed: {}", msg.sender, e);
+                }
+            });
+            true
+        }
+        OnBusy::Drop => {
+            tracing::debug!(
+                "[{}] {}, {} trigger dropped",
+                alias,
+                reason,
+                trigger.label()
+            );
+            false
+        }
+    }
+}
+
+/// Start queued triggers whose job is free again, one per job.
+fn start_queued(
+    in_flight: &mut InFlight,
+    queue: &mut TriggerQueue,
+    runner: &Arc<JobRunner>,
+    tracker: &JobTracker,
+) {
+    for alias in queue.aliases() {
+        let Some((_, job_config)) = runner.app().jobs.iter().find(|(a, _)| *a == alias) else {
+            let dropped = queue.remove(&alias);
+            tracing::info!(
+                "[{}] job is gone, {} queued trigger(s) dropped",
+                alias,
+                dropped
+            );
+            continue;
+        };
+        let (exclusive, cooldown) = job_limits(job_config);
+        if is_paused(tracker, &alias) || !try_acquire(tracker, &alias, exclusive, cooldown) {
+            continue;
+        }
+        match queue.pop(&alias) {
+            Some(trigger) => {
+                tracing::info!("[{}] starting queued {} trigger", alias, trigger.label());
+                in_flight.spawn(runner, tracker, &alias, trigger);
+            }
+            None => release(tracker, &alias),
+        }
+    }
+}
+
+const FIRE_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// When each scheduled job was last handled. Jobs the store doesn't know
+/// yet start from now, saved right away so a run missed before their first
+/// fire still counts.
+fn load_cron_fires(
+    db_path: &Path,
+    schedules: &[(String, CronSchedule)],
+) -> HashMap<String, NaiveDateTime> {
+    let now = Local::now().naive_local();
+    let store = Store::open(db_path);
+    if let Err(e) = &store {
+        tracing::error!("cannot read cron fire times: {}", e);
+    }
+    let stored = |alias: &str| {
+        let fired_at = store.as_ref().ok()?.last_cron_fire(alias).ok()??;
+        NaiveDateTime::parse_from_str(&fired_at, FIRE_FORMAT).ok()
+    };
+    let mut fires = HashMap::new();
+    let mut unknown = Vec::new();
+    for (alias, _) in schedules {
+        let last = stored(alias).unwrap_or_else(|| {
+            unknown.push(alias.clone());
+            now
+        });
+        fires.insert(alias.clone(), last);
+    }
+    save_cron_fires(db_path, &unknown, now);
+    fires
+}
+
+/// A job whose schedule changed on reload starts counting from now, so
+/// times it would have fired under the new schedule don't count as missed.
+fn reset_changed_fires(
+    old: &[(String, CronSchedule)],
+    new: &[(String, CronSchedule)],
+    last_fires: &mut HashMap<String, NaiveDateTime>,
+    db_path: &Path,
+) {
+    let changed: Vec<String> = new
+        .iter()
+        .filter(|(alias, schedule)| old.iter().any(|(a, s)| a == alias && s != schedule))
+        .map(|(alias, _)| alias.clone())
+        .collect();
+    let now = Local::now().naive_local();
+    save_cron_fires(db_path, &changed, now);
+    for alias in changed {
+        tracing::debug!("[{}] schedule changed, counting from now", alias);
+        last_fires.insert(alias, now);
+    }
+}
+
+fn save_cron_fires(db_path: &Path, aliases: &[String], at: NaiveDateTime) {
+    if aliases.is_empty() {
+        return;
+    }
+    let at = at.format(FIRE_FORMAT).to_string();
+    let saved = Store::open(db_path)
+        .and_then(|store| aliases.iter().try_for_each(|a| store.set_cron_fire(a, &at)));
+    if let Err(e) = saved {
+        tracing::error!("cannot save cron fire times: {}", e);
+    }
+}
+
+/// Report channels that stay down (and come back) via `[channel_alert]`.
+fn send_channel_alerts(runner: &Arc<JobRunner>, health: &ChannelHealths) {
+    let Some(alert) = &runner.app().daemon.channel_alert else {
+        return;
+    };
+    let after = Duration::from_secs(alert.after.unwrap_or(10) * 60);
+    let alerts = supervisor::due_alerts(health, after, Local::now());
+    if alerts.is_empty() {
+        return;
+    }
+    let runner = Arc::clone(runner);
+    tokio::spawn(async move {
+        let Some(alert) = &runner.app().daemon.channel_alert else {
+            return;
+        };
+        for text in alerts {
+            tracing::warn!("{}", text);
+            if let Err(e) = output::dispatch(&alert.output, &text, None, runner.channels()).await {
+                tracing::error!("channel alert failed: {}", e);
+            }
+        }
+    });
+}
+
+/// Ctrl-C or SIGTERM (systemd stop/restart).
+async fn shutdown_signal(terminate: &mut Signal) {
+    tokio::select! {
+        _ = tokio::signal::ctrl_c() => {}
+        _ = terminate.recv() => {}
+    }
+}
+
+fn build_schedules(app: &AppConfig) -> Result<Vec<(String, CronSchedule)>> {
+    let mut schedules = Vec::new();
+    for (alias, job) in &app.jobs {
+        if let Some(interval) = job.job.as_ref().and_then(|j| j.interval.as_deref()) {
+            let schedule = CronSchedule::parse(interval).map_err(|e| {
+                crate::error::Error::Config(format!(
+                    "job '{alias}': invalid cron expression '{interval}': {e}"
+                ))
+            })?;
+            tracing::info!("[{}] scheduled: {}", alias, interval);
+            schedules.push((alias.clone(), schedule));
+        }
+    }
+    Ok(schedules)
+}
+
 /// Main loop — listens on channels, runs cron schedules, dispatches jobs.
 pub async fn run_daemon(app: &AppConfig) -> Result<()> {
     app.validate()?;
+    let mut schedules = build_schedules(app)?;
+
+    let db_path = crate::run::db_path(app)?;
+    // Bind before touching anything else, so a second daemon stops here
+    // instead of logging into channels and taking their messages
+    let socket = control::socket_path(&app.data_dir);
+    let (control_tx, mut control_rx) = mpsc::channel::<ControlRequest>(16);
+    control::listen(&socket, control_tx).await?;
 
-    let db_path = app.data_dir.join("vatic.db");
-    if let Some(parent) = db_path.parent() {
-        std::fs::create_dir_all(parent).map_err(|e| {
-            crate::error::Error::Store(format!("cannot create data directory: {e}"))
-        })?;
-    }
     let store = Store::open(&db_path)?;
     if let Err(e) = store.prune(1000, 30) {
         tracing::warn!("database pruning failed: {e}");
@@ -105,95 +482,48 @@ pub async fn run_daemon(app: &AppConfig) -> Result<()> {
 
     let (tx, mut rx) = mpsc::channel::<IncomingMessage>(100);
 
-    // Fall back to stdin if no channels are configured
-    let mut channels: HashMap<String, Arc<dyn Channel>> = HashMap::new();
+    let channels = build_channels(app, &db_path);
 
-    if app.channels.is_empty() {
-        tracing::info!("no channel configs found, defaulting to stdin");
-        let ch: Arc<dyn Channel> = Arc::new(StdinChannel);
-        channels.insert("stdin".to_string(), ch);
-    } else {
-        for (name, channel_config) in &app.channels {
-            let ch: Arc<dyn Channel> = match &channel_config.channel {
-                ChannelSection::Stdin => Arc::new(StdinChannel),
-                #[cfg(feature = "whatsapp")]
-                ChannelSection::Whatsapp => {
-                    let data_dir = app.data_dir.join("channels").join("whatsapp");
-                    Arc::new(WhatsAppChannel::new(data_dir))
-                }
-                ChannelSection::Telegram { token } => Arc::new(TelegramChannel::new(token.clone())),
-                ChannelSection::Matrix {
-                    homeserver,
-                    user,
-                    password,
-                } => {
-                    let data_dir = app.data_dir.join("channels").join("matrix");
-                    Arc::new(MatrixChannel::new(
-                        homeserver.clone(),
-                        user.clone(),
-                        password.clone(),
-                        data_dir,
-                    ))
-                }
-                ChannelSection::Himalaya {
-                    poll_interval,
-                    account,
-                } => Arc::new(EmailChannel::new(
-                    poll_interval.unwrap_or(60),
-                    account.clone(),
-                )),
-                #[cfg(not(feature = "whatsapp"))]
-                ChannelSection::Whatsapp => {
-                    tracing::warn!(
-                        "whatsapp channel requires the 'whatsapp' feature flag, skipping '{}'",
-                        name
-                    );
-                    continue;
-                }
-            };
-            channels.insert(ch.name().to_string(), ch);
-        }
-    }
-
-    let channel_names: Vec<&str> = channels.keys().map(|s| s.as_str()).collect();
+    let mut channel_names: Vec<&str> = channels.keys().map(|s| s.as_str()).collect();
+    channel_names.sort();
     tracing::info!("channels: [{}]", channel_names.join(", "));
+    warn_unknown_inputs(app, &channels);
 
-    for (name, channel) in &channels {
-        let ch = Arc::clone(channel);
-        let channel_tx = tx.clone();
-        let channel_name = name.clone();
-        tokio::spawn(async move {
-            if let Err(e) = ch.start(channel_tx).await {
-                tracing::error!("{} channel error: {}", channel_name, e);
-            }
-        });
-    }
+    let mut runner = Arc::new(JobRunner::new(app.clone(), db_path.clone(), channels));
 
-    let mut schedules: Vec<(String, CronSchedule)> = Vec::new();
-    for (alias, job) in &app.jobs {
-        if let Some(interval) = job.job.as_ref().and_then(|j| j.interval.as_deref()) {
-            match CronSchedule::parse(interval) {
-                Ok(schedule) => {
-                    tracing::info!("[{}] scheduled: {}", alias, interval);
-                    schedules.push((alias.clone(), schedule));
-                }
-                Err(e) => {
-                    tracing::error!("[{}] invalid cron expression '{}': {}", alias, interval, e);
-                }
-            }
-        }
+    let mut tasks = ChannelTasks::new(tx.clone());
+    for (name, channel) in runner.channels() {
+        tasks.start(name, Arc::clone(channel));
     }
 
+    let started = Local::now();
+
+    let mut hangup = signal(SignalKind::hangup())?;
+    let mut terminate = signal(SignalKind::terminate())?;
+    let mut in_flight = InFlight::default();
+    let mut queue = TriggerQueue::default();
+    let (reload_tx, mut reload_rx) = mpsc::channel::<()>(1);
+    let mut config_watch = ConfigWatch {
+        tx: reload_tx,
+        watcher: None,
+    };
+    config_watch.set(app);
+
     // 30s granularity is fine — cron's smallest unit is 1 minute
     let mut cron_interval = tokio::time::interval(tokio::time::Duration::from_secs(30));
-    let mut last_cron_check = Local::now().naive_local();
+    let mut last_fires = load_cron_fires(&db_path, &schedules);
+    let mut backlog: HashMap<String, usize> = HashMap::new();
 
     loop {
         tokio::select! {
-            _ = tokio::signal::ctrl_c() => {
-                tracing::info!("received shutdown signal, exiting");
+            _ = shutdown_signal(&mut terminate) => {
+                tracing::info!("received shutdown signal, stopping");
                 break;
             }
+            Some(done) = in_flight.tasks.join_next_with_id(), if !in_flight.tasks.is_empty() => {
+                in_flight.finished(done);
+                start_queued(&mut in_flight, &mut queue, &runner, &tracker);
+            }
             msg = rx.recv() => {
                 let Some(msg) = msg else { break };
                 let preview: String = msg.text.chars().take(50).collect();
@@ -201,145 +531,874 @@ pub async fn run_daemon(app: &AppConfig) -> Result<()> {
                 tracing::info!("received message on {}: {}{}", msg.channel, preview, truncated);
                 tracing::debug!("full message: {}", msg.text);
 
-                for (alias, job_config) in &app.jobs {
-                    if !matches_input(job_config, &msg) {
+                let mut claimed = false;
+                for (alias, job_config) in &runner.app().jobs {
+                    if !matches_input(job_config, &msg) || is_paused(&tracker, alias) {
                         continue;
                     }
 
-                    let exclusive = job_config.job.as_ref().and_then(|j| j.exclusive).unwrap_or(true);
-                    let cooldown = job_config.job.as_ref().and_then(|j| j.cooldown);
-                    if !try_acquire(&tracker, alias, exclusive, cooldown) {
-                        continue;
+                    claimed |= start_or_wait(&mut in_flight, &mut queue, &runner, &tracker, alias, job_config, Trigger::Message(msg.clone()));
+                }
+                if !claimed {
+                    if let Some(channel) = runner.channels().get(&msg.channel) {
+                        channel.unclaimed(&msg);
                     }
-
-                    let db_path = db_path.clone();
-                    let app = app.clone();
-                    let alias = alias.clone();
-                    let job_config = job_config.clone();
-                    let msg = msg.clone();
-                    let channels = channels.clone();
-                    let tracker = tracker.clone();
-                    tokio::spawn(async move {
-                        let result = run_channel_job(&app, &db_path, &alias, &job_config, &msg).await;
-                        release(&tracker, &alias);
-                        match result {
-                            Ok(result) => {
-                                for out in &job_config.outputs {
-                                    if out.channel.is_some() {
-                                        if let Some(ch) = channels.get(&msg.channel) {
-                                            if let Err(e) = ch.send(&msg.sender, &result).await {
-                                                tracing::error!("failed to send response on {}: {}", msg.channel, e);
-                                            }
-                                        }
-                                    }
-                                }
-                            }
-                            Err(e) => {
-                                tracing::error!("job {} failed: {}", alias, e);
-                            }
-                        }
-                    });
                 }
             }
             _ = cron_interval.tick() => {
                 let now = Local::now().naive_local();
+                if schedules.iter().any(|(a, _)| !last_fires.contains_key(a)) {
+                    last_fires = load_cron_fires(&db_path, &schedules);
+                }
+                let mut handled = Vec::new();
                 for (alias, schedule) in &schedules {
-                    if let Some(next) = schedule.next_from(last_cron_check) {
-                        if next <= now {
-                            if let Some((_, job_config)) = app.jobs.iter().find(|(a, _)| a == alias) {
-                                let exclusive = job_config.job.as_ref().and_then(|j| j.exclusive).unwrap_or(true);
-                                let cooldown = job_config.job.as_ref().and_then(|j| j.cooldown);
-                                if !try_acquire(&tracker, alias, exclusive, cooldown) {
-                                    continue;
-                                }
-
-                                tracing::info!("[{}] cron triggered", alias);
-                                let db_path = db_path.clone();
-                                let app = app.clone();
-                                let alias = alias.clone();
-                                let job_config = job_config.clone();
-                                let tracker = tracker.clone();
-                                tokio::spawn(async move {
-                                    let result = run_scheduled_job(&app, &db_path, &alias, &job_config).await;
-                                    release(&tracker, &alias);
-                                    if let Err(e) = result {
-                                        tracing::error!("[{}] scheduled job failed: {}", alias, e);
-                                    }
-                                });
-                            }
-                        }
+                    let Some((_, job_config)) = runner.app().jobs.iter().find(|(a, _)| a == alias) else {
+                        continue;
+                    };
+                    let catch_up = job_config.job.as_ref().and_then(|j| j.catch_up).unwrap_or_default();
+                    let Some(runs) = schedule.due(last_fires[alias], now, catch_up) else {
+                        continue;
+                    };
+                    handled.push(alias.clone());
+                    if is_paused(&tracker, alias) {
+                        tracing::debug!("[{}] skipped: paused", alias);
+                        continue;
+                    }
+                    if runs == 0 {
+                        tracing::info!("[{}] missed run skipped", alias);
+                        continue;
                     }
+                    tracing::info!("[{}] cron triggered", alias);
+                    start_or_wait(&mut in_flight, &mut queue, &runner, &tracker, alias, job_config, Trigger::Cron);
+                    if runs > 1 {
+                        tracing::info!("[{}] catching up {} missed runs", alias, runs - 1);
+                        *backlog.entry(alias.clone()).or_default() += runs - 1;
+                    }
+                }
+                save_cron_fires(&db_path, &handled, now);
+                for alias in handled {
+                    last_fires.insert(alias, now);
                 }
-                last_cron_check = now;
+
+                // Missed runs go one per tick, whenever the job is free
+                backlog.retain(|alias, owed| {
+                    let Some((_, job_config)) = runner.app().jobs.iter().find(|(a, _)| a == alias) else {
+                        return false;
+                    };
+                    let (exclusive, cooldown) = job_limits(job_config);
+                    if !is_paused(&tracker, alias) && try_acquire(&tracker, alias, exclusive, cooldown) {
+                        in_flight.spawn(&runner, &tracker, alias, Trigger::Cron);
+                        *owed -= 1;
+                    }
+                    *owed > 0
+                });
+                start_queued(&mut in_flight, &mut queue, &runner, &tracker);
+                send_channel_alerts(&runner, &tasks.health);
+            }
+            _ = hangup.recv() => {
+                tracing::info!("received SIGHUP, reloading config");
+                let _ = reload(&mut runner, &mut schedules, &mut last_fires, &mut tasks, &mut config_watch, &db_path);
+            }
+            Some(()) = reload_rx.recv() => {
+                tracing::info!("config directory changed, reloading");
+                let _ = reload(&mut runner, &mut schedules, &mut last_fires, &mut tasks, &mut config_watch, &db_path);
+            }
+            Some(ControlRequest { request, reply }) = control_rx.recv() => {
+                let response = match request {
+                    Request::Reload => match reload(&mut runner, &mut schedules, &mut last_fires, &mut tasks, &mut config_watch, &db_path) {
+                        Ok(summary) => Response::ok(summary),
+                        Err(e) => Response::error(format!("config not reloaded: {e}")),
+                    },
+                    request => handle_control(&request, &mut in_flight, &runner, &tracker, &schedules, &tasks.health, started),
+                };
+                let _ = reply.send(response);
             }
         }
     }
 
+    // No new work from here on: no socket, no cron, and incoming messages
+    // are turned away. Channels stay connected so running jobs can still
+    // reply, and only stop once those are done.
+    let _ = std::fs::remove_file(&socket);
+    drop(control_rx);
+    drop_pending(&mut queue, &backlog);
+    let channels = runner.channels().clone();
+    tokio::spawn(async move {
+        while let Some(msg) = rx.recv().await {
+            tracing::debug!("shutting down, ignoring message on {}", msg.channel);
+            if let Some(channel) = channels.get(&msg.channel) {
+                channel.unclaimed(&msg);
+            }
+        }
+    });
+    let grace = Duration::from_secs(runner.app().daemon.shutdown_grace.unwrap_or(30));
+    in_flight
+        .drain(grace, shutdown_signal(&mut terminate), &db_path)
+        .await;
+    tasks.stop_all();
     Ok(())
 }
 
-async fn run_scheduled_job(
-    app: &AppConfig,
-    db_path: &PathBuf,
-    alias: &str,
-    job_config: &JobConfig,
+/// Forget queued triggers and owed catch-up runs at shutdown, saying how
+/// many. Returns both counts.
+fn drop_pending(queue: &mut TriggerQueue, backlog: &HashMap<String, usize>) -> (usize, usize) {
+    let mut queued = 0;
+    for alias in queue.aliases() {
+        let dropped = queue.remove(&alias);
+        tracing::warn!(
+            "[{}] shutting down, {} queued trigger(s) dropped",
+            alias,
+            dropped
+        );
+        queued += dropped;
+    }
+    let mut missed = 0;
+    for (alias, owed) in backlog {
+        tracing::warn!(
+            "[{}] shutting down, {} missed run(s) not caught up",
+            alias,
+            owed
+        );
+        missed += owed;
+    }
+    (queued, missed)
+}
+
+/// Re-read the config directory and swap it in. If the new config doesn't
+/// load, the old one keeps running untouched.
+fn reload(
+    runner: &mut Arc<JobRunner>,
+    schedules: &mut Vec<(String, CronSchedule)>,
+    last_fires: &mut HashMap<String, NaiveDateTime>,
+    tasks: &mut ChannelTasks,
+    config_watch: &mut ConfigWatch,
+    db_path: &Path,
 ) -> Result<String> {
-    let store = Store::open(db_path)?;
-
-    let prompt_template = job_config
-        .job
-        .as_ref()
-        .and_then(|j| j.prompt.as_deref())
-        .ok_or_else(|| {
-            crate::error::Error::Config(format!("scheduled job '{alias}' has no prompt"))
-        })?;
-
-    let env_wrapper = env::create_environment(job_config.environment.as_ref())?;
-    env_wrapper.ensure_ready()?;
-    let agent = agent::create_agent(&job_config.agent)?;
-
-    let mut ctx = RenderContext::new(app.dictionary.clone());
-    ctx.memories = store.get_memories(alias, 100)?;
-    ctx.secrets = app.secrets.clone();
-
-    let rendered_prompt = template::render(prompt_template, &ctx).await?;
-    let system_prompt = job_config.agent.prompt.as_deref();
-
-    let result = agent
-        .run(&rendered_prompt, system_prompt, env_wrapper.as_ref())
-        .await?;
-
-    // If there's a history prompt, ask the agent to summarize before storing
-    let result_to_store = if let Some(history) = &job_config.history {
-        let summary_prompt = format!("{}\n\n{}", history.prompt, result);
-        match agent.run(&summary_prompt, None, env_wrapper.as_ref()).await {
-            Ok(summary) => summary,
-            Err(e) => {
-                tracing::warn!("[{}] history summarization failed: {}", alias, e);
-                result.clone()
+    let outcome = AppConfig::load().and_then(|app| {
+        app.validate()?;
+        swap_config(
+            app,
+            runner,
+            schedules,
+            last_fires,
+            tasks,
+            config_watch,
+            db_path,
+        )
+    });
+    if let Err(e) = &outcome {
+        tracing::error!("config not reloaded: {e}");
+    }
+    outcome
+}
+
+/// Swap in a loaded config: only channels whose config changed are stopped
+/// or started, and jobs and schedules change together. If a job's interval
+/// doesn't parse or one of the channels can't be built, nothing changes.
+fn swap_config(
+    new_app: AppConfig,
+    runner: &mut Arc<JobRunner>,
+    schedules: &mut Vec<(String, CronSchedule)>,
+    last_fires: &mut HashMap<String, NaiveDateTime>,
+    tasks: &mut ChannelTasks,
+    config_watch: &mut ConfigWatch,
+    db_path: &Path,
+) -> Result<String> {
+    let new_schedules = build_schedules(&new_app)?;
+
+    let old_app = runner.app();
+    let new_channels = effective_channels(&new_app);
+    let diff = reload::diff_channels(
+        &effective_channels(old_app),
+        &new_channels,
+        new_app.secrets != old_app.secrets,
+    );
+
+    // Build first, so a bad channel leaves everything as it was
+    let mut built = Vec::new();
+    for (name, config) in &new_channels {
+        if diff.start.contains(name) || diff.restart.contains(name) {
+            let ch = build_channel(&new_app, db_path, name, config)
+                .map_err(|e| crate::error::Error::Config(format!("channel '{name}': {e}")))?;
+            built.push((name.clone(), ch));
+        }
+    }
+
+    let mut registry = runner.channels().clone();
+    for name in diff.stop.iter().chain(&diff.restart) {
+        tasks.stop(name);
+        registry.remove(name);
+    }
+    for (name, ch) in built {
+        tasks.start(&name, Arc::clone(&ch));
+        registry.insert(name, ch);
+    }
+    warn_unknown_inputs(&new_app, &registry);
+
+    let summary = format!("reloaded {} jobs, {diff}", new_app.jobs.len());
+    tracing::info!("{summary}");
+    config_watch.set(&new_app);
+    reset_changed_fires(schedules, &new_schedules, last_fires, db_path);
+    *schedules = new_schedules;
+    *runner = Arc::new(JobRunner::new(new_app, db_path.to_path_buf(), registry));
+    Ok(summary)
+}
+
+/// Listener tasks of running channels, so a reload can stop and start them.
+struct ChannelTasks {
+    tx: mpsc::Sender<IncomingMessage>,
+    health: ChannelHealths,
+    handles: HashMap<String, tokio::task::JoinHandle<()>>,
+}
+
+impl ChannelTasks {
+    fn new(tx: mpsc::Sender<IncomingMessage>) -> Self {
+        Self {
+            tx,
+            health: ChannelHealths::default(),
+            handles: HashMap::new(),
+        }
+    }
+
+    fn start(&mut self, name: &str, ch: Arc<dyn Channel>) {
+        let channel_tx = tag_messages(name.to_string(), self.tx.clone());
+        self.health
+            .lock()
+            .unwrap()
+            .insert(name.to_string(), ChannelHealth::default());
+        let handle = tokio::spawn(supervisor::supervise(
+            name.to_string(),
+            ch,
+            channel_tx,
+            Arc::clone(&self.health),
+        ));
+        self.handles.insert(name.to_string(), handle);
+    }
+
+    fn stop_all(&mut self) {
+        let names: Vec<String> = self.handles.keys().cloned().collect();
+        for name in names {
+            self.stop(&name);
+        }
+    }
+
+    fn stop(&mut self, name: &str) {
+        if let Some(handle) = self.handles.remove(name) {
+            handle.abort();
+        }
+        self.health.lock().unwrap().remove(name);
+    }
+}
+
+/// Reload on config file changes when `daemon.toml` asks for it.
+struct ConfigWatch {
+    tx: mpsc::Sender<()>,
+    watcher: Option<notify::RecommendedWatcher>,
+}
+
+impl ConfigWatch {
+    fn set(&mut self, app: &AppConfig) {
+        if !app.daemon.watch_config {
+            self.watcher = None;
+        } else if self.watcher.is_none() {
+            match reload::watch_config(&app.config_dir, self.tx.clone()) {
+                Ok(w) => {
+                    tracing::info!("watching {} for changes", app.config_dir.display());
+                    self.watcher = Some(w);
+                }
+                Err(e) => tracing::warn!("{e}, reload with SIGHUP instead"),
             }
         }
-    } else {
-        result.clone()
-    };
+    }
+}
+
+/// Answer everything but `reload`, which swaps out daemon state.
+fn handle_control(
+    request: &Request,
+    in_flight: &mut InFlight,
+    runner: &Arc<JobRunner>,
+    tracker: &JobTracker,
+    schedules: &[(String, CronSchedule)],
+    channel_health: &ChannelHealths,
+    started: DateTime<Local>,
+) -> Response {
+    let job = |alias: &str| runner.app().jobs.iter().find(|(a, _)| a == alias);
+    match request {
+        Request::Status => Response::ok(status_report(
+            runner.app(),
+            tracker,
+            schedules,
+            channel_health,
+            started,
+        )),
+        Request::Jobs => Response::ok(jobs_report(runner.app(), tracker)),
+        Request::Trigger { alias } => {
+            let Some((_, job_config)) = job(alias) else {
+                return Response::error(format!("no job named '{alias}'"));
+            };
+            let (exclusive, cooldown) = job_limits(job_config);
+            match acquire(tracker, alias, exclusive, cooldown) {
+                // A manual trigger overrides pause: pausing stops the
+                // cron and message runs, not someone asking for one
+                Ok(()) if is_paused(tracker, alias) => {
+                    tracing::info!("[{}] triggered via control socket while paused", alias);
+                    in_flight.spawn(runner, tracker, alias, Trigger::Manual);
+                    Response::ok(format!(
+                        "{alias} started (still paused for cron and messages)"
+                    ))
+                }
+                Ok(()) => {
+                    tracing::info!("[{}] triggered via control socket", alias);
+                    in_flight.spawn(runner, tracker, alias, Trigger::Manual);
+                    Response::ok(format!("{alias} started"))
+                }
+                Err(reason) => Response::error(format!("{alias} not started: {reason}")),
+            }
+        }
+        Request::Pause { alias } | Request::Resume { alias } => {
+            if job(alias).is_none() {
+                return Response::error(format!("no job named '{alias}'"));
+            }
+            let paused = matches!(request, Request::Pause { .. });
+            tracker
+                .lock()
+                .unwrap()
+                .entry(alias.clone())
+                .or_default()
+                .paused = paused;
+            let verb = if paused { "paused" } else { "resumed" };
+            tracing::info!("[{}] {}", alias, verb);
+            Response::ok(format!("{alias} {verb}"))
+        }
+        Request::Reload => Response::error("reload is handled by the daemon loop"),
+    }
+}
+
+const TIME_FORMAT: &str = "%Y-%m-%d %H:%M";
 
-    store.store_run(alias, &result_to_store)?;
+/// Per-job state with last and next run, then channel state.
+fn status_report(
+    app: &AppConfig,
+    tracker: &JobTracker,
+    schedules: &[(String, CronSchedule)],
+    channel_health: &ChannelHealths,
+    started: DateTime<Local>,
+) -> String {
+    let now = Local::now().naive_local();
+    let width = app.jobs.iter().map(|(a, _)| a.len()).max().unwrap_or(0);
+    let tracker = tracker.lock().unwrap();
+    let mut out = format!("up since {}\n\njobs:\n", started.format(TIME_FORMAT));
+    for (alias, _) in &app.jobs {
+        let state = tracker.get(alias);
+        let status = match state {
+            Some(s) if s.paused => "paused",
+            Some(s) if s.running => "running",
+            _ => "idle",
+        };
+        let last = match state.and_then(|s| s.last_run.map(|t| (t, &s.last_error))) {
+            Some((t, None)) => t.format(TIME_FORMAT).to_string(),
+            Some((t, Some(e))) => format!("{} (failed: {e})", t.format(TIME_FORMAT)),
+            None => "-".to_string(),
+        };
+        let next = schedules
+            .iter()
+            .find(|(a, _)| a == alias)
+            .and_then(|(_, s)| s.next_from(now))
+            .map_or("-".to_string(), |t| t.format(TIME_FORMAT).to_string());
+        out.push_str(&format!(
+            "  {alias:width$}  {status:7}  last {last}  next {next}\n"
+        ));
+    }
 
-    for output_section in &job_config.outputs {
-        let rendered_message = if let Some(msg_template) = &output_section.message {
-            let mut output_ctx = ctx.clone();
-            output_ctx.result = Some(result.clone());
-            Some(template::render(msg_template, &output_ctx).await?)
+    out.push_str("\nchannels:\n");
+    let health = channel_health.lock().unwrap();
+    let mut names: Vec<&String> = health.keys().collect();
+    names.sort();
+    let width = names.iter().map(|n| n.len()).max().unwrap_or(0);
+    for name in names {
+        out.push_str(&format!("  {name:width$}  {}\n", health[name]));
+    }
+    out
+}
+
+/// What each job listens to, as the daemon currently has it.
+fn jobs_report(app: &AppConfig, tracker: &JobTracker) -> String {
+    let width = app.jobs.iter().map(|(a, _)| a.len()).max().unwrap_or(0);
+    let tracker = tracker.lock().unwrap();
+    let mut out = String::new();
+    for (alias, job) in &app.jobs {
+        let name = job.name.as_deref().unwrap_or("-");
+        let mut via = Vec::new();
+        if let Some(interval) = job.job.as_ref().and_then(|j| j.interval.as_deref()) {
+            via.push(format!("cron {interval}"));
+        }
+        if let Some(input) = &job.input {
+            via.push(match &input.trigger {
+                Some(t) => format!("{} (trigger: {t})", input.channel),
+                None => input.channel.clone(),
+            });
+        }
+        if via.is_empty() {
+            via.push("manual".to_string());
+        }
+        let paused = if tracker.get(alias).is_some_and(|s| s.paused) {
+            "  [paused]"
         } else {
-            None
+            ""
         };
-        if let Err(e) = output::dispatch(output_section, &result, rendered_message.as_deref()).await
-        {
-            tracing::error!("[{}] output dispatch failed: {}", alias, e);
+        out.push_str(&format!(
+            "  {alias:width$}  {name}  via {}{paused}\n",
+            via.join(", ")
+        ));
+    }
+    out
+}
+
+/// Construct every configured channel, keyed by config file name. Nothing
+/// is started; the daemon does that, `vatic run` only sends.
+pub fn build_channels(app: &AppConfig, db_path: &Path) -> ChannelRegistry {
+    if app.channels.is_empty() {
+        tracing::info!("no channel configs found, defaulting to stdin");
+    }
+    let mut channels = ChannelRegistry::new();
+    for (name, config) in effective_channels(app) {
+        match build_channel(app, db_path, &name, &config) {
+            Ok(ch) => {
+                channels.insert(name, ch);
+            }
+            Err(e) => tracing::error!("skipping channel '{}': {}", name, e),
         }
     }
+    channels
+}
 
-    Ok(result)
+/// The configured channels, or stdin when there are none.
+fn effective_channels(app: &AppConfig) -> Vec<(String, ChannelConfig)> {
+    if app.channels.is_empty() {
+        let stdin = ChannelConfig {
+            channel: ChannelSection::Stdin,
+        };
+        return vec![("stdin".to_string(), stdin)];
+    }
+    app.channels.clone()
+}
+
+/// Login state dir `channels/<name>`, taking over an older `channels/<kind>` if unambiguous.
+fn channel_data_dir(app: &AppConfig, name: &str, config: &ChannelConfig, kind: &str) -> PathBuf {
+    let channels = app.data_dir.join("channels");
+    let dir = channels.join(name);
+    let old = channels.join(kind);
+    if name == kind || dir.exists() || !old.is_dir() {
+        return dir;
+    }
+    let same_kind = app
+        .channels
+        .iter()
+        .filter(|(_, c)| {
+            std::mem::discriminant(&c.channel) == std::mem::discriminant(&config.channel)
+        })
+        .count();
+    if same_kind > 1 {
+        tracing::warn!(
+            "[{}] not reusing {}: several {} channels are configured, move it to {} by hand",
+            name,
+            old.display(),
+            kind,
+            dir.display()
+        );
+        return dir;
+    }
+    match std::fs::rename(&old, &dir) {
+        Ok(()) => {
+            tracing::info!("[{}] moved {} to {}", name, old.display(), dir.display());
+            dir
+        }
+        Err(e) => {
+            tracing::warn!(
+                "[{}] cannot move {}: {}; using it in place",
+                name,
+                old.display(),
+                e
+            );
+            old
+        }
+    }
+}
+
+/// One channel from its config; fails on missing secrets and the like.
+fn build_channel(
+    app: &AppConfig,
+    db_path: &Path,
+    name: &str,
+    config: &ChannelConfig,
+) -> Result<Arc<dyn Channel>> {
+    let attachments = app.data_dir.join("attachments").join(name);
+    let ch: Arc<dyn Channel> = match &config.channel {
+        ChannelSection::Stdin => Arc::new(StdinChannel),
+        #[cfg(feature = "whatsapp")]
+        ChannelSection::Whatsapp => {
+            let data_dir = channel_data_dir(app, name, config, "whatsapp");
+            Arc::new(WhatsAppChannel::new(data_dir))
+        }
+        ChannelSection::Telegram { token } => Arc::new(TelegramChannel::new(token.clone())),
+        ChannelSection::Matrix {
+            homeserver,
+            user,
+            password,
+        } => {
+            let data_dir = channel_data_dir(app, name, config, "matrix");
+            Arc::new(MatrixChannel::new(
+                homeserver.clone(),
+                user.clone(),
+                password.clone(),
+                data_dir,
+            ))
+        }
+        ChannelSection::Himalaya {
+            poll_interval,
+            account,
+        } => Arc::new(EmailChannel::new(
+            poll_interval.unwrap_or(60),
+            account.clone(),
+            attachments,
+        )),
+        ChannelSection::Maildir {
+            path,
+            poll_interval,
+            account,
+        } => Arc::new(MaildirChannel::new(
+            path,
+            poll_interval.unwrap_or(5),
+            account.clone(),
+            attachments,
+        )),
+        ChannelSection::Imap {
+            host,
+            port,
+            user,
+            secret,
+            mailbox,
+            smtp_host,
+            smtp_port,
+            from,
+            tls,
+        } => {
+            let password = app.secrets.resolve(secret)?.to_string();
+            let tls = tls.unwrap_or(true);
+            Arc::new(ImapChannel::new(ImapSettings {
+                host: host.clone(),
+                port: port.unwrap_or(if tls { 993 } else { 143 }),
+                user: user.clone(),
+                password,
+                mailbox: mailbox.clone().unwrap_or_else(|| "INBOX".to_string()),
+                smtp_host: smtp_host.clone().unwrap_or_else(|| host.clone()),
+                smtp_port: smtp_port.unwrap_or(if tls { 465 } else { 25 }),
+                from: from.clone().unwrap_or_else(|| user.clone()),
+                tls,
+                attachments,
+            }))
+        }
+        ChannelSection::Webhook {
+            listen,
+            secret,
+            auth,
+            signature_header,
+            sync,
+            timeout,
+        } => {
+            let key = app.secrets.resolve(secret)?.to_string();
+            Arc::new(WebhookChannel::new(
+                listen
+                    .clone()
+                    .unwrap_or_else(|| "127.0.0.1:8787".to_string()),
+                auth.unwrap_or_default(),
+                key,
+                signature_header
+                    .clone()
+                    .unwrap_or_else(|| "X-Hub-Signature-256".to_string()),
+                sync.unwrap_or(false),
+                std::time::Duration::from_secs(timeout.unwrap_or(30)),
+            ))
+        }
+        ChannelSection::Http { listen, secret } => {
+            let token = match secret {
+                Some(secret) => app.secrets.resolve(secret)?.to_string(),
+                None => {
+                    let path = app.data_dir.join("channels").join(name).join("token");
+                    let token = crate::channel::http::load_or_create_token(&path).map_err(|e| {
+                        crate::error::Error::Channel(format!(
+                            "cannot read or create {}: {e}",
+                            path.display()
+                        ))
+                    })?;
+                    tracing::info!("[{}] chat token is in {}", name, path.display());
+                    token
+                }
+            };
+            Arc::new(HttpChannel::new(
+                listen
+                    .clone()
+                    .unwrap_or_else(|| "127.0.0.1:8788".to_string()),
+                token,
+            ))
+        }
+        ChannelSection::Signal {
+            account,
+            socket,
+            tcp,
+            attachments_dir,
+        } => {
+            let endpoint = match (tcp, socket) {
+                (Some(addr), _) => SignalEndpoint::Tcp(addr.clone()),
+                (None, Some(path)) => {
+                    SignalEndpoint::Unix(shellexpand::tilde(path).as_ref().into())
+                }
+                // signal-cli's own default socket location
+                (None, None) => SignalEndpoint::Unix(
+                    std::path::PathBuf::from(
+                        std::env::var("XDG_RUNTIME_DIR").unwrap_or_else(|_| "/tmp".to_string()),
+                    )
+                    .join("signal-cli")
+                    .join("socket"),
+                ),
+            };
+            let attachments_dir = match attachments_dir {
+                Some(dir) => shellexpand::tilde(dir).as_ref().into(),
+                None => app
+                    .data_dir
+                    .parent()
+                    .unwrap_or(&app.data_dir)
+                    .join("signal-cli")
+                    .join("attachments"),
+            };
+            Arc::new(SignalChannel::new(
+                endpoint,
+                account.clone(),
+                attachments_dir,
+            ))
+        }
+        ChannelSection::Irc {
+            server,
+            port,
+            tls,
+            nick,
+            user,
+            secret,
+            channels,
+        } => {
+            let password = match secret {
+                Some(secret) => Some(app.secrets.resolve(secret)?.to_string()),
+                None => None,
+            };
+            let tls = tls.unwrap_or(true);
+            Arc::new(IrcChannel::new(IrcSettings {
+                server: server.clone(),
+                port: port.unwrap_or(if tls { 6697 } else { 6667 }),
+                tls,
+                nick: nick.clone(),
+                user: user.clone(),
+                password,
+                channels: channels.clone(),
+            }))
+        }
+        ChannelSection::Xmpp {
+            jid,
+            password,
+            secret,
+            rooms,
+            nick,
+            server,
+            tls,
+        } => {
+            let password = match (secret, password) {
+                (Some(secret), _) => app.secrets.resolve(secret)?.to_string(),
+                (None, Some(p)) => p.clone(),
+                (None, None) => {
+                    return Err(crate::error::Error::Config(
+                        "xmpp needs `password` or `secret`".to_string(),
+                    ));
+                }
+            };
+            let nick = nick
+                .clone()
+                .unwrap_or_else(|| jid.split('@').next().unwrap_or(jid).to_string());
+            Arc::new(XmppChannel::new(XmppSettings {
+                jid: jid.clone(),
+                password,
+                rooms: rooms.clone(),
+                nick,
+                server: server.clone(),
+                tls: tls.unwrap_or(true),
+            }))
+        }
+        ChannelSection::Slack {
+            app_secret,
+            bot_secret,
+        } => Arc::new(SlackChannel::new(
+            app.secrets.resolve(app_secret)?.to_string(),
+            app.secrets.resolve(bot_secret)?.to_string(),
+        )),
+        ChannelSection::Discord {
+            secret,
+            guilds,
+            channels,
+        } => {
+            let token = app.secrets.resolve(secret)?.to_string();
+            let filter = DiscordFilter {
+                guilds: guilds.clone(),
+                channels: channels.clone(),
+            };
+            Arc::new(DiscordChannel::new(token, filter))
+        }
+        ChannelSection::Mqtt {
+            host,
+            port,
+            tls,
+            client_id,
+            user,
+            secret,
+            topics,
+            reply_topic,
+            qos,
+        } => {
+            let password = match secret {
+                Some(secret) => Some(app.secrets.resolve(secret)?.to_string()),
+                None => None,
+            };
+            let tls = tls.unwrap_or(false);
+            Arc::new(MqttChannel::new(MqttSettings {
+                host: host.clone(),
+                port: port.unwrap_or(if tls { 8883 } else { 1883 }),
+                tls,
+                client_id: client_id.clone().unwrap_or_else(|| "vatic".to_string()),
+                user: user.clone(),
+                password,
+                topics: topics.clone(),
+                reply_topic: reply_topic
+                    .clone()
+                    .unwrap_or_else(|| "{topic}/reply".to_string()),
+                qos: qos.unwrap_or(1),
+            }))
+        }
+        ChannelSection::Ntfy {
+            server,
+            topic,
+            secret,
+            title,
+            priority,
+            tags,
+            click,
+        } => {
+            let token = match secret {
+                Some(secret) => Some(app.secrets.resolve(secret)?.to_string()),
+                None => None,
+            };
+            Arc::new(NtfyChannel::new(
+                server
+                    .clone()
+                    .unwrap_or_else(|| "https://ntfy.sh".to_string()),
+                topic.clone(),
+                token,
+                PushOptions {
+                    title: title.clone(),
+                    priority: *priority,
+                    tags: tags.clone(),
+                    click: click.clone(),
+                },
+            ))
+        }
+        ChannelSection::Gotify {
+            server,
+            app_secret,
+            client_secret,
+            title,
+            priority,
+            click,
+        } => Arc::new(GotifyChannel::new(
+            server.clone(),
+            app.secrets.resolve(app_secret)?.to_string(),
+            app.secrets.resolve(client_secret)?.to_string(),
+            PushOptions {
+                title: title.clone(),
+                priority: *priority,
+                tags: Vec::new(),
+                click: click.clone(),
+            },
+        )),
+        ChannelSection::Feed {
+            urls,
+            poll_interval,
+        } => Arc::new(FeedChannel::new(
+            urls.clone(),
+            std::time::Duration::from_secs(poll_interval.unwrap_or(900)),
+            db_path.to_path_buf(),
+        )),
+        ChannelSection::Watch {
+            paths,
+            patterns,
+            recursive,
+            debounce,
+            poll,
+        } => Arc::new(WatchChannel::new(WatchSettings {
+            paths: paths
+                .iter()
+                .map(|p| shellexpand::tilde(p).as_ref().into())
+                .collect(),
+            patterns: patterns.clone(),
+            recursive: recursive.unwrap_or(false),
+            debounce: std::time::Duration::from_millis(debounce.unwrap_or(2000)),
+            poll: poll.unwrap_or(false),
+        })),
+        ChannelSection::Calendar {
+            paths,
+            lead,
+            poll_interval,
+        } => Arc::new(CalendarChannel::new(CalendarSettings {
+            paths: paths
+                .iter()
+                .map(|p| shellexpand::tilde(p).as_ref().into())
+                .collect(),
+            lead: std::time::Duration::from_secs(lead.unwrap_or(15) * 60),
+            poll_interval: std::time::Duration::from_secs(poll_interval.unwrap_or(60)),
+            db_path: db_path.to_path_buf(),
+        })),
+        #[cfg(not(feature = "whatsapp"))]
+        ChannelSection::Whatsapp => {
+            return Err(crate::error::Error::Config(
+                "whatsapp channel requires the 'whatsapp' feature flag".to_string(),
+            ));
+        }
+    };
+    Ok(ch)
+}
+
+fn warn_unknown_inputs(app: &AppConfig, channels: &ChannelRegistry) {
+    for (alias, job) in &app.jobs {
+        if let Some(input) = &job.input {
+            if !channels.contains_key(&input.channel) {
+                tracing::warn!(
+                    "[{}] input channel '{}' is not configured",
+                    alias,
+                    input.channel
+                );
+            }
+        }
+    }
+}
+
+/// Sender handed to a channel: stamps each message with the channel's
+/// config name before forwarding, so two bots of the same type stay apart.
+fn tag_messages(name: String, tx: mpsc::Sender<IncomingMessage>) -> mpsc::Sender<IncomingMessage> {
+    let (channel_tx, mut channel_rx) = mpsc::channel::<IncomingMessage>(100);
+    tokio::spawn(async move {
+        while let Some(mut msg) = channel_rx.recv().await {
+            msg.channel = name.clone();
+            if tx.send(msg).await.is_err() {
+                break;
+            }
+        }
+    });
+    channel_tx
 }
 
 /// Does the incoming message match this job's input config?
@@ -378,69 +1437,6 @@ pub fn matches_input(job: &JobConfig, msg: &IncomingMessage) -> bool {
     true
 }
 
-async fn run_channel_job(
-    app: &AppConfig,
-    db_path: &PathBuf,
-    alias: &str,
-    job_config: &JobConfig,
-    msg: &IncomingMessage,
-) -> Result<String> {
-    let store = Store::open(db_path)?;
-
-    let env_wrapper = env::create_environment(job_config.environment.as_ref())?;
-    env_wrapper.ensure_ready()?;
-    let agent = agent::create_agent(&job_config.agent)?;
-
-    // Use job's prompt template if available, otherwise the raw message
-    let prompt_template = job_config
-        .job
-        .as_ref()
-        .and_then(|j| j.prompt.as_deref())
-        .unwrap_or(&msg.text);
-
-    let memories = store.get_memories(alias, 100)?;
-    let mut ctx = RenderContext::new(app.dictionary.clone());
-    ctx.result = None;
-    ctx.message = Some(msg.text.clone());
-    ctx.sender = Some(msg.sender.clone());
-    ctx.memories = memories;
-    ctx.secrets = app.secrets.clone();
-
-    let rendered_prompt = template::render(prompt_template, &ctx).await?;
-
-    // Prepend conversation history if session tracking is on
-    let full_prompt = if let Some(session) = &job_config.session {
-        let history = store.get_session(&msg.channel, &msg.sender, session.context)?;
-        build_session_context(&history, &rendered_prompt)
-    } else {
-        rendered_prompt.clone()
-    };
-
-    let system_prompt = job_config.agent.prompt.as_deref();
-    let result = agent
-        .run(&full_prompt, system_prompt, env_wrapper.as_ref())
-        .await?;
-
-    if job_config.session.is_some() {
-        store.store_message(
-            &msg.channel,
-            &msg.sender,
-            crate::store::MessageRole::User,
-            &msg.text,
-        )?;
-        store.store_message(
-            &msg.channel,
-            &msg.sender,
-            crate::store::MessageRole::Assistant,
-            &result,
-        )?;
-    }
-
-    store.store_run(alias, &result)?;
-
-    Ok(result)
-}
-
 /// Flatten session history into a `User: ... / Assistant: ...` conversation string.
 pub fn build_session_context(history: &[SessionMessage], current_message: &str) -> String {
     let mut parts = Vec::new();
@@ -478,6 +1474,7 @@ mod tests {
             channel: channel.into(),
             sender: "local".into(),
             text: text.into(),
+            ..Default::default()
         }
     }
 
@@ -495,6 +1492,63 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_tag_messages_uses_config_name() {
+        let (tx, mut rx) = mpsc::channel(10);
+        let work = tag_messages("telegram-work".into(), tx.clone());
+        let personal = tag_messages("telegram".into(), tx);
+        work.send(make_msg("telegram", "from work")).await.unwrap();
+        personal
+            .send(make_msg("telegram", "from home"))
+            .await
+            .unwrap();
+
+        let mut got = [rx.recv().await.unwrap(), rx.recv().await.unwrap()];
+        got.sort_by(|a, b| a.text.cmp(&b.text));
+        assert_eq!(got[0].channel, "telegram");
+        assert_eq!(got[0].text, "from home");
+        assert_eq!(got[1].channel, "telegram-work");
+    }
+
+    #[test]
+    fn test_channel_data_dir_moves_old_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let matrix = || ChannelConfig {
+            channel: ChannelSection::Matrix {
+                homeserver: "https://matrix.org".into(),
+                user: "@bot:matrix.org".into(),
+                password: "pw".into(),
+            },
+        };
+        let mut app = AppConfig {
+            config_dir: dir.path().into(),
+            data_dir: dir.path().into(),
+            dictionary: crate::config::dictionary::Dictionary::new(),
+            secrets: Default::default(),
+            jobs: vec![],
+            channels: vec![("matrix-work".into(), matrix())],
+            daemon: Default::default(),
+        };
+        let old = dir.path().join("channels").join("matrix");
+        std::fs::create_dir_all(&old).unwrap();
+        std::fs::write(old.join("matrix-sdk-state.sqlite3"), "").unwrap();
+
+        // Two Matrix channels: unclear whose it is, so it stays put
+        app.channels.push(("matrix-home".into(), matrix()));
+        let new = channel_data_dir(&app, "matrix-work", &matrix(), "matrix");
+        assert_eq!(new, dir.path().join("channels").join("matrix-work"));
+        assert!(old.exists());
+
+        app.channels.pop();
+        let new = channel_data_dir(&app, "matrix-work", &matrix(), "matrix");
+        assert!(new.join("matrix-sdk-state.sqlite3").exists());
+        assert!(!old.exists());
+        assert_eq!(
+            channel_data_dir(&app, "matrix-work", &matrix(), "matrix"),
+            new
+        );
+    }
+
     #[test]
     fn test_matches_input_channel_match() {
         let job = make_job(Some(InputSection {
@@ -671,6 +1725,7 @@ mod tests {
             channel: "telegram".into(),
             sender: "franz".into(),
             text: "hello".into(),
+            ..Default::default()
         };
         assert!(matches_input(&job, &msg));
     }
@@ -687,6 +1742,7 @@ mod tests {
             channel: "telegram".into(),
             sender: "attacker".into(),
             text: "hello".into(),
+            ..Default::default()
         };
         assert!(!matches_input(&job, &msg));
     }
@@ -703,6 +1759,7 @@ mod tests {
             channel: "telegram".into(),
             sender: "anyone".into(),
             text: "hello".into(),
+            ..Default::default()
         };
         assert!(matches_input(&job, &msg));
     }
@@ -820,4 +1877,344 @@ mod tests {
         assert!(try_acquire(&tracker, "job1", true, None));
         assert!(try_acquire(&tracker, "job2", true, None));
     }
+
+    #[test]
+    fn test_acquire_reasons() {
+        let tracker = make_tracker();
+        assert_eq!(acquire(&tracker, "job1", true, None), Ok(()));
+        assert_eq!(
+            acquire(&tracker, "job1", true, None),
+            Err("already running")
+        );
+        release(&tracker, "job1");
+        assert_eq!(
+            acquire(&tracker, "job1", true, Some(3600)),
+            Err("cooldown not elapsed")
+        );
+    }
+
+    fn control_runner() -> Arc<JobRunner> {
+        let job = |toml: &str| {
+            crate::config::types::parse_job_config(&toml::from_str(toml).unwrap()).unwrap()
+        };
+        let app = AppConfig {
+            config_dir: "/tmp".into(),
+            data_dir: "/tmp".into(),
+            dictionary: crate::config::dictionary::Dictionary::new(),
+            secrets: Default::default(),
+            jobs: vec![
+                (
+                    "weather".into(),
+                    job("name = \"Weather\"\n[agent]\nname = \"ollama\"\n[job]\ninterval = \"0 8 * * *\"\n"),
+                ),
+                (
+                    "chat".into(),
+                    job("[agent]\nname = \"ollama\"\n[input]\nchannel = \"telegram\"\ntrigger = \"vatic\"\n"),
+                ),
+            ],
+            channels: vec![],
+            daemon: Default::default(),
+        };
+        Arc::new(JobRunner::new(
+            app,
+            "/tmp/vatic.db".into(),
+            Default::default(),
+        ))
+    }
+
+    #[derive(Default)]
+    struct RecordingChannel {
+        sent: Mutex<Vec<(String, String)>>,
+    }
+
+    #[async_trait::async_trait]
+    impl Channel for RecordingChannel {
+        async fn start(&self, _tx: mpsc::Sender<IncomingMessage>) -> Result<()> {
+            Ok(())
+        }
+
+        async fn send(&self, to: &str, message: &str) -> Result<()> {
+            self.sent
+                .lock()
+                .unwrap()
+                .push((to.to_string(), message.to_string()));
+            Ok(())
+        }
+
+        fn name(&self) -> &str {
+            "recording"
+        }
+    }
+
+    #[tokio::test]
+    async fn test_on_busy() {
+        let dir = tempfile::tempdir().unwrap();
+        let job = |toml: &str| {
+            crate::config::types::parse_job_config(&toml::from_str(toml).unwrap()).unwrap()
+        };
+        let app = AppConfig {
+            config_dir: dir.path().into(),
+            data_dir: dir.path().into(),
+            dictionary: crate::config::dictionary::Dictionary::new(),
+            secrets: Default::default(),
+            jobs: vec![
+                (
+                    "chat".into(),
+                    job("[agent]\nname = \"ollama\"\n[job]\non_busy = \"reply\"\nbusy_message = \"hold on\"\n"),
+                ),
+                (
+                    "digest".into(),
+                    job("[agent]\nname = \"ollama\"\n[job]\non_busy = \"queue\"\n"),
+                ),
+            ],
+            channels: vec![],
+            daemon: Default::default(),
+        };
+        let telegram = Arc::new(RecordingChannel::default());
+        let mut channels = ChannelRegistry::new();
+        channels.insert("telegram".into(), telegram.clone() as Arc<dyn Channel>);
+        let runner = Arc::new(JobRunner::new(app, dir.path().join("vatic.db"), channels));
+        let tracker = make_tracker();
+        let mut in_flight = InFlight::default();
+        let mut queue = TriggerQueue::default();
+        acquire(&tracker, "chat", true, None).unwrap();
+        acquire(&tracker, "digest", true, None).unwrap();
+
+        let msg = IncomingMessage {
+            channel: "telegram".into(),
+            sender: "alice".into(),
+            text: "hi".into(),
+            ..Default::default()
+        };
+        let jobs = &runner.app().jobs;
+        start_or_wait(
+            &mut in_flight,
+            &mut queue,
+            &runner,
+            &tracker,
+            "chat",
+            &jobs[0].1,
+            Trigger::Message(msg),
+        );
+        start_or_wait(
+            &mut in_flight,
+            &mut queue,
+            &runner,
+            &tracker,
+            "digest",
+            &jobs[1].1,
+            Trigger::Cron,
+        );
+        assert_eq!(queue.aliases(), ["digest"]);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(
+            *telegram.sent.lock().unwrap(),
+            [("alice".to_string(), "hold on".to_string())]
+        );
+
+        // Still busy: stays queued
+        start_queued(&mut in_flight, &mut queue, &runner, &tracker);
+        assert_eq!(queue.aliases(), ["digest"]);
+
+        release(&tracker, "digest");
+        start_queued(&mut in_flight, &mut queue, &runner, &tracker);
+        assert!(queue.aliases().is_empty());
+        assert_eq!(in_flight.runs.len(), 1);
+        in_flight.tasks.abort_all();
+    }
+
+    #[test]
+    fn test_reset_changed_fires() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("vatic.db");
+        let cron =
+            |alias: &str, expr: &str| (alias.to_string(), CronSchedule::parse(expr).unwrap());
+        let old = [cron("weather", "0 8 * * *"), cron("digest", "0 9 * * *")];
+        let new = [cron("weather", "*/5 * * * *"), cron("digest", "0 9 * * *")];
+        let long_ago = NaiveDateTime::parse_from_str("2026-01-01 00:00", "%Y-%m-%d %H:%M").unwrap();
+        let mut last_fires = HashMap::from([
+            ("weather".to_string(), long_ago),
+            ("digest".to_string(), long_ago),
+        ]);
+
+        reset_changed_fires(&old, &new, &mut last_fires, &db_path);
+        assert!(last_fires["weather"] > long_ago);
+        assert_eq!(last_fires["digest"], long_ago);
+        let stored = Store::open(&db_path)
+            .unwrap()
+            .last_cron_fire("weather")
+            .unwrap();
+        assert!(stored.is_some());
+    }
+
+    #[test]
+    fn test_reload_keeps_schedules_on_bad_interval() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("vatic.db");
+        let mut runner = control_runner();
+        let mut schedules = build_schedules(runner.app()).unwrap();
+        let mut last_fires = HashMap::new();
+        let (tx, _rx) = mpsc::channel(1);
+        let mut tasks = ChannelTasks::new(tx);
+        let (reload_tx, _reload_rx) = mpsc::channel(1);
+        let mut config_watch = ConfigWatch {
+            tx: reload_tx,
+            watcher: None,
+        };
+
+        let mut app = runner.app().clone();
+        app.jobs[0].1.job.as_mut().unwrap().interval = Some("every morning".into());
+        app.jobs.pop();
+        let err = swap_config(
+            app,
+            &mut runner,
+            &mut schedules,
+            &mut last_fires,
+            &mut tasks,
+            &mut config_watch,
+            &db_path,
+        )
+        .unwrap_err();
+
+        assert!(err
+            .to_string()
+            .contains("job 'weather': invalid cron expression"));
+        assert_eq!(runner.app().jobs.len(), 2);
+        assert_eq!(schedules.len(), 1);
+        assert!(
+            schedules[0]
+                == (
+                    "weather".to_string(),
+                    CronSchedule::parse("0 8 * * *").unwrap()
+                )
+        );
+    }
+
+    #[test]
+    fn test_drop_pending_counts() {
+        let mut queue = TriggerQueue::default();
+        queue.push("weather", Trigger::Cron, 5, false);
+        queue.push(
+            "chat",
+            Trigger::Message(make_msg("telegram", "hi")),
+            5,
+            false,
+        );
+        queue.push("chat", Trigger::Manual, 5, false);
+        let backlog = HashMap::from([("weather".to_string(), 3)]);
+        assert_eq!(drop_pending(&mut queue, &backlog), (3, 3));
+        assert!(queue.aliases().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_drain_records_interrupted_runs() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("vatic.db");
+        let mut in_flight = InFlight::default();
+        for (alias, secs) in [("quick", 0), ("slow", 60)] {
+            let handle = in_flight.tasks.spawn(async move {
+                tokio::time::sleep(Duration::from_secs(secs)).await;
+            });
+            in_flight.runs.insert(
+                handle.id(),
+                (alias.to_string(), "cron", "2026-01-01 08:00:00".to_string()),
+            );
+        }
+
+        in_flight
+            .drain(Duration::from_millis(200), std::future::pending(), &db_path)
+            .await;
+
+        let store = Store::open(&db_path).unwrap();
+        assert!(store.interrupted_runs("quick", 10).unwrap().is_empty());
+        let slow = store.interrupted_runs("slow", 10).unwrap();
+        assert_eq!(slow.len(), 1);
+        assert_eq!(slow[0].trigger, "cron");
+        assert_eq!(slow[0].started_at, "2026-01-01 08:00:00");
+    }
+
+    #[test]
+    fn test_control_pause_resume() {
+        let runner = control_runner();
+        let tracker = make_tracker();
+        let states = ChannelHealths::default();
+        let mut in_flight = InFlight::default();
+        let mut handle = |req: Request| {
+            handle_control(
+                &req,
+                &mut in_flight,
+                &runner,
+                &tracker,
+                &[],
+                &states,
+                Local::now(),
+            )
+        };
+
+        assert!(
+            handle(Request::Pause {
+                alias: "weather".into()
+            })
+            .ok
+        );
+        assert!(is_paused(&tracker, "weather"));
+        let jobs = handle(Request::Jobs).output;
+        assert!(jobs.contains("weather  Weather  via cron 0 8 * * *  [paused]"));
+        assert!(jobs.contains("chat     -  via telegram (trigger: vatic)\n"));
+
+        assert!(
+            handle(Request::Resume {
+                alias: "weather".into()
+            })
+            .ok
+        );
+        assert!(!is_paused(&tracker, "weather"));
+        assert!(
+            !handle(Request::Pause {
+                alias: "nope".into()
+            })
+            .ok
+        );
+        assert!(
+            !handle(Request::Trigger {
+                alias: "nope".into()
+            })
+            .ok
+        );
+    }
+
+    #[test]
+    fn test_status_report() {
+        let runner = control_runner();
+        let tracker = make_tracker();
+        acquire(&tracker, "chat", true, None).unwrap();
+        let states = ChannelHealths::default();
+        let down = Local::now();
+        states.lock().unwrap().insert(
+            "telegram".into(),
+            ChannelHealth {
+                state: supervisor::ChannelState::Retrying {
+                    error: "bad token".into(),
+                    at: down,
+                },
+                down_since: Some(down),
+                ..Default::default()
+            },
+        );
+        states
+            .lock()
+            .unwrap()
+            .insert("irc".into(), ChannelHealth::default());
+        let schedules = build_schedules(runner.app()).unwrap();
+
+        let report = status_report(runner.app(), &tracker, &schedules, &states, Local::now());
+        assert!(report.contains("  chat     running  last "));
+        assert!(report.contains("  weather  idle     last -  next "));
+        assert!(report.contains("  irc       running\n"));
+        assert!(report.contains(&format!(
+            "  telegram  down since {}, retry at {}: bad token",
+            down.format("%H:%M"),
+            down.format("%H:%M:%S")
+        )));
+    }
 }
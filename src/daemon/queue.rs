@@ -0,0 +1,131 @@
+use std::collections::{HashMap, VecDeque};
+
+use crate::run::Trigger;
+
+/// Waiting triggers per job across all senders. Feed, watch, MQTT and
+/// webhook triggers come from URLs, paths or topics, so the per-sender
+/// limit alone doesn't bound the queue.
+pub const MAX_QUEUED: usize = 50;
+
+/// Who a trigger is from, so one chatty sender can't fill a job's queue.
+/// Cron and manual triggers count as one sender.
+fn sender(trigger: &Trigger) -> Option<(&str, &str)> {
+    match trigger {
+        Trigger::Message(msg) => Some((&msg.channel, &msg.sender)),
+        Trigger::Manual | Trigger::Cron => None,
+    }
+}
+
+/// Triggers waiting for a busy job, oldest first.
+#[derive(Default)]
+pub struct TriggerQueue {
+    waiting: HashMap<String, VecDeque<Trigger>>,
+}
+
+impl TriggerQueue {
+    /// Queue a trigger behind `alias`. With `replace` it takes the place of
+    /// whatever its sender already has waiting; otherwise it's refused once
+    /// the sender has `limit` waiting. Either way it's refused once the job
+    /// has `MAX_QUEUED` waiting. Returns whether it was queued.
+    pub fn push(&mut self, alias: &str, trigger: Trigger, limit: usize, replace: bool) -> bool {
+        let queue = self.waiting.entry(alias.to_string()).or_default();
+        let from = sender(&trigger);
+        if replace {
+            queue.retain(|t| sender(t) != from);
+        } else if queue.iter().filter(|t| sender(t) == from).count() >= limit {
+            return false;
+        }
+        if queue.len() >= MAX_QUEUED {
+            return false;
+        }
+        queue.push_back(trigger);
+        true
+    }
+
+    pub fn pop(&mut self, alias: &str) -> Option<Trigger> {
+        let queue = self.waiting.get_mut(alias)?;
+        let trigger = queue.pop_front();
+        if queue.is_empty() {
+            self.waiting.remove(alias);
+        }
+        trigger
+    }
+
+    /// Aliases with something waiting.
+    pub fn aliases(&self) -> Vec<String> {
+        let mut aliases: Vec<String> = self.waiting.keys().cloned().collect();
+        aliases.sort();
+        aliases
+    }
+
+    /// Forget a job's waiting triggers, e.g. after it was removed.
+    pub fn remove(&mut self, alias: &str) -> usize {
+        self.waiting.remove(alias).map_or(0, |q| q.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::channel::IncomingMessage;
+
+    fn message(sender: &str, text: &str) -> Trigger {
+        Trigger::Message(IncomingMessage {
+            channel: "telegram".into(),
+            sender: sender.into(),
+            text: text.into(),
+            ..Default::default()
+        })
+    }
+
+    fn text(trigger: Option<Trigger>) -> String {
+        match trigger {
+            Some(Trigger::Message(msg)) => msg.text,
+            other => format!("{other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_queue_limit_per_sender() {
+        let mut queue = TriggerQueue::default();
+        assert!(queue.push("chat", message("alice", "1"), 2, false));
+        assert!(queue.push("chat", message("alice", "2"), 2, false));
+        assert!(!queue.push("chat", message("alice", "3"), 2, false));
+        assert!(queue.push("chat", message("bob", "4"), 2, false));
+        assert!(queue.push("chat", Trigger::Cron, 2, false));
+        assert_eq!(queue.aliases(), ["chat"]);
+
+        assert_eq!(text(queue.pop("chat")), "1");
+        assert_eq!(text(queue.pop("chat")), "2");
+        assert_eq!(text(queue.pop("chat")), "4");
+        assert!(matches!(queue.pop("chat"), Some(Trigger::Cron)));
+        assert!(queue.pop("chat").is_none());
+        assert!(queue.aliases().is_empty());
+    }
+
+    #[test]
+    fn test_queue_limit_per_alias() {
+        let mut queue = TriggerQueue::default();
+        for i in 0..MAX_QUEUED {
+            assert!(queue.push("feed", message(&format!("url{i}"), "x"), 5, false));
+        }
+        assert!(!queue.push("feed", message("another", "x"), 5, false));
+        assert!(!queue.push("feed", message("another", "x"), 5, true));
+        // Replacing a sender's waiting trigger still fits
+        assert!(queue.push("feed", message("url0", "newer"), 5, true));
+        assert!(queue.push("other", message("url0", "x"), 5, false));
+        assert_eq!(queue.remove("feed"), MAX_QUEUED);
+    }
+
+    #[test]
+    fn test_queue_replace() {
+        let mut queue = TriggerQueue::default();
+        assert!(queue.push("chat", message("alice", "old"), 1, true));
+        assert!(queue.push("chat", message("bob", "other"), 1, true));
+        assert!(queue.push("chat", message("alice", "new"), 1, true));
+
+        assert_eq!(text(queue.pop("chat")), "other");
+        assert_eq!(text(queue.pop("chat")), "new");
+        assert_eq!(queue.remove("chat"), 0);
+    }
+}
@@ -0,0 +1,185 @@
+use std::path::Path;
+use std::time::Duration;
+
+use notify::event::ModifyKind;
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use tokio::sync::mpsc;
+
+use crate::config::types::ChannelConfig;
+use crate::error::{Error, Result};
+
+/// Editors write a file several times per save; wait for it to settle.
+const SETTLE: Duration = Duration::from_millis(500);
+
+/// Which channels a reload has to touch, by name.
+#[derive(Debug, Default, PartialEq)]
+pub struct ChannelDiff {
+    pub start: Vec<String>,
+    pub stop: Vec<String>,
+    pub restart: Vec<String>,
+}
+
+impl ChannelDiff {
+    pub fn is_empty(&self) -> bool {
+        self.start.is_empty() && self.stop.is_empty() && self.restart.is_empty()
+    }
+}
+
+impl std::fmt::Display for ChannelDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let parts: Vec<String> = [
+            ("started", &self.start),
+            ("stopped", &self.stop),
+            ("restarted", &self.restart),
+        ]
+        .iter()
+        .filter(|(_, names)| !names.is_empty())
+        .map(|(verb, names)| format!("{verb} {}", names.join(", ")))
+        .collect();
+        if parts.is_empty() {
+            f.write_str("channels unchanged")
+        } else {
+            write!(f, "channels {}", parts.join("; "))
+        }
+    }
+}
+
+/// Compare channel configs by name. When secrets changed every remaining
+/// channel restarts, since there's no telling which one uses which secret.
+pub fn diff_channels(
+    old: &[(String, ChannelConfig)],
+    new: &[(String, ChannelConfig)],
+    secrets_changed: bool,
+) -> ChannelDiff {
+    let find = |list: &[(String, ChannelConfig)], name: &str| {
+        list.iter().find(|(n, _)| n == name).map(|(_, c)| c.clone())
+    };
+    let mut diff = ChannelDiff::default();
+    for (name, config) in new {
+        match find(old, name) {
+            None => diff.start.push(name.clone()),
+            Some(previous) if previous != *config || secrets_changed => {
+                diff.restart.push(name.clone())
+            }
+            Some(_) => {}
+        }
+    }
+    for (name, _) in old {
+        if find(new, name).is_none() {
+            diff.stop.push(name.clone());
+        }
+    }
+    diff.start.sort();
+    diff.stop.sort();
+    diff.restart.sort();
+    diff
+}
+
+/// Only `.toml` edits count; metadata changes and reads don't.
+fn is_config_change(event: &notify::Event) -> bool {
+    let relevant = match event.kind {
+        EventKind::Create(_) | EventKind::Remove(_) => true,
+        EventKind::Modify(ModifyKind::Metadata(_)) => false,
+        EventKind::Modify(_) => true,
+        _ => false,
+    };
+    relevant
+        && event
+            .paths
+            .iter()
+            .any(|p| p.extension().is_some_and(|e| e == "toml"))
+}
+
+/// Nudge `tx` once a burst of changes under `dir` has settled. The
+/// returned watcher has to be kept alive.
+pub fn watch_config(dir: &Path, tx: mpsc::Sender<()>) -> Result<RecommendedWatcher> {
+    let (events_tx, mut events) = mpsc::unbounded_channel();
+    let mut watcher = notify::recommended_watcher(move |e: notify::Result<notify::Event>| {
+        if let Ok(event) = e {
+            if is_config_change(&event) {
+                let _ = events_tx.send(());
+            }
+        }
+    })
+    .map_err(|e| Error::Config(format!("cannot watch {}: {e}", dir.display())))?;
+    watcher
+        .watch(dir, RecursiveMode::Recursive)
+        .map_err(|e| Error::Config(format!("cannot watch {}: {e}", dir.display())))?;
+
+    tokio::spawn(async move {
+        while events.recv().await.is_some() {
+            loop {
+                match tokio::time::timeout(SETTLE, events.recv()).await {
+                    Ok(Some(())) => continue,
+                    Ok(None) => return,
+                    Err(_) => break,
+                }
+            }
+            if tx.send(()).await.is_err() {
+                return;
+            }
+        }
+    });
+    Ok(watcher)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::types::parse_channel_config;
+
+    fn channel(toml: &str) -> ChannelConfig {
+        parse_channel_config(toml).unwrap()
+    }
+
+    #[test]
+    fn test_diff_channels() {
+        let telegram = channel("[channel]\ntype = \"telegram\"\ntoken = \"a\"\n");
+        let telegram_new = channel("[channel]\ntype = \"telegram\"\ntoken = \"b\"\n");
+        let stdin = channel("[channel]\ntype = \"stdin\"\n");
+        let old = vec![
+            ("telegram".to_string(), telegram.clone()),
+            ("work".to_string(), telegram.clone()),
+            ("stdin".to_string(), stdin.clone()),
+        ];
+        let new = vec![
+            ("telegram".to_string(), telegram.clone()),
+            ("work".to_string(), telegram_new),
+            ("console".to_string(), stdin),
+        ];
+
+        let diff = diff_channels(&old, &new, false);
+        assert_eq!(diff.start, ["console"]);
+        assert_eq!(diff.stop, ["stdin"]);
+        assert_eq!(diff.restart, ["work"]);
+        assert_eq!(
+            diff.to_string(),
+            "channels started console; stopped stdin; restarted work"
+        );
+
+        assert!(diff_channels(&old, &old, false).is_empty());
+        let secrets = diff_channels(&old, &old, true);
+        assert_eq!(secrets.restart, ["stdin", "telegram", "work"]);
+    }
+
+    #[tokio::test]
+    async fn test_watch_config_settles() {
+        let dir = tempfile::tempdir().unwrap();
+        let (tx, mut rx) = mpsc::channel(4);
+        let _watcher = watch_config(dir.path(), tx).unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        std::fs::write(dir.path().join("notes.txt"), "ignored").unwrap();
+        for i in 0..3 {
+            std::fs::write(dir.path().join("daemon.toml"), format!("# {i}")).unwrap();
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+
+        tokio::time::timeout(Duration::from_secs(5), rx.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        let again = tokio::time::timeout(Duration::from_secs(1), rx.recv()).await;
+        assert!(again.is_err());
+    }
+}
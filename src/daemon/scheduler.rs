@@ -1,7 +1,15 @@
+use crate::config::types::CatchUp;
 use crate::error::{Error, Result};
 use chrono::NaiveDateTime;
 
+/// A slot this many minutes behind the clock was missed, not merely
+/// reached between two ticks.
+const LATE_MINUTES: i64 = 2;
+/// `catch_up = "all"` runs at most this many, the current slot included.
+pub const MAX_CATCH_UP: usize = 100;
+
 /// Parsed 5-field cron: minute hour day-of-month month day-of-week.
+#[derive(PartialEq)]
 pub struct CronSchedule {
     minutes: Vec<u32>,
     hours: Vec<u32>,
@@ -142,6 +150,41 @@ impl CronSchedule {
 
         None
     }
+
+    /// Runs owed for the slots in `(last, now]`, or `None` if no slot
+    /// passed. Slots a few minutes old were missed (daemon down, machine
+    /// asleep) and count according to `catch_up`.
+    pub fn due(&self, last: NaiveDateTime, now: NaiveDateTime, catch_up: CatchUp) -> Option<usize> {
+        let late = now - chrono::Duration::minutes(LATE_MINUTES);
+        let (mut missed, mut on_time) = (0, false);
+        let mut from = last;
+        while let Some(slot) = self.next_from(from) {
+            if slot > now {
+                break;
+            }
+            if slot > late {
+                on_time = true;
+                from = slot;
+                continue;
+            }
+            missed += 1;
+            // Only "all" cares how many; skip the rest of the gap otherwise
+            from = if catch_up == CatchUp::All && missed < MAX_CATCH_UP {
+                slot
+            } else {
+                late
+            };
+        }
+
+        if missed == 0 && !on_time {
+            return None;
+        }
+        Some(match catch_up {
+            CatchUp::None => usize::from(on_time),
+            CatchUp::Once => 1,
+            CatchUp::All => (missed + usize::from(on_time)).min(MAX_CATCH_UP),
+        })
+    }
 }
 
 fn next_matching(values: &[u32], after: u32) -> Option<u32> {
@@ -209,6 +252,46 @@ mod tests {
     use super::*;
     use chrono::NaiveDate;
 
+    fn at(day: u32, hour: u32, min: u32, sec: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2026, 3, day)
+            .unwrap()
+            .and_hms_opt(hour, min, sec)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_due_on_time() {
+        let cron = CronSchedule::parse("0 8 * * *").unwrap();
+        for policy in [CatchUp::None, CatchUp::Once, CatchUp::All] {
+            assert_eq!(cron.due(at(2, 7, 59, 30), at(2, 8, 0, 10), policy), Some(1));
+        }
+        assert_eq!(cron.due(at(2, 8, 1, 0), at(2, 8, 5, 0), CatchUp::All), None);
+    }
+
+    #[test]
+    fn test_due_after_wakeup() {
+        // Asleep from 07:50 until 08:05
+        let cron = CronSchedule::parse("0 8 * * *").unwrap();
+        let (last, now) = (at(2, 7, 50, 0), at(2, 8, 5, 0));
+        assert_eq!(cron.due(last, now, CatchUp::None), Some(0));
+        assert_eq!(cron.due(last, now, CatchUp::Once), Some(1));
+        assert_eq!(cron.due(last, now, CatchUp::All), Some(1));
+
+        let cron = CronSchedule::parse("*/15 * * * *").unwrap();
+        let (last, now) = (at(2, 6, 0, 0), at(2, 8, 5, 0));
+        assert_eq!(cron.due(last, now, CatchUp::None), Some(0));
+        assert_eq!(cron.due(last, now, CatchUp::Once), Some(1));
+        assert_eq!(cron.due(last, now, CatchUp::All), Some(8));
+    }
+
+    #[test]
+    fn test_due_caps_catch_up() {
+        let cron = CronSchedule::parse("* * * * *").unwrap();
+        let (last, now) = (at(1, 8, 0, 0), at(8, 8, 5, 0));
+        assert_eq!(cron.due(last, now, CatchUp::All), Some(MAX_CATCH_UP));
+        assert_eq!(cron.due(last, now, CatchUp::None), Some(1));
+    }
+
     #[test]
     fn test_parse_simple() {
         let cron = CronSchedule::parse("0 8 * * *").unwrap();
@@ -0,0 +1,261 @@
+use std::collections::HashMap;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use chrono::{DateTime, Local, TimeDelta};
+use tokio::sync::mpsc;
+
+use crate::channel::{Channel, IncomingMessage};
+
+const FIRST_DELAY: Duration = Duration::from_secs(1);
+const MAX_DELAY: Duration = Duration::from_secs(300);
+/// A channel that stays up this long counts as recovered and its backoff
+/// starts over.
+const STABLE: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChannelState {
+    Running,
+    /// `start` returned without an error (stdin hit EOF); not restarted.
+    Stopped,
+    Retrying {
+        error: String,
+        at: DateTime<Local>,
+    },
+}
+
+/// What `vatic ctl status` shows per channel.
+#[derive(Debug, Clone)]
+pub struct ChannelHealth {
+    pub state: ChannelState,
+    pub restarts: u32,
+    pub last_error: Option<String>,
+    /// Set on the first failure, cleared once the channel stays up.
+    pub down_since: Option<DateTime<Local>>,
+    /// An alert went out for the current outage.
+    pub alerted: bool,
+}
+
+impl Default for ChannelHealth {
+    fn default() -> Self {
+        Self {
+            state: ChannelState::Running,
+            restarts: 0,
+            last_error: None,
+            down_since: None,
+            alerted: false,
+        }
+    }
+}
+
+impl std::fmt::Display for ChannelHealth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.state {
+            ChannelState::Running if self.restarts == 0 => f.write_str("running"),
+            ChannelState::Running => write!(
+                f,
+                "running, {} restart(s), last error: {}",
+                self.restarts,
+                self.last_error.as_deref().unwrap_or("-")
+            ),
+            ChannelState::Stopped => f.write_str("stopped"),
+            ChannelState::Retrying { error, at } => {
+                let since = self.down_since.unwrap_or(*at);
+                write!(
+                    f,
+                    "down since {}, retry at {}: {error}",
+                    since.format("%H:%M"),
+                    at.format("%H:%M:%S")
+                )
+            }
+        }
+    }
+}
+
+pub type ChannelHealths = Arc<Mutex<HashMap<String, ChannelHealth>>>;
+
+/// Doubles from 1s up to 5 minutes, then spread by ±20% so channels that
+/// failed together (network down) don't all retry at once. `jitter` is in
+/// `[0, 1)`.
+pub fn backoff(attempt: u32, jitter: f64) -> Duration {
+    let base = FIRST_DELAY
+        .saturating_mul(2u32.saturating_pow(attempt))
+        .min(MAX_DELAY);
+    base.mul_f64(0.8 + 0.4 * jitter)
+}
+
+/// Random enough for spreading retries, without pulling in `rand`.
+pub(crate) fn jitter() -> f64 {
+    let bits = std::collections::hash_map::RandomState::new()
+        .build_hasher()
+        .finish();
+    (bits >> 11) as f64 / (1u64 << 53) as f64
+}
+
+fn update(health: &ChannelHealths, name: &str, f: impl FnOnce(&mut ChannelHealth)) {
+    f(health.lock().unwrap().entry(name.to_string()).or_default());
+}
+
+/// Run a channel until it stops cleanly, restarting it whenever `start`
+/// fails.
+pub async fn supervise(
+    name: String,
+    channel: Arc<dyn Channel>,
+    tx: mpsc::Sender<IncomingMessage>,
+    health: ChannelHealths,
+) {
+    let mut attempt = 0;
+    loop {
+        update(&health, &name, |h| h.state = ChannelState::Running);
+        let run = channel.start(tx.clone());
+        tokio::pin!(run);
+        let result = tokio::select! {
+            result = &mut run => result,
+            _ = tokio::time::sleep(STABLE) => {
+                attempt = 0;
+                update(&health, &name, |h| h.down_since = None);
+                run.await
+            }
+        };
+
+        let error = match result {
+            Ok(()) => {
+                tracing::info!("{} channel stopped", name);
+                update(&health, &name, |h| h.state = ChannelState::Stopped);
+                return;
+            }
+            Err(e) => e.to_string(),
+        };
+        let delay = backoff(attempt, jitter());
+        attempt = attempt.saturating_add(1);
+        tracing::error!(
+            "{} channel error: {}; restarting in {}s",
+            name,
+            error,
+            delay.as_secs()
+        );
+        let now = Local::now();
+        update(&health, &name, |h| {
+            h.state = ChannelState::Retrying {
+                error: error.clone(),
+                at: now + TimeDelta::from_std(delay).unwrap_or_default(),
+            };
+            h.last_error = Some(error);
+            h.down_since.get_or_insert(now);
+        });
+        tokio::time::sleep(delay).await;
+        update(&health, &name, |h| h.restarts += 1);
+    }
+}
+
+/// Alert texts that are due: channels down for at least `after` that
+/// haven't been reported yet, and reported ones that came back.
+pub fn due_alerts(health: &ChannelHealths, after: Duration, now: DateTime<Local>) -> Vec<String> {
+    let mut alerts = Vec::new();
+    for (name, h) in health.lock().unwrap().iter_mut() {
+        match h.down_since {
+            Some(since) if !h.alerted && (now - since).to_std().is_ok_and(|d| d >= after) => {
+                h.alerted = true;
+                alerts.push(format!(
+                    "channel {name} down since {}: {}",
+                    since.format("%H:%M"),
+                    h.last_error.as_deref().unwrap_or("unknown error")
+                ));
+            }
+            None if h.alerted => {
+                h.alerted = false;
+                alerts.push(format!("channel {name} is back up"));
+            }
+            _ => {}
+        }
+    }
+    alerts.sort();
+    alerts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::{Error, Result};
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    #[test]
+    fn test_backoff() {
+        assert_eq!(backoff(0, 0.5), Duration::from_secs(1));
+        assert_eq!(backoff(3, 0.5), Duration::from_secs(8));
+        assert_eq!(backoff(30, 0.5), MAX_DELAY);
+        assert_eq!(backoff(u32::MAX, 0.5), MAX_DELAY);
+        assert_eq!(backoff(2, 0.0), Duration::from_millis(3200));
+        assert!(backoff(2, 0.999) < Duration::from_millis(4800));
+        let j = jitter();
+        assert!((0.0..1.0).contains(&j));
+    }
+
+    /// Fails once, then stops cleanly.
+    struct Flaky {
+        starts: AtomicU32,
+    }
+
+    #[async_trait::async_trait]
+    impl Channel for Flaky {
+        async fn start(&self, _tx: mpsc::Sender<IncomingMessage>) -> Result<()> {
+            if self.starts.fetch_add(1, Ordering::SeqCst) == 0 {
+                return Err(Error::Channel("login failed".into()));
+            }
+            Ok(())
+        }
+
+        async fn send(&self, _to: &str, _message: &str) -> Result<()> {
+            Ok(())
+        }
+
+        fn name(&self) -> &str {
+            "flaky"
+        }
+    }
+
+    #[tokio::test]
+    async fn test_supervise_restarts() {
+        let channel = Arc::new(Flaky {
+            starts: AtomicU32::new(0),
+        });
+        let health = ChannelHealths::default();
+        let (tx, _rx) = mpsc::channel(1);
+        supervise("flaky".into(), channel.clone(), tx, Arc::clone(&health)).await;
+
+        assert_eq!(channel.starts.load(Ordering::SeqCst), 2);
+        let h = health.lock().unwrap()["flaky"].clone();
+        assert_eq!(h.state, ChannelState::Stopped);
+        assert_eq!(h.restarts, 1);
+        assert_eq!(h.last_error.as_deref(), Some("channel error: login failed"));
+        assert!(h.down_since.is_some());
+    }
+
+    #[test]
+    fn test_due_alerts() {
+        let health = ChannelHealths::default();
+        let now = Local::now();
+        update(&health, "matrix", |h| {
+            h.last_error = Some("login failed".into());
+            h.down_since = Some(now - TimeDelta::minutes(11));
+        });
+        update(&health, "irc", |h| {
+            h.down_since = Some(now - TimeDelta::minutes(2));
+        });
+        let after = Duration::from_secs(600);
+
+        let alerts = due_alerts(&health, after, now);
+        assert_eq!(alerts.len(), 1);
+        assert!(alerts[0].starts_with("channel matrix down since "));
+        assert!(alerts[0].ends_with(": login failed"));
+        assert!(due_alerts(&health, after, now).is_empty());
+
+        update(&health, "matrix", |h| h.down_since = None);
+        assert_eq!(
+            due_alerts(&health, after, now),
+            ["channel matrix is back up"]
+        );
+        assert!(due_alerts(&health, after, now).is_empty());
+    }
+}
@@ -28,6 +28,9 @@ pub enum Error {
 
     #[error("channel error: {0}")]
     Channel(String),
+
+    #[error("control error: {0}")]
+    Control(String),
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -78,6 +81,12 @@ mod tests {
         assert_eq!(err.to_string(), "channel error: disconnected");
     }
 
+    #[test]
+    fn test_display_control() {
+        let err = Error::Control("no daemon".into());
+        assert_eq!(err.to_string(), "control error: no daemon");
+    }
+
     #[test]
     fn test_from_io_error() {
         let io_err = std::io::Error::new(std::io::ErrorKind::NotFound, "file not found");
@@ -1,7 +1,8 @@
 use clap::{Parser, Subcommand};
 use tracing_subscriber::EnvFilter;
 
-use vatic::config::AppConfig;
+use vatic::config::{resolve_data_dir, AppConfig};
+use vatic::daemon::control::{self, socket_path, Request};
 use vatic::daemon::run_daemon;
 use vatic::run::run_job;
 
@@ -23,6 +24,40 @@ enum Commands {
     List,
     /// Start the daemon
     Daemon,
+    /// Talk to a running daemon
+    Ctl {
+        #[command(subcommand)]
+        command: CtlCommand,
+    },
+}
+
+#[derive(Subcommand)]
+enum CtlCommand {
+    /// Running jobs, last and next runs, channel state
+    Status,
+    /// Jobs the daemon knows about
+    Jobs,
+    /// Run a job now, respecting exclusive/cooldown (works while paused)
+    Trigger { alias: String },
+    /// Stop cron and message runs of a job (`trigger` still runs it)
+    Pause { alias: String },
+    /// Undo `pause`
+    Resume { alias: String },
+    /// Re-read the config directory
+    Reload,
+}
+
+impl From<CtlCommand> for Request {
+    fn from(command: CtlCommand) -> Self {
+        match command {
+            CtlCommand::Status => Request::Status,
+            CtlCommand::Jobs => Request::Jobs,
+            CtlCommand::Trigger { alias } => Request::Trigger { alias },
+            CtlCommand::Pause { alias } => Request::Pause { alias },
+            CtlCommand::Resume { alias } => Request::Resume { alias },
+            CtlCommand::Reload => Request::Reload,
+        }
+    }
 }
 
 #[tokio::main]
@@ -86,5 +121,35 @@ async fn main() {
                 std::process::exit(1);
             }
         }
+        Commands::Ctl { command } => {
+            // Only the data dir is needed, so a broken config can still be reloaded
+            let socket = match resolve_data_dir() {
+                Ok(dir) => socket_path(&dir),
+                Err(e) => {
+                    eprintln!("error: {e}");
+                    std::process::exit(1);
+                }
+            };
+
+            match control::send(&socket, &command.into()).await {
+                Ok(resp) if resp.ok => print!("{}", with_newline(&resp.output)),
+                Ok(resp) => {
+                    eprint!("error: {}", with_newline(&resp.output));
+                    std::process::exit(1);
+                }
+                Err(e) => {
+                    eprintln!("error: {e}");
+                    std::process::exit(1);
+                }
+            }
+        }
+    }
+}
+
+fn with_newline(s: &str) -> String {
+    if s.ends_with('\n') {
+        s.to_string()
+    } else {
+        format!("{s}\n")
     }
 }
@@ -1,12 +1,25 @@
+use crate::channel::ChannelRegistry;
 use crate::config::types::OutputSection;
-use crate::error::Result;
+use crate::error::{Error, Result};
 
-/// Channel output stub — the daemon handles actual delivery.
-/// No-op when called outside daemon context.
+/// Post to a configured channel, e.g. a cron job's summary to a Telegram chat.
+/// Needs both `channel` (config file name) and `to` (chat, room or address).
 pub async fn send(
-    _output: &OutputSection,
-    _result: &str,
-    _rendered_message: Option<&str>,
+    output: &OutputSection,
+    result: &str,
+    rendered_message: Option<&str>,
+    channels: &ChannelRegistry,
 ) -> Result<()> {
-    Ok(())
+    let name = output
+        .channel
+        .as_deref()
+        .ok_or_else(|| Error::Config("channel output needs a `channel`".to_string()))?;
+    let to = output
+        .to
+        .as_deref()
+        .ok_or_else(|| Error::Config(format!("channel output to '{name}' needs a `to`")))?;
+    let ch = channels
+        .get(name)
+        .ok_or_else(|| Error::Channel(format!("no channel named '{name}'")))?;
+    ch.send(to, rendered_message.unwrap_or(result)).await
 }
@@ -3,6 +3,7 @@ pub mod command;
 pub mod msmtp;
 pub mod notification;
 
+use crate::channel::ChannelRegistry;
 use crate::config::types::OutputSection;
 use crate::error::Result;
 
@@ -13,24 +14,23 @@ pub async fn dispatch(
     output: &OutputSection,
     result: &str,
     rendered_message: Option<&str>,
+    channels: &ChannelRegistry,
 ) -> Result<()> {
-    let name = output.name.unwrap_or_default();
-
-    match name {
+    match output.kind() {
         OutputName::Notification => notification::send(output, result, rendered_message).await,
         OutputName::Msmtp => msmtp::send(output, result, rendered_message).await,
         OutputName::Command => command::execute(output, result, rendered_message).await,
-        OutputName::Channel => {
-            // Daemon handles channel delivery, not us
-            Ok(())
-        }
+        OutputName::Channel => channel::send(output, result, rendered_message, channels).await,
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::channel::{Channel, IncomingMessage};
     use crate::config::types::OutputSection;
+    use std::sync::{Arc, Mutex};
+    use tokio::sync::mpsc;
 
     fn output_config(name: Option<OutputName>) -> OutputSection {
         OutputSection {
@@ -43,21 +43,84 @@ mod tests {
         }
     }
 
+    #[derive(Default)]
+    struct RecordingChannel {
+        sent: Mutex<Vec<(String, String)>>,
+    }
+
+    #[async_trait::async_trait]
+    impl Channel for RecordingChannel {
+        async fn start(&self, _tx: mpsc::Sender<IncomingMessage>) -> Result<()> {
+            Ok(())
+        }
+
+        async fn send(&self, to: &str, message: &str) -> Result<()> {
+            self.sent
+                .lock()
+                .unwrap()
+                .push((to.to_string(), message.to_string()));
+            Ok(())
+        }
+
+        fn name(&self) -> &str {
+            "telegram"
+        }
+    }
+
+    fn channel_output(channel: Option<&str>, to: Option<&str>) -> OutputSection {
+        OutputSection {
+            channel: channel.map(String::from),
+            to: to.map(String::from),
+            ..output_config(Some(OutputName::Channel))
+        }
+    }
+
     #[tokio::test]
-    async fn test_dispatch_channel_noop() {
-        let result = dispatch(
-            &output_config(Some(OutputName::Channel)),
-            "test result",
-            None,
-        )
-        .await;
-        assert!(result.is_ok());
+    async fn test_dispatch_channel_posts_to_named_channel() {
+        let work = Arc::new(RecordingChannel::default());
+        let mut channels = ChannelRegistry::new();
+        channels.insert("telegram-work".into(), work.clone() as Arc<dyn Channel>);
+        channels.insert("telegram".into(), Arc::new(RecordingChannel::default()));
+
+        let out = channel_output(Some("telegram-work"), Some("-100123"));
+        dispatch(&out, "raw", Some("Morning summary"), &channels)
+            .await
+            .unwrap();
+        dispatch(&out, "raw", None, &channels).await.unwrap();
+
+        let sent = work.sent.lock().unwrap();
+        assert_eq!(
+            *sent,
+            [
+                ("-100123".to_string(), "Morning summary".to_string()),
+                ("-100123".to_string(), "raw".to_string()),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_channel_errors() {
+        let mut channels = ChannelRegistry::new();
+        channels.insert("telegram".into(), Arc::new(RecordingChannel::default()));
+
+        let missing_to = channel_output(Some("telegram"), None);
+        assert!(dispatch(&missing_to, "r", None, &channels).await.is_err());
+        let missing_name = channel_output(None, Some("42"));
+        assert!(dispatch(&missing_name, "r", None, &channels).await.is_err());
+        let unknown = channel_output(Some("matrix"), Some("42"));
+        assert!(dispatch(&unknown, "r", None, &channels).await.is_err());
     }
 
     #[tokio::test]
     async fn test_dispatch_defaults_to_notification() {
         // notify-send won't exist in CI, but we just want to confirm
         // the default path works without panicking
-        let _ = dispatch(&output_config(None), "test result", None).await;
+        let _ = dispatch(
+            &output_config(None),
+            "test result",
+            None,
+            &ChannelRegistry::new(),
+        )
+        .await;
     }
 }
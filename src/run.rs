@@ -1,79 +1,463 @@
+use std::path::PathBuf;
+
 use crate::agent::create_agent;
+use crate::channel::{ChannelRegistry, IncomingMessage};
+use crate::config::types::{JobConfig, OutputName, OutputSection};
 use crate::config::AppConfig;
+use crate::daemon::build_session_context;
 use crate::env::create_environment;
 use crate::error::{Error, Result};
 use crate::output;
-use crate::store::Store;
+use crate::store::{MessageRole, Store};
 use crate::template::functions::RenderContext;
 use crate::template::render;
 
-/// Run a single job by alias (one-shot execution).
-pub async fn run_job(app: &AppConfig, alias: &str) -> Result<String> {
-    let (_, job_config) = app
-        .jobs
-        .iter()
-        .find(|(key, _)| key == alias)
-        .ok_or_else(|| Error::Config(format!("no job found with alias '{alias}'")))?;
+/// What started a run.
+#[derive(Debug, Clone)]
+pub enum Trigger {
+    /// `vatic run <alias>`
+    Manual,
+    /// A job's `interval` came due.
+    Cron,
+    /// A message matched the job's `[input]`.
+    Message(IncomingMessage),
+}
+
+impl Trigger {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Manual => "manual",
+            Self::Cron => "cron",
+            Self::Message(_) => "message",
+        }
+    }
+}
+
+/// Runs jobs the same way whatever triggered them:
+/// render → agent → summarize → store → outputs.
+pub struct JobRunner {
+    app: AppConfig,
+    db_path: PathBuf,
+    channels: ChannelRegistry,
+}
+
+impl JobRunner {
+    pub fn new(app: AppConfig, db_path: PathBuf, channels: ChannelRegistry) -> Self {
+        Self {
+            app,
+            db_path,
+            channels,
+        }
+    }
+
+    pub fn app(&self) -> &AppConfig {
+        &self.app
+    }
+
+    pub fn channels(&self) -> &ChannelRegistry {
+        &self.channels
+    }
+
+    pub async fn run(&self, alias: &str, trigger: &Trigger) -> Result<String> {
+        let (_, job_config) = self
+            .app
+            .jobs
+            .iter()
+            .find(|(key, _)| key == alias)
+            .ok_or_else(|| Error::Config(format!("no job found with alias '{alias}'")))?;
+        let message = match trigger {
+            Trigger::Message(msg) => Some(msg),
+            Trigger::Manual | Trigger::Cron => None,
+        };
+
+        // Message-triggered jobs may go without a prompt and use the text as is
+        let prompt_template = job_config
+            .job
+            .as_ref()
+            .and_then(|j| j.prompt.as_deref())
+            .or(message.map(|m| m.text.as_str()))
+            .ok_or_else(|| Error::Config(format!("job '{alias}' has no prompt configured")))?;
+
+        let env_wrapper = create_environment(job_config.environment.as_ref())?;
+        env_wrapper.ensure_ready()?;
+        let agent = create_agent(&job_config.agent)?;
+        let store = Store::open(&self.db_path)?;
+
+        let mut ctx = RenderContext::new(self.app.dictionary.clone());
+        ctx.memories = store.get_memories(alias, 100)?;
+        ctx.secrets = self.app.secrets.clone();
+        if let Some(msg) = message {
+            ctx.message = Some(msg.text.clone());
+            ctx.sender = Some(msg.sender.clone());
+            ctx.attachments = msg
+                .attachments
+                .iter()
+                .map(|p| p.display().to_string())
+                .collect();
+            ctx.meta = msg.meta.clone();
+        }
+
+        let rendered_prompt = render(prompt_template, &ctx).await?;
+
+        // Prepend conversation history if session tracking is on
+        let full_prompt = match (&job_config.session, message) {
+            (Some(session), Some(msg)) => {
+                let history = store.get_session(&msg.channel, &msg.sender, session.context)?;
+                build_session_context(&history, &rendered_prompt)
+            }
+            _ => rendered_prompt,
+        };
+
+        let system_prompt = job_config.agent.prompt.as_deref();
+        let result = agent
+            .run(&full_prompt, system_prompt, env_wrapper.as_ref())
+            .await?;
+
+        // If there's a history prompt, summarize the result before storing it
+        let result_to_store = if let Some(history) = &job_config.history {
+            let summary_prompt = format!("{}\n\n{}", history.prompt, result);
+            match agent.run(&summary_prompt, None, env_wrapper.as_ref()).await {
+                Ok(summary) => summary,
+                Err(e) => {
+                    tracing::warn!("[{}] history summarization failed: {}", alias, e);
+                    result.clone()
+                }
+            }
+        } else {
+            result.clone()
+        };
+
+        if let (Some(_), Some(msg)) = (&job_config.session, message) {
+            store.store_message(&msg.channel, &msg.sender, MessageRole::User, &msg.text)?;
+            store.store_message(&msg.channel, &msg.sender, MessageRole::Assistant, &result)?;
+        }
+        store.store_run(alias, &result_to_store)?;
 
-    let prompt_template = job_config
-        .job
-        .as_ref()
-        .and_then(|j| j.prompt.as_deref())
-        .ok_or_else(|| Error::Config(format!("job '{alias}' has no prompt configured")))?;
+        ctx.result = Some(result.clone());
+        self.send_outputs(alias, job_config, &ctx, &result, message)
+            .await;
 
-    let env_wrapper = create_environment(job_config.environment.as_ref())?;
-    env_wrapper.ensure_ready()?;
+        Ok(result)
+    }
+
+    /// Every output gets its turn; one failing doesn't stop the rest.
+    async fn send_outputs(
+        &self,
+        alias: &str,
+        job_config: &JobConfig,
+        ctx: &RenderContext,
+        result: &str,
+        message: Option<&IncomingMessage>,
+    ) {
+        for output_section in &job_config.outputs {
+            let output_section = match message {
+                Some(msg) if output_section.kind() == OutputName::Channel => {
+                    reply_target(output_section, msg)
+                }
+                _ => output_section.clone(),
+            };
+            let output_section = &output_section;
+
+            let rendered_message = match &output_section.message {
+                Some(msg_template) => match render(msg_template, ctx).await {
+                    Ok(rendered) => Some(rendered),
+                    Err(e) => {
+                        tracing::error!("[{}] output message failed to render: {}", alias, e);
+                        continue;
+                    }
+                },
+                None => None,
+            };
+            if let Err(e) = output::dispatch(
+                output_section,
+                result,
+                rendered_message.as_deref(),
+                &self.channels,
+            )
+            .await
+            {
+                tracing::error!("[{}] output dispatch failed: {}", alias, e);
+            }
+        }
+    }
+}
 
-    let agent = create_agent(&job_config.agent)?;
+/// Fill in where a channel output on a message-triggered job goes: the
+/// channel the message came in on unless another is named, and back to the
+/// sender when staying on that channel. Another channel needs its own `to`.
+fn reply_target(output: &OutputSection, msg: &IncomingMessage) -> OutputSection {
+    let mut output = output.clone();
+    let channel = output.channel.get_or_insert_with(|| msg.channel.clone());
+    if output.to.is_none() && *channel == msg.channel {
+        output.to = Some(msg.reply_address().to_string());
+    }
+    output
+}
 
+/// Where the database lives, creating the data directory if needed.
+pub fn db_path(app: &AppConfig) -> Result<PathBuf> {
     let db_path = app.data_dir.join("vatic.db");
     if let Some(parent) = db_path.parent() {
         std::fs::create_dir_all(parent)
             .map_err(|e| Error::Store(format!("cannot create data directory: {e}")))?;
     }
-    let store = Store::open(&db_path)?;
+    Ok(db_path)
+}
 
-    let mut ctx = RenderContext::new(app.dictionary.clone());
-    ctx.memories = store.get_memories(alias, 100)?;
+/// Run a single job by alias (one-shot execution).
+pub async fn run_job(app: &AppConfig, alias: &str) -> Result<String> {
+    let db_path = db_path(app)?;
 
-    let rendered_prompt = render(prompt_template, &ctx).await?;
-    let system_prompt = job_config.agent.prompt.as_deref();
+    // Channels are only built, not started: fine for HTTP-based ones like
+    // Telegram or Slack, while Matrix, IRC and friends need the daemon
+    let posts_to_channel = app
+        .jobs
+        .iter()
+        .filter(|(key, _)| key == alias)
+        .flat_map(|(_, job)| &job.outputs)
+        .any(|o| o.kind() == OutputName::Channel);
+    let channels = if posts_to_channel {
+        crate::daemon::build_channels(app, &db_path)
+    } else {
+        ChannelRegistry::new()
+    };
 
-    let result = agent
-        .run(&rendered_prompt, system_prompt, env_wrapper.as_ref())
-        .await?;
+    JobRunner::new(app.clone(), db_path, channels)
+        .run(alias, &Trigger::Manual)
+        .await
+}
 
-    // If there's a history prompt, summarize the result before storing it
-    let result_to_store = if let Some(history) = &job_config.history {
-        let summary_prompt = format!("{}\n\n{}", history.prompt, result);
-        match agent.run(&summary_prompt, None, env_wrapper.as_ref()).await {
-            Ok(summary) => summary,
-            Err(e) => {
-                tracing::warn!("history summarization failed, storing raw result: {}", e);
-                result.clone()
-            }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::channel::Channel;
+    use crate::config::dictionary::Dictionary;
+    use crate::config::types::parse_job_config;
+    use axum::routing::post;
+    use axum::{Json, Router};
+    use std::path::Path;
+    use std::sync::{Arc, Mutex};
+    use tokio::sync::mpsc;
+
+    #[derive(Default)]
+    struct RecordingChannel {
+        sent: Mutex<Vec<(String, String)>>,
+    }
+
+    #[async_trait::async_trait]
+    impl Channel for RecordingChannel {
+        async fn start(&self, _tx: mpsc::Sender<IncomingMessage>) -> Result<()> {
+            Ok(())
+        }
+
+        async fn send(&self, to: &str, message: &str) -> Result<()> {
+            self.sent
+                .lock()
+                .unwrap()
+                .push((to.to_string(), message.to_string()));
+            Ok(())
         }
-    } else {
-        result.clone()
-    };
 
-    store.store_run(alias, &result_to_store)?;
+        fn name(&self) -> &str {
+            "telegram"
+        }
+    }
 
-    for output_section in &job_config.outputs {
-        // Render the output's message template if it has one
-        let rendered_message = if let Some(msg_template) = &output_section.message {
-            let mut output_ctx = ctx.clone();
-            output_ctx.result = Some(result.clone());
-            Some(render(msg_template, &output_ctx).await?)
-        } else {
-            None
-        };
+    /// Ollama stand-in: answers every prompt with `echo: <prompt>`.
+    async fn fake_ollama() -> String {
+        let app = Router::new().route(
+            "/api/generate",
+            post(|Json(body): Json<serde_json::Value>| async move {
+                let prompt = body["prompt"].as_str().unwrap_or_default();
+                Json(serde_json::json!({ "response": format!("echo: {prompt}") }))
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        url
+    }
 
-        if let Err(e) = output::dispatch(output_section, &result, rendered_message.as_deref()).await
-        {
-            tracing::warn!("output dispatch failed: {e}");
+    fn app_with_job(dir: &Path, job_toml: &str) -> AppConfig {
+        let job = parse_job_config(&toml::from_str(job_toml).unwrap()).unwrap();
+        AppConfig {
+            config_dir: dir.to_path_buf(),
+            data_dir: dir.to_path_buf(),
+            dictionary: Dictionary::new(),
+            secrets: Default::default(),
+            jobs: vec![("digest".to_string(), job)],
+            channels: vec![],
+            daemon: Default::default(),
         }
     }
 
-    Ok(result)
+    #[tokio::test]
+    async fn test_message_run_summarizes_replies_and_dispatches() {
+        let host = fake_ollama().await;
+        let dir = tempfile::tempdir().unwrap();
+        let out_file = dir.path().join("out.txt");
+        let app = app_with_job(
+            dir.path(),
+            &format!(
+                r#"
+[agent]
+name = "ollama"
+host = "{host}"
+
+[job]
+prompt = "from {{% sender %}}: {{% message %}}"
+
+[history]
+prompt = "Summarize"
+
+[output]
+channel = "telegram"
+
+["output:1"]
+name = "command"
+command = "printf '%s' \"$VATIC_RESULT\" > {out}"
+"#,
+                out = out_file.display()
+            ),
+        );
+        let telegram = Arc::new(RecordingChannel::default());
+        let mut channels = ChannelRegistry::new();
+        channels.insert("telegram".into(), telegram.clone() as Arc<dyn Channel>);
+        let runner = JobRunner::new(app, dir.path().join("vatic.db"), channels);
+
+        let msg = IncomingMessage {
+            channel: "telegram".into(),
+            sender: "42".into(),
+            text: "hi".into(),
+            ..Default::default()
+        };
+        let result = runner.run("digest", &Trigger::Message(msg)).await.unwrap();
+        assert_eq!(result, "echo: from 42: hi");
+
+        assert_eq!(
+            *telegram.sent.lock().unwrap(),
+            [("42".to_string(), result.clone())]
+        );
+        assert_eq!(std::fs::read_to_string(&out_file).unwrap(), result);
+        let store = Store::open(&dir.path().join("vatic.db")).unwrap();
+        let memories = store.get_memories("digest", 10).unwrap();
+        assert_eq!(memories[0].result, format!("echo: Summarize\n\n{result}"));
+    }
+
+    #[test]
+    fn test_reply_target() {
+        let msg = IncomingMessage {
+            channel: "himalaya".into(),
+            sender: "alice@example.org".into(),
+            ..Default::default()
+        };
+        let output = |channel: Option<&str>, to: Option<&str>| OutputSection {
+            name: Some(OutputName::Channel),
+            channel: channel.map(String::from),
+            to: to.map(String::from),
+            subject: None,
+            message: None,
+            command: None,
+        };
+
+        let same = reply_target(&output(None, None), &msg);
+        assert_eq!(same.channel.as_deref(), Some("himalaya"));
+        assert_eq!(same.to.as_deref(), Some("alice@example.org"));
+
+        let elsewhere = reply_target(&output(Some("matrix"), Some("!room:m.org")), &msg);
+        assert_eq!(elsewhere.channel.as_deref(), Some("matrix"));
+        assert_eq!(elsewhere.to.as_deref(), Some("!room:m.org"));
+
+        // No sender to borrow on another channel
+        let no_to = reply_target(&output(Some("matrix"), None), &msg);
+        assert_eq!(no_to.to, None);
+    }
+
+    #[tokio::test]
+    async fn test_reply_renders_message_on_named_channel() {
+        let host = fake_ollama().await;
+        let dir = tempfile::tempdir().unwrap();
+        let app = app_with_job(
+            dir.path(),
+            &format!(
+                r#"
+[agent]
+name = "ollama"
+host = "{host}"
+
+[output]
+channel = "matrix"
+to = "!ops:example.org"
+message = "{{% sender %}} asked, {{% result %}}"
+"#
+            ),
+        );
+        let email = Arc::new(RecordingChannel::default());
+        let matrix = Arc::new(RecordingChannel::default());
+        let mut channels = ChannelRegistry::new();
+        channels.insert("himalaya".into(), email.clone() as Arc<dyn Channel>);
+        channels.insert("matrix".into(), matrix.clone() as Arc<dyn Channel>);
+        let runner = JobRunner::new(app, dir.path().join("vatic.db"), channels);
+
+        let msg = IncomingMessage {
+            channel: "himalaya".into(),
+            sender: "alice@example.org".into(),
+            text: "disk usage?".into(),
+            ..Default::default()
+        };
+        runner.run("digest", &Trigger::Message(msg)).await.unwrap();
+
+        assert!(email.sent.lock().unwrap().is_empty());
+        assert_eq!(
+            *matrix.sent.lock().unwrap(),
+            [(
+                "!ops:example.org".to_string(),
+                "alice@example.org asked, echo: disk usage?".to_string()
+            )]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_bad_output_message_skips_only_that_output() {
+        let host = fake_ollama().await;
+        let dir = tempfile::tempdir().unwrap();
+        let app = app_with_job(
+            dir.path(),
+            &format!(
+                r#"
+[agent]
+name = "ollama"
+host = "{host}"
+
+[job]
+prompt = "status"
+
+[output]
+channel = "telegram"
+to = "42"
+message = "{{% custom:missing %}}"
+
+["output:1"]
+channel = "telegram"
+to = "7"
+"#
+            ),
+        );
+        let telegram = Arc::new(RecordingChannel::default());
+        let mut channels = ChannelRegistry::new();
+        channels.insert("telegram".into(), telegram.clone() as Arc<dyn Channel>);
+        let runner = JobRunner::new(app, dir.path().join("vatic.db"), channels);
+
+        let result = runner.run("digest", &Trigger::Manual).await.unwrap();
+        assert_eq!(*telegram.sent.lock().unwrap(), [("7".to_string(), result)]);
+    }
+
+    #[tokio::test]
+    async fn test_manual_run_needs_prompt() {
+        let dir = tempfile::tempdir().unwrap();
+        let app = app_with_job(dir.path(), "[agent]\nname = \"ollama\"\n");
+        let runner = JobRunner::new(app, dir.path().join("vatic.db"), ChannelRegistry::new());
+        assert!(runner.run("digest", &Trigger::Manual).await.is_err());
+        assert!(runner.run("missing", &Trigger::Cron).await.is_err());
+    }
 }
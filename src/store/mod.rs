@@ -39,6 +39,16 @@ pub struct SessionMessage {
     pub timestamp: String,
 }
 
+/// A run cut short by shutdown.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InterruptedRun {
+    pub job_alias: String,
+    /// `manual`, `cron` or `message`.
+    pub trigger: String,
+    pub started_at: String,
+    pub interrupted_at: String,
+}
+
 pub struct Store {
     conn: Connection,
 }
@@ -79,6 +89,38 @@ impl Store {
                 created_at TEXT NOT NULL DEFAULT (datetime('now'))
             );
 
+            CREATE TABLE IF NOT EXISTS feed_items (
+                feed TEXT NOT NULL,
+                guid TEXT NOT NULL,
+                seen_at TEXT NOT NULL DEFAULT (datetime('now')),
+                PRIMARY KEY (feed, guid)
+            );
+
+            CREATE TABLE IF NOT EXISTS feeds (
+                url TEXT PRIMARY KEY,
+                polled_at TEXT NOT NULL DEFAULT (datetime('now'))
+            );
+
+            CREATE TABLE IF NOT EXISTS calendar_fires (
+                uid TEXT NOT NULL,
+                start TEXT NOT NULL,
+                fired_at TEXT NOT NULL DEFAULT (datetime('now')),
+                PRIMARY KEY (uid, start)
+            );
+
+            CREATE TABLE IF NOT EXISTS interrupted_runs (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                job_alias TEXT NOT NULL,
+                trigger TEXT NOT NULL,
+                started_at TEXT NOT NULL,
+                interrupted_at TEXT NOT NULL DEFAULT (datetime('now'))
+            );
+
+            CREATE TABLE IF NOT EXISTS cron_fires (
+                job_alias TEXT PRIMARY KEY,
+                fired_at TEXT NOT NULL
+            );
+
             CREATE INDEX IF NOT EXISTS idx_job_runs_alias ON job_runs(job_alias);
             CREATE INDEX IF NOT EXISTS idx_sessions_channel_sender ON sessions(channel, sender);
         ",
@@ -156,6 +198,8 @@ impl Store {
 
     /// Remove old data to prevent unbounded growth.
     /// Keeps the most recent `max_runs` per job and sessions from the last `max_session_days`.
+    /// Feed entries are forgotten once they've been missing from their feed's
+    /// polls for `max_session_days`.
     pub fn prune(&self, max_runs: u32, max_session_days: u32) -> Result<()> {
         // Prune job_runs: keep only the latest max_runs per job_alias
         self.conn.execute(
@@ -174,6 +218,138 @@ impl Store {
             rusqlite::params![format!("-{max_session_days} days")],
         )?;
 
+        // Relative to the feed's last poll, so downtime doesn't count
+        self.conn.execute(
+            "DELETE FROM feed_items WHERE seen_at < (
+                SELECT datetime(polled_at, ?1) FROM feeds WHERE url = feed_items.feed
+            )",
+            rusqlite::params![format!("-{max_session_days} days")],
+        )?;
+
+        // Event instances this far in the past can't come round again
+        self.conn.execute(
+            "DELETE FROM calendar_fires WHERE datetime(start) < datetime('now', ?1)",
+            rusqlite::params![format!("-{max_session_days} days")],
+        )?;
+
+        Ok(())
+    }
+
+    /// Whether this feed was polled before.
+    pub fn has_feed(&self, feed: &str) -> Result<bool> {
+        let found = self
+            .conn
+            .query_row(
+                "SELECT 1 FROM feeds WHERE url = ?1
+                 UNION ALL SELECT 1 FROM feed_items WHERE feed = ?1 LIMIT 1",
+                rusqlite::params![feed],
+                |_| Ok(()),
+            )
+            .optional()?;
+        Ok(found.is_some())
+    }
+
+    /// Note a successful poll, even one that found no entries.
+    pub fn record_feed_poll(&self, feed: &str) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO feeds (url) VALUES (?1)
+             ON CONFLICT (url) DO UPDATE SET polled_at = datetime('now')",
+            rusqlite::params![feed],
+        )?;
+        Ok(())
+    }
+
+    /// Record a feed entry. Returns true if it wasn't seen before.
+    pub fn record_feed_item(&self, feed: &str, guid: &str) -> Result<bool> {
+        let inserted = self.conn.execute(
+            "INSERT OR IGNORE INTO feed_items (feed, guid) VALUES (?1, ?2)",
+            rusqlite::params![feed, guid],
+        )?;
+        if inserted == 0 {
+            self.conn.execute(
+                "UPDATE feed_items SET seen_at = datetime('now') WHERE feed = ?1 AND guid = ?2",
+                rusqlite::params![feed, guid],
+            )?;
+        }
+        Ok(inserted == 1)
+    }
+
+    /// Whether this event instance fired before.
+    pub fn has_calendar_fire(&self, uid: &str, start: &str) -> Result<bool> {
+        let found = self
+            .conn
+            .query_row(
+                "SELECT 1 FROM calendar_fires WHERE uid = ?1 AND start = ?2",
+                rusqlite::params![uid, start],
+                |_| Ok(()),
+            )
+            .optional()?;
+        Ok(found.is_some())
+    }
+
+    /// Record that an event instance fired. Returns true the first time.
+    pub fn record_calendar_fire(&self, uid: &str, start: &str) -> Result<bool> {
+        let inserted = self.conn.execute(
+            "INSERT OR IGNORE INTO calendar_fires (uid, start) VALUES (?1, ?2)",
+            rusqlite::params![uid, start],
+        )?;
+        Ok(inserted == 1)
+    }
+
+    /// Note a run that was still going when the daemon stopped. Kept apart
+    /// from `job_runs` so it doesn't show up as a memory.
+    pub fn record_interrupted(
+        &self,
+        job_alias: &str,
+        trigger: &str,
+        started_at: &str,
+    ) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO interrupted_runs (job_alias, trigger, started_at) VALUES (?1, ?2, ?3)",
+            rusqlite::params![job_alias, trigger, started_at],
+        )?;
+        Ok(())
+    }
+
+    /// Interrupted runs of a job, latest first.
+    pub fn interrupted_runs(&self, job_alias: &str, limit: u32) -> Result<Vec<InterruptedRun>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT job_alias, trigger, started_at, interrupted_at FROM interrupted_runs \
+             WHERE job_alias = ?1 ORDER BY id DESC LIMIT ?2",
+        )?;
+        let runs = stmt
+            .query_map(rusqlite::params![job_alias, limit], |row| {
+                Ok(InterruptedRun {
+                    job_alias: row.get(0)?,
+                    trigger: row.get(1)?,
+                    started_at: row.get(2)?,
+                    interrupted_at: row.get(3)?,
+                })
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(runs)
+    }
+
+    /// When a job's schedule was last handled, in local time. Runs due
+    /// after this were missed if the daemon wasn't around for them.
+    pub fn last_cron_fire(&self, job_alias: &str) -> Result<Option<String>> {
+        let fired_at = self
+            .conn
+            .query_row(
+                "SELECT fired_at FROM cron_fires WHERE job_alias = ?1",
+                rusqlite::params![job_alias],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(fired_at)
+    }
+
+    pub fn set_cron_fire(&self, job_alias: &str, fired_at: &str) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO cron_fires (job_alias, fired_at) VALUES (?1, ?2) \
+             ON CONFLICT(job_alias) DO UPDATE SET fired_at = excluded.fired_at",
+            rusqlite::params![job_alias, fired_at],
+        )?;
         Ok(())
     }
 
@@ -384,6 +560,100 @@ mod tests {
         assert_eq!(random[0].content, "random msg");
     }
 
+    #[test]
+    fn test_record_feed_item() {
+        let store = Store::open_memory().unwrap();
+        assert!(!store.has_feed("https://a/rss").unwrap());
+        assert!(store.record_feed_item("https://a/rss", "g1").unwrap());
+        assert!(!store.record_feed_item("https://a/rss", "g1").unwrap());
+        assert!(store.record_feed_item("https://b/rss", "g1").unwrap());
+        assert!(store.has_feed("https://a/rss").unwrap());
+
+        assert!(!store.has_feed("https://empty/rss").unwrap());
+        store.record_feed_poll("https://empty/rss").unwrap();
+        assert!(store.has_feed("https://empty/rss").unwrap());
+    }
+
+    #[test]
+    fn test_prune_feed_items() {
+        let store = Store::open_memory().unwrap();
+        let feed = "https://a/rss";
+        store.record_feed_item(feed, "gone").unwrap();
+        store.record_feed_item(feed, "current").unwrap();
+        store.record_feed_poll(feed).unwrap();
+        store
+            .conn
+            .execute(
+                "UPDATE feed_items SET seen_at = datetime('now', '-40 days')",
+                [],
+            )
+            .unwrap();
+        // Still listed in the feed: seen again on this poll
+        assert!(!store.record_feed_item(feed, "current").unwrap());
+
+        store.prune(1000, 30).unwrap();
+        assert!(store.record_feed_item(feed, "gone").unwrap());
+        assert!(!store.record_feed_item(feed, "current").unwrap());
+    }
+
+    #[test]
+    fn test_record_calendar_fire() {
+        let store = Store::open_memory().unwrap();
+        let start = "2026-01-05T09:00:00+00:00";
+        assert!(!store.has_calendar_fire("e1", start).unwrap());
+        assert!(store.record_calendar_fire("e1", start).unwrap());
+        assert!(store.has_calendar_fire("e1", start).unwrap());
+        assert!(!store.record_calendar_fire("e1", start).unwrap());
+        assert!(store
+            .record_calendar_fire("e1", "2026-01-12T09:00:00+00:00")
+            .unwrap());
+    }
+
+    #[test]
+    fn test_prune_calendar_fires() {
+        let store = Store::open_memory().unwrap();
+        let old = (chrono::Utc::now() - chrono::Duration::days(40)).to_rfc3339();
+        let recent = (chrono::Utc::now() - chrono::Duration::days(2)).to_rfc3339();
+        store.record_calendar_fire("daily", &old).unwrap();
+        store.record_calendar_fire("daily", &recent).unwrap();
+
+        store.prune(1000, 30).unwrap();
+        assert!(!store.has_calendar_fire("daily", &old).unwrap());
+        assert!(store.has_calendar_fire("daily", &recent).unwrap());
+    }
+
+    #[test]
+    fn test_interrupted_runs() {
+        let store = Store::open_memory().unwrap();
+        store
+            .record_interrupted("report", "cron", "2026-03-01 08:00:00")
+            .unwrap();
+        store
+            .record_interrupted("report", "manual", "2026-03-02 08:00:00")
+            .unwrap();
+        let runs = store.interrupted_runs("report", 10).unwrap();
+        assert_eq!(runs.len(), 2);
+        assert_eq!(runs[0].trigger, "manual");
+        assert_eq!(runs[1].started_at, "2026-03-01 08:00:00");
+        assert!(store.get_memories("report", 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_cron_fires() {
+        let store = Store::open_memory().unwrap();
+        assert_eq!(store.last_cron_fire("digest").unwrap(), None);
+        store
+            .set_cron_fire("digest", "2026-03-01 08:00:10")
+            .unwrap();
+        store
+            .set_cron_fire("digest", "2026-03-02 08:00:20")
+            .unwrap();
+        assert_eq!(
+            store.last_cron_fire("digest").unwrap().as_deref(),
+            Some("2026-03-02 08:00:20")
+        );
+    }
+
     #[test]
     fn test_session_empty() {
         let store = Store::open_memory().unwrap();
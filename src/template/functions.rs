@@ -27,6 +27,10 @@ pub struct RenderContext {
     pub result: Option<String>,
     pub message: Option<String>,
     pub sender: Option<String>,
+    /// Paths of files that came with the incoming message.
+    pub attachments: Vec<String>,
+    /// Channel-specific fields, read with `{% meta:key %}`.
+    pub meta: HashMap<String, String>,
     pub memories: Vec<MemoryEntry>,
     pub loop_vars: HashMap<String, LoopValue>,
 }
@@ -40,6 +44,8 @@ impl RenderContext {
             result: None,
             message: None,
             sender: None,
+            attachments: vec![],
+            meta: HashMap::new(),
             memories: vec![],
             loop_vars: HashMap::new(),
         }
@@ -50,6 +56,12 @@ impl RenderContext {
 pub fn resolve_tag(tag: &TagContent, ctx: &RenderContext) -> Result<String> {
     let name = &tag.name;
 
+    // meta:key — channel fields; keys may contain dots, so check before loop vars.
+    // Missing keys are empty since payloads vary between events.
+    if let Some(key) = name.strip_prefix("meta:") {
+        return Ok(ctx.meta.get(key).cloned().unwrap_or_default());
+    }
+
     // Dotted access for loop variables: `i.date`, `i.result`, etc.
     if let Some(dot_pos) = name.find('.') {
         let var_name = &name[..dot_pos];
@@ -74,6 +86,7 @@ pub fn resolve_tag(tag: &TagContent, ctx: &RenderContext) -> Result<String> {
         "result" => Ok(ctx.result.clone().unwrap_or_default()),
         "message" => Ok(ctx.message.clone().unwrap_or_default()),
         "sender" => Ok(ctx.sender.clone().unwrap_or_default()),
+        "attachments" => Ok(ctx.attachments.join("\n")),
         "memory" => resolve_memory(tag, ctx),
         _ => {
             // Fall through to loop variables
@@ -657,6 +670,37 @@ mod tests {
         assert_eq!(resolve_tag(&t, &ctx).unwrap(), "");
     }
 
+    #[test]
+    fn test_attachments_one_per_line() {
+        let mut ctx = empty_ctx();
+        ctx.attachments = vec!["/tmp/a.pdf".into(), "/tmp/b.csv".into()];
+        let t = tag("attachments");
+        assert_eq!(resolve_tag(&t, &ctx).unwrap(), "/tmp/a.pdf\n/tmp/b.csv");
+    }
+
+    #[test]
+    fn test_attachments_missing_returns_empty() {
+        let ctx = empty_ctx();
+        let t = tag("attachments");
+        assert_eq!(resolve_tag(&t, &ctx).unwrap(), "");
+    }
+
+    #[test]
+    fn test_meta_dotted_key() {
+        let mut ctx = empty_ctx();
+        ctx.meta
+            .insert("pull_request.title".into(), "Fix parser".into());
+        let t = tag("meta:pull_request.title");
+        assert_eq!(resolve_tag(&t, &ctx).unwrap(), "Fix parser");
+    }
+
+    #[test]
+    fn test_meta_missing_returns_empty() {
+        let ctx = empty_ctx();
+        let t = tag("meta:alerts.0.status");
+        assert_eq!(resolve_tag(&t, &ctx).unwrap(), "");
+    }
+
     #[test]
     fn test_parse_duration_decimal() {
         let err = parse_duration("1.5d").unwrap_err();